@@ -0,0 +1,44 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS stations_system_idx;
+DROP TABLE IF EXISTS stations;
+
+DROP VIEW IF EXISTS observatory_path;
+CREATE VIEW IF NOT EXISTS observatory_path AS
+SELECT
+	S0.system_id AS s0_id,
+	S0.system_name AS s0_name,
+	S1.system_id AS s1_id,
+	S1.system_name AS s1_name,
+	CASE WHEN S1.observatory IS NULL THEN 0 ELSE 1 END AS s1_JO,
+	S2.system_id AS s2_id,
+	S2.system_name AS s2_name,
+	CASE WHEN S2.observatory IS NULL THEN 0 ELSE 1 END AS s2_JO,
+	S3.system_id AS s3_id,
+	S3.system_name AS s3_name,
+	CASE WHEN S3.observatory IS NULL THEN 0 ELSE 1 END AS s3_JO,
+	S4.system_id AS s4_id,
+	S4.system_name AS s4_name,
+	CASE WHEN S4.observatory IS NULL THEN 0 ELSE 1 END AS s4_JO,
+	S5.system_id AS s5_id,
+	S5.system_name AS s5_name,
+	CASE WHEN S5.observatory IS NULL THEN 0 ELSE 1 END AS s5_JO
+FROM named_systems S0
+JOIN stargates SG0 ON SG0.system_id = S0.system_id
+JOIN named_systems S1 ON SG0.dst_system_id = S1.system_id
+JOIN stargates SG1 ON SG1.system_id = S1.system_id
+JOIN named_systems S2 ON SG1.dst_system_id = S2.system_id AND S2.system_id != S0.system_id
+JOIN stargates SG2 ON SG2.system_id = S2.system_id
+JOIN named_systems S3 ON SG2.dst_system_id = S3.system_id AND S3.system_id NOT IN (S0.system_id, S1.system_id)
+JOIN stargates SG3 ON SG3.system_id = S3.system_id
+JOIN named_systems S4 ON SG3.dst_system_id = S4.system_id AND S4.system_id NOT IN (S0.system_id, S1.system_id, S2.system_id)
+JOIN stargates SG4 ON SG4.system_id = S4.system_id
+JOIN named_systems S5 ON SG4.dst_system_id = S5.system_id AND S5.system_id NOT IN (S0.system_id, S1.system_id, S2.system_id, S3.system_id)
+WHERE
+(
+	(S1.observatory IS NOT NULL AND S2.observatory IS NULL AND S3.observatory IS NULL AND S4.observatory IS NULL AND S5.observatory IS NULL) OR
+	(S1.observatory IS NULL AND S2.observatory IS NOT NULL AND S3.observatory IS NULL AND S4.observatory IS NULL AND S5.observatory IS NULL) OR
+	(S1.observatory IS NULL AND S2.observatory IS NULL AND S3.observatory IS NOT NULL AND S4.observatory IS NULL AND S5.observatory IS NULL) OR
+	(S1.observatory IS NULL AND S2.observatory IS NULL AND S3.observatory IS NULL AND S4.observatory IS NOT NULL AND S5.observatory IS NULL) OR
+	(S1.observatory IS NULL AND S2.observatory IS NULL AND S3.observatory IS NULL AND S4.observatory IS NULL AND S5.observatory IS NOT NULL)
+)
+ORDER BY S1_JO DESC, S2_JO DESC, S3_JO DESC, S4_JO DESC, S5_JO DESC;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS stations(
+    station_id INTEGER NOT NULL PRIMARY KEY ON CONFLICT IGNORE,
+    system_id INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS stations_system_idx ON stations(system_id);
+
+-- replaced by the nearest systems search over the stargates graph
+DROP VIEW IF EXISTS observatory_path;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS alerts_time_idx;
+DROP TABLE IF EXISTS alerts;
+DROP TABLE IF EXISTS rules;
+DROP TABLE IF EXISTS watched;
+DROP TABLE IF EXISTS watchlists;
@@ -0,0 +1,30 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS watchlists(
+    watchlist_id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE ON CONFLICT IGNORE
+);
+
+CREATE TABLE IF NOT EXISTS watched(
+    watchlist_id INTEGER NOT NULL,
+    category TEXT NOT NULL,
+    object_id INTEGER NOT NULL,
+    PRIMARY KEY (watchlist_id, category, object_id) ON CONFLICT IGNORE
+);
+
+CREATE TABLE IF NOT EXISTS rules(
+    rule_id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    watchlist_id INTEGER,
+    system_id INTEGER,
+    jumps INTEGER,
+    capitals BOOLEAN NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS alerts(
+    alert_id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    rule_id INTEGER NOT NULL,
+    killmail_id INTEGER NOT NULL,
+    alert_time TIMESTAMP NOT NULL,
+    UNIQUE (rule_id, killmail_id) ON CONFLICT IGNORE
+);
+CREATE INDEX IF NOT EXISTS alerts_time_idx ON alerts(alert_time);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS prices_date_idx;
+DROP TABLE IF EXISTS prices;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS prices(
+    type_id INTEGER NOT NULL,
+    price_date DATE NOT NULL,
+    average REAL,
+    adjusted REAL,
+    buy REAL,
+    sell REAL,
+    PRIMARY KEY (type_id, price_date) ON CONFLICT REPLACE
+);
+CREATE INDEX IF NOT EXISTS prices_date_idx ON prices(price_date);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS order_events_type_idx;
+DROP INDEX IF EXISTS order_events_time_idx;
+DROP TABLE IF EXISTS order_events;
+DROP INDEX IF EXISTS market_orders_type_idx;
+DROP INDEX IF EXISTS market_orders_region_idx;
+DROP TABLE IF EXISTS market_orders;
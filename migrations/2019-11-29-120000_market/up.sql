@@ -0,0 +1,34 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS market_orders(
+    order_id BIGINT NOT NULL PRIMARY KEY ON CONFLICT REPLACE,
+    region_id INTEGER NOT NULL,
+    type_id INTEGER NOT NULL,
+    location_id BIGINT NOT NULL,
+    system_id INTEGER NOT NULL,
+    is_buy_order BOOLEAN NOT NULL,
+    price REAL NOT NULL,
+    volume_total INTEGER NOT NULL,
+    volume_remain INTEGER NOT NULL,
+    min_volume INTEGER NOT NULL,
+    duration INTEGER NOT NULL,
+    issued TIMESTAMP NOT NULL,
+    order_range TEXT NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS market_orders_region_idx ON market_orders(region_id);
+CREATE INDEX IF NOT EXISTS market_orders_type_idx ON market_orders(type_id);
+
+CREATE TABLE IF NOT EXISTS order_events(
+    event_id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    order_id BIGINT NOT NULL,
+    region_id INTEGER NOT NULL,
+    type_id INTEGER NOT NULL,
+    is_buy_order BOOLEAN NOT NULL,
+    event TEXT NOT NULL,
+    price REAL NOT NULL,
+    traded INTEGER NOT NULL,
+    event_time TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS order_events_time_idx ON order_events(event_time);
+CREATE INDEX IF NOT EXISTS order_events_type_idx ON order_events(type_id);
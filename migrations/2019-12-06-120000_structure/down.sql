@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE victims DROP COLUMN position_z;
+ALTER TABLE victims DROP COLUMN position_y;
+ALTER TABLE victims DROP COLUMN position_x;
+ALTER TABLE items DROP COLUMN container;
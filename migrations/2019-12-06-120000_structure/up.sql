@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE items ADD COLUMN container INTEGER NULL;
+ALTER TABLE victims ADD COLUMN position_x DOUBLE NULL;
+ALTER TABLE victims ADD COLUMN position_y DOUBLE NULL;
+ALTER TABLE victims ADD COLUMN position_z DOUBLE NULL;
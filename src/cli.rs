@@ -0,0 +1,133 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use chrono::NaiveDate;
+use lib::config::Config;
+
+fn is_date(value: String) -> Result<(), String> {
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d").map(|_| ()).map_err(|_| format!("'{}' is not YYYY-MM-DD", value))
+}
+
+fn is_number(value: String) -> Result<(), String> {
+    value.parse::<u32>().map(|_| ()).map_err(|_| format!("'{}' is not a number", value))
+}
+
+pub fn get_date(matches: &ArgMatches, name: &str) -> Option<NaiveDate> {
+    matches.value_of(name).and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+pub fn get_number(matches: &ArgMatches, name: &str) -> Option<u32> {
+    matches.value_of(name).and_then(|value| value.parse().ok())
+}
+
+/// The serve command runs when no command is given
+pub fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("zkb")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("zKillboard monitor, navigator and market tools")
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(Arg::with_name("config")
+            .long("config")
+            .value_name("FILE")
+            .global(true)
+            .help("TOML config file, zkb.toml or ZKB_CONFIG by default"))
+        .arg(Arg::with_name("set")
+            .long("set")
+            .value_name("KEY=VALUE")
+            .multiple(true)
+            .number_of_values(1)
+            .global(true)
+            .help("Overrides the setting, e.g. server.timeout=30"))
+        .subcommand(SubCommand::with_name("serve")
+            .about("Runs the web server with the monitor and the background services"))
+        .subcommand(SubCommand::with_name("backfill")
+            .about("Loads the killmails of the days listed by the zKillboard history")
+            .arg(Arg::with_name("from").required(true).validator(is_date).help("The first day, YYYY-MM-DD"))
+            .arg(Arg::with_name("to").validator(is_date).help("The last day, the first one by default"))
+            .arg(Arg::with_name("workers").long("workers").value_name("N").validator(is_number).help("Parallel ESI requests")))
+        .subcommand(SubCommand::with_name("import-sde")
+            .about("Imports the item type names of the invTypes.csv dump")
+            .arg(Arg::with_name("file").required(true)))
+        .subcommand(SubCommand::with_name("export")
+            .about("Exports the killmails of the period")
+            .arg(Arg::with_name("format").required(true).possible_values(lib::export::FORMATS))
+            .arg(Arg::with_name("start").required(true).validator(is_date).help("YYYY-MM-DD"))
+            .arg(Arg::with_name("end").required(true).validator(is_date).help("YYYY-MM-DD, inclusive"))
+            .arg(Arg::with_name("class").requires("id").help("character, corporation, alliance, faction, system, region..."))
+            .arg(Arg::with_name("id"))
+            .arg(Arg::with_name("output").long("output").value_name("FILE").help("Standard output by default")))
+        .subcommand(SubCommand::with_name("resolve")
+            .about("Resolves the ids or the names with ESI and stores the objects")
+            .arg(Arg::with_name("target").required(true).multiple(true)))
+        .subcommand(SubCommand::with_name("market")
+            .about("Takes the market snapshot of the regions")
+            .arg(Arg::with_name("region").multiple(true).validator(is_number).help("The configured regions by default")))
+        .subcommand(SubCommand::with_name("db")
+            .about("Database maintenance")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("check")
+                .about("Checks the integrity and the pending migrations"))
+            .subcommand(SubCommand::with_name("stations")
+                .about("Loads the stations of the systems saved without them"))
+            .subcommand(SubCommand::with_name("prune")
+                .about("Deletes the killmails older than the retention")
+                .arg(Arg::with_name("weeks").long("weeks").value_name("N").validator(is_number).help("The configured retention by default"))
+                .arg(Arg::with_name("dry-run").long("dry-run").help("Counts the killmails only"))
+                .arg(Arg::with_name("vacuum").long("vacuum").help("Shrinks the database file afterwards"))))
+}
+
+/// Values of the global option given before or after the commands
+fn get_global<'a>(matches: &'a ArgMatches, name: &str) -> Vec<&'a str> {
+    let mut values: Vec<&str> = matches.values_of(name).into_iter().flatten().collect();
+    if let (_, Some(command)) = matches.subcommand() {
+        for value in get_global(command, name) {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+    }
+    values
+}
+
+/// The file is overridden by the environment variables and those by the --set options
+pub fn configure(matches: &ArgMatches) -> Result<Config, Vec<String>> {
+    let path = get_global(matches, "config").pop().map(String::from).or(std::env::var("ZKB_CONFIG").ok());
+    let mut config = Config::load(path.as_deref()).map_err(|e| vec![e])?;
+    config.apply_env(&|name| std::env::var(name).ok()).map_err(|e| vec![e])?;
+    for setting in get_global(matches, "set") {
+        let mut parts = setting.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => config.set(key.trim(), value).map_err(|e| vec![e])?,
+            _ => return Err(vec![format!("--set {} has to be section.name=value", setting)]),
+        }
+    }
+    config.validate()?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() {
+        let matches = app().get_matches_from_safe(vec!["zkb", "--set", "server.timeout=30", "db", "prune", "--weeks", "10", "--dry-run"]).unwrap();
+        assert_eq!(vec!["server.timeout=30"], get_global(&matches, "set"));
+        let (name, db) = matches.subcommand();
+        assert_eq!("db", name);
+        let (name, prune) = db.unwrap().subcommand();
+        assert_eq!("prune", name);
+        assert_eq!(Some(10), get_number(prune.unwrap(), "weeks"));
+        assert!(prune.unwrap().is_present("dry-run"));
+
+        let matches = app().get_matches_from_safe(vec!["zkb", "export", "csv", "2019-11-01", "2019-11-30", "--config", "zkb.toml"]).unwrap();
+        let export = matches.subcommand_matches("export").unwrap();
+        assert_eq!(Some(NaiveDate::from_ymd(2019, 11, 30)), get_date(export, "end"));
+        assert_eq!(vec!["zkb.toml"], get_global(&matches, "config"));
+
+        let matches = app().get_matches_from_safe(vec!["zkb", "db", "stations"]).unwrap();
+        assert_eq!(Some("stations"), matches.subcommand_matches("db").unwrap().subcommand_name());
+
+        assert!(app().get_matches_from_safe(vec!["zkb", "backfill", "2019-13-01"]).is_err());
+        assert!(app().get_matches_from_safe(vec!["zkb", "export", "xml", "2019-11-01", "2019-11-30"]).is_err());
+        assert_eq!("", app().get_matches_from_safe(vec!["zkb"]).unwrap().subcommand_name().unwrap_or_default());
+    }
+}
@@ -0,0 +1,355 @@
+use actix_web::web;
+use clap::ArgMatches;
+use crossbeam_utils::thread::scope;
+
+use lib::api;
+use lib::config::Config;
+use lib::models::{self, DB, Connection};
+use lib::models::maintenance;
+use lib::services::*;
+
+use crate::cli;
+
+/// Runs the web server, the monitor, the resolvers and the enabled background services
+pub fn serve(config: &Config) -> i32 {
+    info!("Bind {} interface", config.server.interface);
+    let conn = DB::connect(&config.database.url);
+    info!("Connection established");
+    if let Err(e) = crate::embedded_migrations::run(&conn) {
+        eprintln!("Database migration failed: {}", e);
+        return 1;
+    }
+    info!("Database migration complete");
+    let api_id = config.services.redisq_id.clone().unwrap_or(dns_lookup::get_hostname().unwrap_or(String::from("seb_odessa")));
+    info!("ZKB API ID: {}", api_id);
+    info!("Minimal allowed date: {}", config.get_allowed());
+
+    let context = web::Data::new(AppContext::new(config, &api_id));
+    context.health.set_migrated();
+    info!("Application context constructed");
+    scope(|scope| {
+        scope.builder()
+             .name("Web Server".to_string())
+             .spawn(|_| server::run(context.clone()))
+             .expect("Failed to create API Server");
+        if config.features.monitor {
+            scope.builder()
+                 .name("ZKB Monitor".to_string())
+                 .spawn(|_| monitor::run(context.clone()))
+                 .expect("Failed to create Monitor");
+        }
+        for id in 0..config.services.resolvers {
+            let context = context.clone();
+            scope.builder()
+                 .name(format!("API Name Resolver {}", id))
+                 .spawn(move |_| resolver::run(context, id))
+                 .expect("Failed to create Name Resolver");
+        }
+        // The killfeed stream goes through the notifier, the feature switches the webhooks only
+        scope.builder()
+             .name("Webhook Notifier".to_string())
+             .spawn(|_| notifier::run(context.clone()))
+             .expect("Failed to create Notifier");
+        if config.features.pricer {
+            scope.builder()
+                 .name("Price Snapshots".to_string())
+                 .spawn(|_| pricer::run(context.clone()))
+                 .expect("Failed to create Pricer");
+        }
+        if config.features.ingestor {
+            scope.builder()
+                 .name("Market Ingestor".to_string())
+                 .spawn(|_| ingestor::run(context.clone()))
+                 .expect("Failed to create Ingestor");
+        }
+        scope.builder()
+             .name("DB provider".to_string())
+             .spawn(|_| database::run(conn, context.clone()))
+             .expect("Failed to create database");
+    })
+    .unwrap();
+    info!("Application finished");
+    0
+}
+
+/// The commands other than serve do not run the migrations, the database has to be served once
+fn connect(config: &Config) -> Connection {
+    DB::connect(&config.database.url)
+}
+
+/// zkb backfill <from> [<to>] [--workers N]
+pub fn backfill(matches: &ArgMatches, config: &Config) -> i32 {
+    use lib::backfill::{self, Summary, WORKERS};
+    let from = cli::get_date(matches, "from").expect("the date is validated");
+    let to = cli::get_date(matches, "to").unwrap_or(from);
+    let workers = cli::get_number(matches, "workers").map(|workers| workers as usize).unwrap_or(WORKERS);
+    let conn = connect(config);
+    let mut total = Summary::default();
+    let mut date = from;
+    while date <= to {
+        match backfill::load_day(&conn, &date, workers) {
+            Ok(summary) => {
+                println!("{}: {} listed, {} stored before, {} saved, {} failed",
+                    date, summary.listed, summary.existing, summary.saved, summary.failed);
+                total.add(&summary);
+            },
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        }
+        date = date.succ();
+    }
+    println!("Total: {} listed, {} stored before, {} saved, {} failed", total.listed, total.existing, total.saved, total.failed);
+    if total.failed > 0 { 1 } else { 0 }
+}
+
+/// zkb import-sde <invTypes.csv>
+pub fn import_sde(matches: &ArgMatches, config: &Config) -> i32 {
+    use lib::sde;
+    let path = matches.value_of("file").expect("the file is required");
+    let types = match std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|text| sde::parse_types(&text)) {
+        Ok(types) => types,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return 1;
+        }
+    };
+    match sde::import_types(&connect(config), &types) {
+        Ok(count) => {
+            println!("Imported {} of {} item types", count, types.len());
+            0
+        },
+        Err(e) => {
+            eprintln!("Import failed: {}", e);
+            1
+        }
+    }
+}
+
+/// zkb export <csv|jsonl|parquet> <start> <end> [<class> <id>] [--output <file>]
+pub fn export(matches: &ArgMatches, config: &Config) -> i32 {
+    use lib::export::{self, Format, Filter};
+    use lib::reports::statistics::get_entity;
+
+    let format = matches.value_of("format").and_then(Format::from_name).expect("the format is validated");
+    let start = cli::get_date(matches, "start").expect("the date is validated");
+    let end = cli::get_date(matches, "end").expect("the date is validated");
+    let entity = match (matches.value_of("class"), matches.value_of("id")) {
+        (Some(class), Some(id)) => match id.parse::<i32>().ok().and_then(|id| get_entity(class, id)) {
+            Some(entity) => Some(entity),
+            None => {
+                eprintln!("Unknown {} {}", class, id);
+                return 2;
+            }
+        },
+        _ => None,
+    };
+    let filter = Filter { entity, start: start.and_hms(0, 0, 0), end: end.succ().and_hms(0, 0, 0) };
+    match export::run(&connect(config), format, &filter, matches.value_of("output").map(String::from)) {
+        Ok(count) => {
+            info!("exported {} killmails", count);
+            0
+        },
+        Err(e) => {
+            eprintln!("Export failed: {}", e);
+            1
+        }
+    }
+}
+
+/// zkb resolve <id|name>...
+pub fn resolve(matches: &ArgMatches, config: &Config) -> i32 {
+    use api::names::Names;
+    use api::object::Object;
+    let conn = connect(config);
+    let mut ids = Vec::new();
+    for target in matches.values_of("target").into_iter().flatten() {
+        match target.parse::<i32>() {
+            Ok(id) => ids.push(id),
+            Err(_) => match Names::new(&target.to_string()).map(|names| names.get_ids()) {
+                Some(ref found) if !found.is_empty() => ids.extend(found),
+                _ => eprintln!("'{}' is not known to ESI", target),
+            }
+        }
+    }
+    let mut failed = 0;
+    for id in &ids {
+        match Object::new(id) {
+            Some(object) => match models::ObjectsApi::save(&conn, &object) {
+                Ok(_) => println!("{}\t{}\t{}", object.id, object.category, object.name),
+                Err(e) => {
+                    eprintln!("was not able to save {}: {}", id, e);
+                    failed += 1;
+                }
+            },
+            None => {
+                eprintln!("{} is not known to ESI", id);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 || ids.is_empty() { 1 } else { 0 }
+}
+
+/// zkb market [<region>...]
+pub fn market(matches: &ArgMatches, config: &Config) -> i32 {
+    use lib::market::ingest;
+    let regions: Vec<i32> = match matches.values_of("region") {
+        Some(regions) => regions.filter_map(|id| id.parse().ok()).collect(),
+        None => config.market.regions.clone(),
+    };
+    if regions.is_empty() {
+        eprintln!("No regions were given or configured in market.regions");
+        return 2;
+    }
+    let conn = connect(config);
+    let mut failed = 0;
+    for region_id in &regions {
+        let orders = match api::Order::load(region_id, api::OrderType::ALL, None) {
+            Some(ref orders) if orders.is_empty() => {
+                eprintln!("{}: ESI returned no orders", region_id);
+                failed += 1;
+                continue;
+            },
+            Some(orders) => orders,
+            None => {
+                eprintln!("{}: not all pages were loaded, the snapshot is skipped", region_id);
+                failed += 1;
+                continue;
+            }
+        };
+        let now = chrono::Utc::now().naive_utc();
+        match ingest::save(&conn, region_id, &orders, &now) {
+            Ok(summary) => println!("{}: {} orders, {} new, {} updated, {} filled, {} cancelled",
+                region_id, summary.orders, summary.new, summary.updated, summary.filled, summary.cancelled),
+            Err(e) => {
+                eprintln!("{}: {}", region_id, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 { 1 } else { 0 }
+}
+
+/// zkb db check
+fn db_check(config: &Config) -> i32 {
+    let conn = connect(config);
+    let mut problems = 0;
+    match maintenance::integrity(&conn) {
+        Ok(ref errors) if errors.is_empty() => println!("Integrity: ok"),
+        Ok(errors) => {
+            for error in &errors {
+                println!("Integrity: {}", error);
+            }
+            problems += errors.len();
+        },
+        Err(e) => {
+            eprintln!("Integrity check failed: {}", e);
+            problems += 1;
+        }
+    }
+    match maintenance::pending(&conn) {
+        Ok(ref pending) if pending.is_empty() => println!("Migrations: all applied"),
+        Ok(pending) => {
+            println!("Migrations: {} pending, run zkb serve to apply", pending.join(", "));
+            problems += 1;
+        },
+        Err(e) => {
+            eprintln!("Migrations check failed: {}", e);
+            problems += 1;
+        }
+    }
+    match maintenance::stats(&conn) {
+        Ok(stats) => {
+            let format = |time: &Option<models::DateTime>| time.map(|time| time.to_string()).unwrap_or(String::from("-"));
+            println!("Killmails: {} from {} to {}", stats.killmails, format(&stats.oldest), format(&stats.latest));
+            println!("Objects: {}", stats.objects);
+        },
+        Err(e) => {
+            eprintln!("Statistics failed: {}", e);
+            problems += 1;
+        }
+    }
+    if problems > 0 { 1 } else { 0 }
+}
+
+/// zkb db prune [--weeks N] [--dry-run] [--vacuum]
+fn db_prune(matches: &ArgMatches, config: &Config) -> i32 {
+    let weeks = cli::get_number(matches, "weeks").map(|weeks| weeks as i64).unwrap_or(config.database.retention_weeks);
+    let before = (chrono::Utc::now() - chrono::Duration::weeks(weeks)).naive_utc();
+    let conn = connect(config);
+    if matches.is_present("dry-run") {
+        return match maintenance::count_before(&conn, &before) {
+            Ok(count) => {
+                println!("{} killmails are older than {}", count, before);
+                0
+            },
+            Err(e) => {
+                eprintln!("Count failed: {}", e);
+                1
+            }
+        };
+    }
+    match maintenance::prune(&conn, &before) {
+        Ok(pruned) => println!("Deleted before {}: {} killmails, {} victims, {} attackers, {} items, {} alerts, {} market events",
+            before, pruned.killmails, pruned.victims, pruned.attackers, pruned.items, pruned.alerts, pruned.order_events),
+        Err(e) => {
+            eprintln!("Prune failed: {}", e);
+            return 1;
+        }
+    }
+    if matches.is_present("vacuum") {
+        if let Err(e) = maintenance::vacuum(&conn) {
+            eprintln!("Vacuum failed: {}", e);
+            return 1;
+        }
+    }
+    0
+}
+
+/// zkb db stations
+fn db_stations(config: &Config) -> i32 {
+    use api::system::System;
+    use models::station::Station;
+    let conn = connect(config);
+    let ids = match Station::load_unknown_system_ids(&conn) {
+        Ok(ids) => ids,
+        Err(e) => {
+            eprintln!("was not able to load systems: {}", e);
+            return 1;
+        }
+    };
+    let mut saved = 0;
+    let mut failed = 0;
+    for id in &ids {
+        match System::new(id) {
+            Some(system) => match Station::save(&conn, &system) {
+                Ok(count) => saved += count,
+                Err(e) => {
+                    eprintln!("was not able to save stations of system {}: {}", id, e);
+                    failed += 1;
+                }
+            },
+            None => {
+                eprintln!("{} is not known to ESI", id);
+                failed += 1;
+            }
+        }
+    }
+    println!("Stations: {} systems checked, {} stations saved, {} failed", ids.len(), saved, failed);
+    if failed > 0 { 1 } else { 0 }
+}
+
+/// zkb db <check|stations|prune>
+pub fn db(matches: &ArgMatches, config: &Config) -> i32 {
+    match matches.subcommand() {
+        ("check", _) => db_check(config),
+        ("stations", _) => db_stations(config),
+        ("prune", Some(prune)) => db_prune(prune, config),
+        _ => {
+            eprintln!("{}", matches.usage());
+            2
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::api;
+use crate::provider;
+use crate::models::Integer;
+use crate::models::rule::Rule;
+use crate::models::watchlist::Watched;
+use crate::navigation::Graph;
+
+use std::collections::{HashMap, HashSet};
+
+/// Titans, dreadnoughts, carriers, supercarriers, capital industrials, force auxiliaries and lancers
+const CAPITAL_GROUPS: [Integer; 7] = [30, 485, 547, 659, 883, 1538, 4594];
+
+/// Watchlist categories
+pub const CATEGORIES: [&str; 5] = ["character", "corporation", "alliance", "system", "ship"];
+
+/// Loads the capital groups from ESI into the provider cache, true when all of them are there
+pub fn load_capitals() -> bool {
+    CAPITAL_GROUPS.iter().all(|group_id| provider::get_group(group_id, &|id| api::group::Group::new(id)).is_some())
+}
+
+/// Looks the victim hull up in the cached groups only, the database thread must not wait for ESI
+pub fn is_capital(ship_id: &Integer) -> bool {
+    CAPITAL_GROUPS.iter().any(|group_id| {
+        provider::get_group(group_id, &|_| None)
+            .map(|group| group.types.contains(ship_id))
+            .unwrap_or(false)
+    })
+}
+
+/// Everything on the killmail a watchlist may refer to
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Event {
+    pub killmail_id: Integer,
+    pub system_id: Integer,
+    pub ship_id: Integer,
+    pub objects: HashSet<(&'static str, Integer)>,
+}
+impl From<&api::Killmail> for Event {
+    fn from(killmail: &api::Killmail) -> Self {
+        let mut objects = HashSet::new();
+        objects.insert(("system", killmail.solar_system_id));
+        objects.insert(("ship", killmail.victim.ship_type_id));
+        let victim = &killmail.victim;
+        for (category, id) in [("character", victim.character_id), ("corporation", victim.corporation_id), ("alliance", victim.alliance_id)] {
+            if let Some(id) = id {
+                objects.insert((category, id));
+            }
+        }
+        for attacker in &killmail.attackers {
+            for (category, id) in [
+                ("character", attacker.character_id),
+                ("corporation", attacker.corporation_id),
+                ("alliance", attacker.alliance_id),
+                ("ship", attacker.ship_type_id)]
+            {
+                if let Some(id) = id {
+                    objects.insert((category, id));
+                }
+            }
+        }
+        Self {
+            killmail_id: killmail.killmail_id,
+            system_id: killmail.solar_system_id,
+            ship_id: killmail.victim.ship_type_id,
+            objects,
+        }
+    }
+}
+
+/// Watched objects grouped by the watchlist
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Watchlists {
+    lists: HashMap<Integer, HashSet<(String, Integer)>>,
+}
+impl Watchlists {
+    pub fn new(watched: Vec<Watched>) -> Self {
+        let mut lists = HashMap::new();
+        for item in watched {
+            lists.entry(item.watchlist_id).or_insert(HashSet::new()).insert((item.category, item.object_id));
+        }
+        Self { lists }
+    }
+
+    pub fn contains(&self, watchlist_id: &Integer, event: &Event) -> bool {
+        self.lists.get(watchlist_id)
+            .map(|list| event.objects.iter().any(|(category, id)| list.contains(&(String::from(*category), *id))))
+            .unwrap_or(false)
+    }
+}
+
+/// The rule without any condition matches nothing
+pub fn matches<C>(rule: &Rule, event: &Event, watchlists: &Watchlists, graph: &Graph, is_capital: &C) -> bool
+    where C: Fn(&Integer) -> bool
+{
+    if rule.watchlist_id.is_none() && rule.system_id.is_none() && !rule.capitals {
+        return false;
+    }
+    if let Some(watchlist_id) = rule.watchlist_id {
+        if !watchlists.contains(&watchlist_id, event) {
+            return false;
+        }
+    }
+    if let Some(system_id) = rule.system_id {
+        let jumps = rule.jumps.unwrap_or_default().max(0) as u32;
+        if graph.get_jumps(&system_id, &event.system_id, jumps).is_none() {
+            return false;
+        }
+    }
+    !rule.capitals || is_capital(&event.ship_id)
+}
+
+/// Ids of the matched rules
+pub fn evaluate<C>(rules: &[Rule], event: &Event, watchlists: &Watchlists, graph: &Graph, is_capital: &C) -> Vec<Integer>
+    where C: Fn(&Integer) -> bool
+{
+    rules.iter()
+        .filter(|rule| matches(rule, event, watchlists, graph, is_capital))
+        .map(|rule| rule.rule_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAGLFAR: Integer = 19722;
+
+    fn rule(rule_id: Integer, watchlist_id: Option<Integer>, system_id: Option<Integer>, jumps: Option<Integer>, capitals: bool) -> Rule {
+        Rule {
+            rule_id,
+            name: format!("rule {}", rule_id),
+            watchlist_id,
+            system_id,
+            jumps,
+            capitals,
+        }
+    }
+
+    fn event(system_id: Integer, ship_id: Integer, character_id: Integer) -> Event {
+        let mut objects = HashSet::new();
+        objects.insert(("system", system_id));
+        objects.insert(("ship", ship_id));
+        objects.insert(("character", character_id));
+        Event { killmail_id: 1, system_id, ship_id, objects }
+    }
+
+    // 1 - 2 - 3 - 4
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        for id in 1..5 {
+            graph.add_system(id, 0.5);
+        }
+        for (from, to) in [(1, 2), (2, 3), (3, 4)] {
+            graph.add_link(from, to);
+            graph.add_link(to, from);
+        }
+        graph
+    }
+
+    fn watchlists() -> Watchlists {
+        Watchlists::new(vec![
+            Watched { watchlist_id: 1, category: String::from("character"), object_id: 42 },
+            Watched { watchlist_id: 2, category: String::from("system"), object_id: 4 },
+        ])
+    }
+
+    fn is_capital(ship_id: &Integer) -> bool {
+        *ship_id == NAGLFAR
+    }
+
+    #[test]
+    fn watched_pilot() {
+        let rules = vec![rule(1, Some(1), None, None, false), rule(2, Some(2), None, None, false)];
+        assert_eq!(vec![1], evaluate(&rules, &event(3, 587, 42), &watchlists(), &graph(), &is_capital));
+        assert_eq!(vec![2], evaluate(&rules, &event(4, 587, 7), &watchlists(), &graph(), &is_capital));
+    }
+
+    #[test]
+    fn capital_nearby() {
+        let rules = vec![rule(1, None, Some(1), Some(2), true)];
+        assert_eq!(vec![1], evaluate(&rules, &event(3, NAGLFAR, 7), &watchlists(), &graph(), &is_capital));
+        assert!(evaluate(&rules, &event(4, NAGLFAR, 7), &watchlists(), &graph(), &is_capital).is_empty());
+        assert!(evaluate(&rules, &event(2, 587, 7), &watchlists(), &graph(), &is_capital).is_empty());
+    }
+
+    #[test]
+    fn empty_rule() {
+        let rules = vec![rule(1, None, None, None, false)];
+        assert!(evaluate(&rules, &event(1, 587, 42), &watchlists(), &graph(), &is_capital).is_empty());
+    }
+}
@@ -41,7 +41,7 @@ impl Attacker {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde::export::Result;
+    use std::result::Result;
     use serde_json::json;
 
     #[test]
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// The fixtures are read from and written to the directory relative to the crate root
+pub const FIXTURES_DIR: &str = "responses/fixtures";
+
+/// Where the gateway takes the responses from
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mode {
+    /// The network only
+    Live,
+    /// The network, the each response is written to the fixtures
+    Record,
+    /// The fixtures only, the missing one panics to be recorded
+    Replay,
+    /// The fixture when it is recorded, the network otherwise
+    Passthrough,
+}
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "live" => Some(Mode::Live),
+            "record" => Some(Mode::Record),
+            "replay" => Some(Mode::Replay),
+            "passthrough" => Some(Mode::Passthrough),
+            _ => None
+        }
+    }
+}
+
+/// The tests take the recorded fixtures only unless ZKB_FIXTURES says otherwise, the application goes live
+fn get_default() -> (Mode, String) {
+    let default = if cfg!(test) { Mode::Replay } else { Mode::Live };
+    let mode = std::env::var("ZKB_FIXTURES").ok().and_then(|name| Mode::from_name(&name)).unwrap_or(default);
+    let dir = std::env::var("ZKB_FIXTURES_DIR").unwrap_or(String::from(FIXTURES_DIR));
+    (mode, dir)
+}
+
+lazy_static! {
+    static ref SETTINGS: RwLock<(Mode, String)> = RwLock::new(get_default());
+}
+
+pub fn set_mode(mode: Mode, dir: &str) {
+    if let Ok(mut settings) = SETTINGS.write() {
+        *settings = (mode, dir.to_string());
+    }
+}
+
+pub fn get_mode() -> (Mode, String) {
+    SETTINGS.read().map(|settings| settings.clone()).unwrap_or_else(|_| get_default())
+}
+
+/// The request with the response it got
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Fixture {
+    pub method: String,
+    pub url: String,
+    pub request: Option<String>,
+    pub status: u32,
+    /// X-Pages of the paged ESI responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<u32>,
+    pub response: String,
+}
+
+/// The status, the page count and the body of the response
+#[derive(Debug, PartialEq, Clone)]
+pub struct Response {
+    pub status: u32,
+    pub pages: Option<u32>,
+    pub content: Vec<u8>,
+}
+
+/// FNV-1a, the file names have to be the same for the every build
+fn fnv(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// The readable part of the url with the hash of the whole request
+pub fn get_name(method: &str, url: &str, request: &Option<String>) -> String {
+    let path = url.splitn(2, "://").last().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+    let mut slug: String = path.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    slug.truncate(100);
+    let key = format!("{} {} {}", method, url, request.clone().unwrap_or_default());
+    format!("{}_{}_{:016x}.json", method.to_lowercase(), slug.trim_matches('_'), fnv(&key))
+}
+
+fn get_path(dir: &str, method: &str, url: &str, request: &Option<String>) -> PathBuf {
+    PathBuf::from(dir).join(get_name(method, url, request))
+}
+
+pub fn load(dir: &str, method: &str, url: &str, request: &Option<String>) -> Result<Fixture, String> {
+    let path = get_path(dir, method, url, request);
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("no fixture {} for {} {}, record it with ZKB_FIXTURES=record: {}", path.display(), method, url, e))?;
+    serde_json::from_str(&json).map_err(|e| format!("broken fixture {}: {}", path.display(), e))
+}
+
+pub fn save(dir: &str, fixture: &Fixture) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = get_path(dir, &fixture.method, &fixture.url, &fixture.request);
+    let json = serde_json::to_string_pretty(fixture).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("was not able to write {}: {}", path.display(), e))
+}
+
+/// The local services are reachable offline, only the remote APIs are recorded
+fn is_local(url: &str) -> bool {
+    let host = url.splitn(2, "://").last().and_then(|rest| rest.split(['/', ':']).next()).unwrap_or_default();
+    host == "localhost" || host == "127.0.0.1"
+}
+
+/// Takes the response by the mode, the live one is called for the network
+pub fn exchange<L>(method: &str, url: &str, request: Option<&str>, live: L) -> Result<Response, String>
+    where L: FnOnce() -> Result<Response, String>
+{
+    let (mode, dir) = get_mode();
+    let mode = if is_local(url) { Mode::Live } else { mode };
+    let request = request.map(String::from);
+    let replay = |fixture: Fixture| Response {
+        status: fixture.status,
+        pages: fixture.pages,
+        content: fixture.response.into_bytes(),
+    };
+    match mode {
+        Mode::Live => live(),
+        Mode::Replay => match load(&dir, method, url, &request) {
+            Ok(fixture) => Ok(replay(fixture)),
+            Err(e) => panic!("{}", e),
+        },
+        Mode::Passthrough => match load(&dir, method, url, &request) {
+            Ok(fixture) => Ok(replay(fixture)),
+            Err(_) => live(),
+        },
+        Mode::Record => {
+            let response = live()?;
+            let fixture = Fixture {
+                method: method.to_string(),
+                url: url.to_string(),
+                request,
+                status: response.status,
+                pages: response.pages,
+                response: String::from_utf8_lossy(&response.content).to_string(),
+            };
+            if let Err(e) = save(&dir, &fixture) {
+                warn!("{}", e);
+            }
+            Ok(response)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        let url = "https://esi.evetech.net/latest/universe/names/?datasource=tranquility";
+        let name = get_name("POST", url, &Some(String::from("[34]")));
+        assert!(name.starts_with("post_esi_evetech_net_latest_universe_names_"));
+        assert!(name.ends_with(".json"));
+        assert_eq!(name, get_name("POST", url, &Some(String::from("[34]"))));
+        assert_ne!(name, get_name("POST", url, &Some(String::from("[35]"))));
+        assert!(is_local("http://127.0.0.1:8080/hook"));
+        assert!(!is_local(url));
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = std::env::temp_dir().join(format!("zkb-fixtures-{}", crate::create_id()));
+        let dir = dir.to_str().unwrap();
+        let fixture = Fixture {
+            method: String::from("GET"),
+            url: String::from("https://zkillboard.com/api/history/20190101.json"),
+            request: None,
+            status: 200,
+            pages: None,
+            response: String::from(r#"{"74351681":"2627f994d452c5d87d1eb35b9978e8f81e7e9d31"}"#),
+        };
+        assert!(load(dir, "GET", &fixture.url, &None).unwrap_err().contains("ZKB_FIXTURES=record"));
+        save(dir, &fixture).unwrap();
+        assert_eq!(fixture, load(dir, "GET", &fixture.url, &None).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+        assert_eq!(Some(Mode::Replay), Mode::from_name("replay"));
+        assert_eq!(Some(Mode::Passthrough), Mode::from_name("passthrough"));
+    }
+}
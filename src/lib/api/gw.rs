@@ -1,8 +1,11 @@
-use curl::easy::Easy;
+use curl::easy::{Easy, List};
 use curl::Error;
 use super::Killmail;
 use super::zkb::Package;
+use super::fixtures::{self, Response};
 use std::convert::TryFrom;
+use std::time::Duration;
+use std::sync::RwLock;
 
 //https://esi.evetech.net/latest/swagger.json
 //https://esi.evetech.net/latest/characters/2114350216/?datasource=tranquility
@@ -13,24 +16,51 @@ pub const EVE_API: &str = "https://esi.evetech.net/latest";
 pub const EVE_SRV: &str = "?datasource=tranquility";
 pub const ZKB_API: &str = "https://zkillboard.com/api";
 
-fn get(url: &str) -> Result<Vec<u8>, Error> {
+lazy_static! {
+    static ref USER_AGENT: RwLock<String> = RwLock::new(String::from(crate::config::USER_AGENT));
+}
+
+/// ESI asks to put the contact into the user agent
+pub fn set_user_agent(agent: &str) {
+    if let Ok(mut user_agent) = USER_AGENT.write() {
+        *user_agent = agent.to_string();
+    }
+}
+
+fn get_user_agent() -> String {
+    USER_AGENT.read().map(|agent| agent.clone()).unwrap_or(String::from(crate::config::USER_AGENT))
+}
+
+/// The page count of the paged ESI responses
+fn get_pages(header: &[u8]) -> Option<u32> {
+    let header = String::from_utf8_lossy(header);
+    let mut parts = header.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("x-pages") => value.trim().parse().ok(),
+        _ => None
+    }
+}
+
+fn get_live(url: &str) -> Result<Response, Error> {
     let mut easy = Easy::new();
     easy.accept_encoding("gzip")?;
-    easy.useragent("Easy API, Maintainer: seb@ukr.net")?;
+    easy.useragent(&get_user_agent())?;
     easy.url(url)?;
     let mut content = Vec::new();
+    let mut pages = None;
     {
         let mut transfer = easy.transfer();
+        transfer.header_function(|header| {pages = pages.or(get_pages(header)); true})?;
         transfer.write_function(|data| {content.extend_from_slice(data); Ok(data.len())})?;
         transfer.perform()?;
     }
-    return Ok(content);
+    Ok(Response { status: easy.response_code()?, pages, content })
 }
 
-fn post(url: &str, request: &str) -> Result<Vec<u8>, Error> {
+fn post_live(url: &str, request: &str) -> Result<Response, Error> {
     let mut easy = Easy::new();
     easy.accept_encoding("gzip")?;
-    easy.useragent("Easy API, Maintainer: seb@ukr.net")?;
+    easy.useragent(&get_user_agent())?;
     easy.url(url)?;
     easy.post_fields_copy(request.as_bytes())?;
     let mut content = Vec::new();
@@ -39,7 +69,79 @@ fn post(url: &str, request: &str) -> Result<Vec<u8>, Error> {
         transfer.write_function(|data| {content.extend_from_slice(data); Ok(data.len())})?;
         transfer.perform()?;
     }
-    return Ok(content);
+    Ok(Response { status: easy.response_code()?, pages: None, content })
+}
+
+/// Counts the network requests by the host and the status
+fn count(url: &str, result: Result<Response, Error>) -> Result<Response, String> {
+    let host = url.splitn(2, "://").last().and_then(|rest| rest.split('/').next()).unwrap_or_default();
+    let status = match &result {
+        Ok(response) => response.status.to_string(),
+        Err(_) => String::from("error"),
+    };
+    crate::metrics::increment("zkb_api_requests_total", &[("host", host), ("status", status.as_str())]);
+    result.map_err(|e| e.to_string())
+}
+
+/// The ESI and zKillboard reads go through the fixtures, see ZKB_FIXTURES
+fn get(url: &str) -> Result<Vec<u8>, String> {
+    fixtures::exchange("GET", url, None, || count(url, get_live(url)))
+        .map(|response| response.content)
+}
+
+fn post(url: &str, request: &str) -> Result<Vec<u8>, String> {
+    fixtures::exchange("POST", url, Some(request), || count(url, post_live(url, request)))
+        .map(|response| response.content)
+}
+
+/// The slow webhook is retried later instead of holding the notifier
+const POST_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn post_json_live(url: &str, body: &str) -> Result<Response, Error> {
+    let mut easy = Easy::new();
+    easy.useragent(&get_user_agent())?;
+    easy.url(url)?;
+    let mut headers = List::new();
+    headers.append("Content-Type: application/json")?;
+    headers.append("Expect:")?;
+    easy.http_headers(headers)?;
+    easy.post_fields_copy(body.as_bytes())?;
+    easy.timeout(POST_TIMEOUT)?;
+    let mut content = Vec::new();
+    {
+        let mut transfer = easy.transfer();
+        transfer.write_function(|data| {content.extend_from_slice(data); Ok(data.len())})?;
+        transfer.perform()?;
+    }
+    Ok(Response { status: easy.response_code()?, pages: None, content })
+}
+
+/// Posts the JSON body, returns the HTTP status code.
+/// The webhook deliveries go through the fixtures and the metrics as the reads do
+pub fn post_json(url: &str, body: &str) -> Result<u32, String> {
+    fixtures::exchange("POST", url, Some(body), || count(url, post_json_live(url, body)))
+        .map(|response| response.status)
+}
+
+/// Asks the ESI status, the probe has the short timeout and never goes through the fixtures
+pub fn is_esi_reachable() -> bool {
+    let url = format!("{}/status/{}", EVE_API, EVE_SRV);
+    let probe = || -> Result<u32, Error> {
+        let mut easy = Easy::new();
+        easy.useragent(&get_user_agent())?;
+        easy.url(&url)?;
+        easy.timeout(Duration::from_secs(5))?;
+        {
+            let mut transfer = easy.transfer();
+            transfer.write_function(|data| Ok(data.len()))?;
+            transfer.perform()?;
+        }
+        easy.response_code()
+    };
+    match probe() {
+        Ok(code) => code == 200,
+        Err(err) => { warn!("ESI is not reachable: {}", err); false }
+    }
 }
 
 pub fn eve_api(cmd: &str) -> Option<String> {
@@ -58,6 +160,17 @@ pub fn eve_api_ex(cmd: &str, flag: &str) -> Option<String> {
     }
 }
 
+/// The page of the paged ESI list with the page count, the failed request is the error
+pub fn eve_api_page(cmd: &str, flag: &str) -> Result<(String, u32), String> {
+    let url = format!("{}/{}/{}&{}", EVE_API, cmd, EVE_SRV, flag);
+    let response = fixtures::exchange("GET", &url, None, || count(&url, get_live(&url)))?;
+    let content = String::from_utf8_lossy(&response.content).to_string();
+    if response.status != 200 {
+        return Err(format!("{} answered {}: {}", url, response.status, content));
+    }
+    Ok((content, response.pages.unwrap_or(1)))
+}
+
 pub fn eve_api_post(cmd: &str, request: &str) -> Option<String> {
     let url = format!("{}/{}/{}", EVE_API, cmd, EVE_SRV);
     if let Some(response) = post(&url, &request).ok() {
@@ -0,0 +1,62 @@
+use crate::api::*;
+use crate::provider;
+
+use std::convert::TryFrom;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct ItemType {
+    pub type_id: IntRequired,
+    pub group_id: IntRequired,
+    pub name: StrRequired,
+    pub published: BoolRequired,
+    /// Assembled volume in m3
+    pub volume: FloatOptional,
+    /// Volume of the repackaged item, the ships and the containers are smaller packaged
+    pub packaged_volume: FloatOptional,
+}
+impl ItemType {
+    fn load(id: &i32) -> Option<Self> {
+        let response = gw::eve_api(&format!("universe/types/{}", id)).unwrap_or_default();
+        Self::try_from(response).ok()
+    }
+
+    pub fn new(id: &IntRequired) -> Option<Self> {
+        provider::get_type(id, &Self::load)
+    }
+
+    /// The volume of the item in the cargo hold
+    pub fn get_volume(&self) -> Option<f32> {
+        self.packaged_volume.or(self.volume)
+    }
+}
+impl TryFrom<String> for ItemType {
+    type Error = serde_json::Error;
+    fn try_from(json: String) -> Result<Self, Self::Error> {
+        serde_json::from_str(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_json() {
+        let json = r#"{"type_id": 587, "group_id": 25, "name": "Rifter", "published": true,
+            "volume": 27289.0, "packaged_volume": 2500.0, "capacity": 140.0}"#;
+        let item = ItemType::try_from(String::from(json)).unwrap();
+        assert_eq!(587, item.type_id);
+        assert_eq!(Some(2500.0), item.get_volume());
+    }
+
+    #[test]
+    fn from_api() {
+        let response = ItemType::new(&34);
+        assert!(response.is_some());
+        let item = response.unwrap();
+        assert_eq!(item.name, "Tritanium");
+        assert_eq!(item.get_volume(), Some(0.01));
+    }
+}
@@ -33,7 +33,7 @@ impl Killmail {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde::export::Result;
+    use std::result::Result;
     use serde_json::json;
     use std::collections::HashMap;
 
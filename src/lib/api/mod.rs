@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 pub mod gw;
+pub mod fixtures;
 pub mod killmail;
 mod item;
 mod victim;
@@ -19,6 +20,7 @@ pub mod names;
 pub mod alliance;
 pub mod corporation;
 pub mod group;
+pub mod item_type;
 pub mod stats;
 
 
@@ -35,6 +35,19 @@ impl Names {
         Self::load(name)
     }
 
+    /// Resolves up to 500 names with the single request
+    pub fn find(names: &Vec<String>) -> Option<Self> {
+        let query = serde_json::to_string(names).ok()?;
+        let response = gw::eve_api_post("universe/ids", &query).unwrap_or_default();
+        Self::try_from(response).ok()
+    }
+
+    /// Ids of the all categories
+    pub fn get_ids(&self) -> Vec<IntRequired> {
+        let lists = vec![&self.agents, &self.alliances, &self.characters, &self.constellations, &self.corporations,
+            &self.factions, &self.inventory_types, &self.regions, &self.stations, &self.systems];
+        lists.into_iter().flat_map(|items| items.iter().flatten().map(|item| item.id)).collect()
+    }
 }
 impl TryFrom<String> for Names {
     type Error = serde_json::Error;
@@ -56,4 +69,14 @@ mod tests {
         assert_eq!(names.corporations, Some(vec![Item{ id: 383768304, name: String::from("jion ss Corp") }]));
         assert_eq!(names.systems, Some(vec![Item{ id: 30000142, name: String::from("Jita") }]));
     }
+
+    #[test]
+    fn find() {
+        let response = Names::find(&vec![String::from("Tritanium"), String::from("Rifter")]);
+        assert!(response.is_some());
+        let names = response.unwrap();
+        let mut types = names.inventory_types.unwrap_or_default();
+        types.sort_by_key(|item| item.id);
+        assert_eq!(types, vec![Item{ id: 34, name: String::from("Tritanium") }, Item{ id: 587, name: String::from("Rifter") }]);
+    }
 }
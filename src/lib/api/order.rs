@@ -1,7 +1,7 @@
 use crate::api::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum OrderType{
     ALL,
     BUY,
@@ -26,7 +26,8 @@ pub struct Order {
     pub range: StrRequired
 }
 impl Order {
-    fn load_page(region_id: &i32, order_type: &OrderType, page: &IntRequired, type_id: &IntOptional) -> Option<Orders> {
+    /// The orders of the page with the page count
+    fn load_page(region_id: &i32, order_type: &OrderType, page: &IntRequired, type_id: &IntOptional) -> Result<(Orders, u32), String> {
         let order_type_name = match order_type {
             OrderType::ALL => "all",
             OrderType::BUY => "buy",
@@ -37,28 +38,28 @@ impl Order {
         } else {
             format!("order_type={}&page={}", order_type_name, page)
         };
-        let response = gw::eve_api_ex(&format!("markets/{}/orders", region_id), &filter).unwrap_or_default();
-        serde_json::from_str(&response).ok()
+        let (response, pages) = gw::eve_api_page(&format!("markets/{}/orders", region_id), &filter)?;
+        let orders = serde_json::from_str(&response).map_err(|e| format!("page {} of {}: {}", page, region_id, e))?;
+        Ok((orders, pages))
     }
 
-    pub fn load(region_id: &i32, order_type: OrderType, type_id: IntOptional) -> Orders {
-        let mut result = Vec::new();
-        let mut page = 1;
-        const API_MAX_ITEMS: usize = 1000;
-        while let Some(mut records) = Self::load_page(region_id, &order_type, &page, &type_id) {
-            let count = records.len();
-            if count > 0 {
+    /// All pages listed by X-Pages or None, the partial list is never returned
+    pub fn load(region_id: &i32, order_type: OrderType, type_id: IntOptional) -> Option<Orders> {
+        let load = || -> Result<Orders, String> {
+            let (mut result, pages) = Self::load_page(region_id, &order_type, &1, &type_id)?;
+            for page in 2..=pages as i32 {
+                let (mut records, _) = Self::load_page(region_id, &order_type, &page, &type_id)?;
                 result.append(&mut records);
-                page = page + 1;
             }
-            if count > 500 {
-                break;
-            }
-            if count == 0 || count < API_MAX_ITEMS { // all records received
-                break;
+            Ok(result)
+        };
+        match load() {
+            Ok(orders) => Some(orders),
+            Err(e) => {
+                warn!("was not able to load the orders of {}: {}", region_id, e);
+                None
             }
         }
-        return result;
     }
 
     pub fn load_for_system(system_id: &i32, order_type: OrderType, type_id: IntOptional) -> Orders {
@@ -66,6 +67,7 @@ impl Order {
         if let Some(system) = system::System::new(system_id) {
             if let Some(region_id) = system.get_region_id() {
                 let mut filtered: Orders = Self::load(&region_id, order_type, type_id)
+                            .unwrap_or_default()
                             .iter()
                             .filter(|order| order.system_id == system.system_id)
                             .cloned()
@@ -79,14 +81,14 @@ impl Order {
 
     pub fn load_nth_best_sell_orders(region_id: &i32, type_id: IntRequired, count: usize) -> Orders {
         use std::cmp::Ordering;
-        let mut orders = Self::load(region_id, OrderType::SELL, Some(type_id));
+        let mut orders = Self::load(region_id, OrderType::SELL, Some(type_id)).unwrap_or_default();
         orders.as_mut_slice().sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap_or(Ordering::Equal));
         orders.iter().take(count).cloned().collect()
     }
 
     pub fn load_nth_best_buy_orders(region_id: &i32, type_id: IntRequired, count: usize) -> Orders {
         use std::cmp::Ordering;
-        let mut orders = Self::load(region_id, OrderType::BUY, Some(type_id));
+        let mut orders = Self::load(region_id, OrderType::BUY, Some(type_id)).unwrap_or_default();
         orders.as_mut_slice().sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap_or(Ordering::Equal));
         orders.iter().take(count).cloned().collect()
     }
@@ -101,14 +103,14 @@ mod tests {
 
     #[test]
     fn from_api_load_all() {
-        let orders = Order::load(&REGION_ID, OrderType::ALL, Some(PLEX_ID));
+        let orders = Order::load(&REGION_ID, OrderType::ALL, Some(PLEX_ID)).unwrap();
         assert!(!orders.is_empty());
         assert!(orders.iter().all(|order| order.type_id == PLEX_ID));
     }
 
     #[test]
     fn from_api_load_buy() {
-        let orders = Order::load(&REGION_ID, OrderType::BUY, Some(PLEX_ID));
+        let orders = Order::load(&REGION_ID, OrderType::BUY, Some(PLEX_ID)).unwrap();
         assert!(!orders.is_empty());
         assert!(orders.iter().all(|order| order.type_id == PLEX_ID));
         assert!(orders.iter().all(|order| order.is_buy_order));
@@ -116,7 +118,7 @@ mod tests {
 
     #[test]
     fn from_api_load_sell() {
-        let orders = Order::load(&REGION_ID, OrderType::SELL, Some(PLEX_ID));
+        let orders = Order::load(&REGION_ID, OrderType::SELL, Some(PLEX_ID)).unwrap();
         assert!(!orders.is_empty());
         assert!(orders.iter().all(|order| order.type_id == PLEX_ID));
         assert!(orders.iter().all(|order| !order.is_buy_order));
@@ -41,7 +41,7 @@ impl Prices {
     }
 
     pub fn adj(&self, id: IntRequired) -> FloatOptional {
-        self.items.get(&id).and_then(|price| price.average)
+        self.items.get(&id).and_then(|price| price.adjusted)
     }
 }
 
@@ -34,7 +34,7 @@ impl Victim {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde::export::Result;
+    use std::result::Result;
     use serde_json::json;
 
     #[test]
@@ -0,0 +1,102 @@
+use crate::api::gw;
+use crate::models::{Connection, Date, KillmailsApi};
+
+use chrono::Datelike;
+use crossbeam_queue::SegQueue;
+use crossbeam_utils::thread::scope;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Parallel ESI requests, the zKillboard history lists thousands of killmails a day
+pub const WORKERS: usize = 8;
+
+/// Outcome of the day
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Summary {
+    /// Listed by the zKillboard history
+    pub listed: usize,
+    /// Already stored
+    pub existing: usize,
+    pub saved: usize,
+    pub failed: usize,
+}
+impl Summary {
+    pub fn add(&mut self, other: &Summary) {
+        self.listed += other.listed;
+        self.existing += other.existing;
+        self.saved += other.saved;
+        self.failed += other.failed;
+    }
+}
+
+/// The history is the map of the killmail ids to the hashes
+pub fn parse_history(json: &str) -> Result<Vec<(i32, String)>, String> {
+    let map: HashMap<String, String> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut ids = Vec::new();
+    for (id, hash) in map {
+        let id = id.parse().map_err(|_| format!("Invalid killmail id '{}'", id))?;
+        ids.push((id, hash));
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+/// Loads the killmails of the day missing in the database, the workers query ESI
+/// while the caller thread saves what they received
+pub fn load_day(conn: &Connection, date: &Date, workers: usize) -> Result<Summary, String> {
+    let json = gw::get_history(date.year(), date.month(), date.day());
+    let ids = parse_history(&json).map_err(|e| format!("history of {} is not available: {}", date, e))?;
+    let mut summary = Summary { listed: ids.len(), ..Summary::default() };
+    let tasks = SegQueue::new();
+    for (id, hash) in ids {
+        if KillmailsApi::exist(conn, id) {
+            summary.existing += 1;
+        } else {
+            tasks.push((id, hash));
+        }
+    }
+    let results = SegQueue::new();
+    let running = AtomicUsize::new(workers.max(1));
+    scope(|scope| {
+        for _ in 0..workers.max(1) {
+            scope.spawn(|_| {
+                while let Ok((id, hash)) = tasks.pop() {
+                    results.push(gw::get_killamil(id, &hash).ok_or(id));
+                }
+                running.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+        loop {
+            match results.pop() {
+                Ok(Ok(killmail)) => match KillmailsApi::save(conn, &killmail) {
+                    Ok(()) => summary.saved += 1,
+                    Err(e) => {
+                        warn!("was not able to save killmail {}: {}", killmail.killmail_id, e);
+                        summary.failed += 1;
+                    }
+                },
+                Ok(Err(id)) => {
+                    warn!("was not able to load killmail {}", id);
+                    summary.failed += 1;
+                },
+                Err(_) if running.load(Ordering::SeqCst) == 0 && results.is_empty() => break,
+                Err(_) => std::thread::sleep(Duration::from_millis(50)),
+            }
+        }
+    }).map_err(|_| String::from("backfill worker panicked"))?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history() {
+        let ids = parse_history(r#"{"80000002": "b", "80000001": "a"}"#).unwrap();
+        assert_eq!(vec![(80000001, String::from("a")), (80000002, String::from("b"))], ids);
+        assert!(parse_history("").is_err());
+        assert!(parse_history(r#"{"kill": "a"}"#).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// Read from the working directory when no file is given
+pub const CONFIG_FILE: &str = "zkb.toml";
+pub const USER_AGENT: &str = "Easy API, Maintainer: seb@ukr.net";
+
+/// Environment variables and the settings they override
+pub const ENV: [(&str, &str); 13] = [
+    ("ZKB_INTERFACE", "server.interface"),
+    ("ZKB_BASE_URL", "server.base_url"),
+    ("ZKB_TIMEOUT", "server.timeout"),
+    ("DATABASE_URL", "database.url"),
+    ("ZKB_RETENTION_WEEKS", "database.retention_weeks"),
+    ("ZKB_STORAGE", "storage.scripts"),
+    ("ZKB_BACKUP", "storage.backup"),
+    ("ZKB_RESOLVERS", "services.resolvers"),
+    ("ZKB_REDISQ_ID", "services.redisq_id"),
+    ("ZKB_USER_AGENT", "services.user_agent"),
+    ("ZKB_WEBHOOKS", "services.webhooks"),
+    ("ZKB_MARKET_REGIONS", "market.regions"),
+    ("ZKB_FEATURES", "features"),
+];
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Server {
+    /// host:port to bind
+    pub interface: String,
+    /// Public url of the site, http://{interface} when omitted
+    pub base_url: Option<String>,
+    /// Seconds of the RedisQ suspension and the shutdown
+    pub timeout: u64,
+}
+impl Default for Server {
+    fn default() -> Self {
+        Self { interface: String::from("127.0.0.1:8088"), base_url: None, timeout: 15 }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Database {
+    pub url: String,
+    /// Older killmails are not saved
+    pub retention_weeks: i64,
+}
+impl Default for Database {
+    fn default() -> Self {
+        Self { url: String::from("navigator.db"), retention_weeks: 32 }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Storage {
+    /// Served as /navigator/js/
+    pub scripts: String,
+    /// Served as /navigator/backup/
+    pub backup: String,
+}
+impl Default for Storage {
+    fn default() -> Self {
+        Self { scripts: String::from("js/chartjs/dist/"), backup: String::from("backup/") }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Services {
+    /// Name resolver threads
+    pub resolvers: usize,
+    /// RedisQ queue id, the host name when omitted
+    pub redisq_id: Option<String>,
+    pub user_agent: String,
+    /// The webhooks file of the notifier
+    pub webhooks: Option<String>,
+}
+impl Default for Services {
+    fn default() -> Self {
+        Self { resolvers: 4, redisq_id: None, user_agent: String::from(USER_AGENT), webhooks: None }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Market {
+    /// Regions whose orders are snapshot
+    pub regions: Vec<i32>,
+}
+
+/// Background threads, the web server and the database are always run
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    pub monitor: bool,
+    /// The webhooks, the killfeed is served without them
+    pub notifier: bool,
+    pub pricer: bool,
+    pub ingestor: bool,
+}
+impl Default for Features {
+    fn default() -> Self {
+        Self { monitor: true, notifier: true, pricer: true, ingestor: true }
+    }
+}
+impl Features {
+    /// Comma separated features to run, the rest are disabled
+    fn enable_only(&mut self, list: &str) -> Result<(), String> {
+        *self = Self { monitor: false, notifier: false, pricer: false, ingestor: false };
+        for name in list.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name {
+                "monitor" => self.monitor = true,
+                "notifier" => self.notifier = true,
+                "pricer" => self.pricer = true,
+                "ingestor" => self.ingestor = true,
+                _ => return Err(format!("Unknown feature '{}'", name)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: Server,
+    pub database: Database,
+    pub storage: Storage,
+    pub services: Services,
+    pub market: Market,
+    pub features: Features,
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("Invalid value '{}' for {}", value, key))
+}
+
+fn optional(value: &str) -> Option<String> {
+    if value.trim().is_empty() { None } else { Some(value.trim().to_string()) }
+}
+
+fn directory(path: &str) -> String {
+    if path.ends_with('/') { path.to_string() } else { format!("{}/", path) }
+}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// The given file has to exist, the default one is optional
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let file = path.unwrap_or(CONFIG_FILE);
+        match std::fs::read_to_string(file) {
+            Ok(text) => Self::from_toml(&text).map_err(|e| format!("{}: {}", file, e)),
+            Err(_) if path.is_none() => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", file, e)),
+        }
+    }
+
+    /// Sets the setting by the section.name key
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "server.interface" => self.server.interface = value.trim().to_string(),
+            "server.base_url" => self.server.base_url = optional(value),
+            "server.timeout" => self.server.timeout = parse(key, value)?,
+            "database.url" => self.database.url = value.trim().to_string(),
+            "database.retention_weeks" => self.database.retention_weeks = parse(key, value)?,
+            "storage.scripts" => self.storage.scripts = value.trim().to_string(),
+            "storage.backup" => self.storage.backup = value.trim().to_string(),
+            "services.resolvers" => self.services.resolvers = parse(key, value)?,
+            "services.redisq_id" => self.services.redisq_id = optional(value),
+            "services.user_agent" => self.services.user_agent = value.trim().to_string(),
+            "services.webhooks" => self.services.webhooks = optional(value),
+            "market.regions" => self.market.regions = value.split(',')
+                .filter(|id| !id.trim().is_empty())
+                .map(|id| parse(key, id))
+                .collect::<Result<Vec<i32>, String>>()?,
+            "features" => self.features.enable_only(value)?,
+            "features.monitor" => self.features.monitor = parse(key, value)?,
+            "features.notifier" => self.features.notifier = parse(key, value)?,
+            "features.pricer" => self.features.pricer = parse(key, value)?,
+            "features.ingestor" => self.features.ingestor = parse(key, value)?,
+            _ => return Err(format!("Unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Applies the set environment variables of the ENV table
+    pub fn apply_env<V>(&mut self, var: &V) -> Result<(), String>
+        where V: Fn(&str) -> Option<String>
+    {
+        for (name, key) in ENV.iter() {
+            if let Some(value) = var(name) {
+                self.set(key, &value).map_err(|e| format!("{}: {}", name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// All the problems are reported at once
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        let port = self.server.interface.rsplit(':').next().and_then(|port| port.parse::<u16>().ok());
+        if !self.server.interface.contains(':') || port.is_none() {
+            errors.push(format!("server.interface '{}' has to be host:port", self.server.interface));
+        }
+        if let Some(url) = &self.server.base_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.push(format!("server.base_url '{}' has to start with http:// or https://", url));
+            }
+        }
+        if self.server.timeout == 0 {
+            errors.push(String::from("server.timeout has to be positive"));
+        }
+        if self.database.url.is_empty() {
+            errors.push(String::from("database.url is required"));
+        }
+        if self.database.retention_weeks <= 0 {
+            errors.push(String::from("database.retention_weeks has to be positive"));
+        }
+        if self.storage.scripts.is_empty() {
+            errors.push(String::from("storage.scripts is required"));
+        }
+        if self.storage.backup.is_empty() {
+            errors.push(String::from("storage.backup is required"));
+        }
+        if self.services.resolvers == 0 || self.services.resolvers > 64 {
+            errors.push(format!("services.resolvers {} has to be from 1 to 64", self.services.resolvers));
+        }
+        if self.services.user_agent.is_empty() {
+            errors.push(String::from("services.user_agent is required, ESI asks for the contact"));
+        }
+        if let Some(path) = &self.services.webhooks {
+            if !std::path::Path::new(path).is_file() {
+                errors.push(format!("services.webhooks file '{}' does not exist", path));
+            }
+        }
+        for id in self.market.regions.iter().filter(|id| !(10000000..20000000).contains(*id)) {
+            errors.push(format!("market.regions {} is not a region id", id));
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// http://{interface}/navigator or {base_url}/navigator
+    pub fn get_root(&self) -> String {
+        let base = self.server.base_url.clone().unwrap_or(format!("http://{}", self.server.interface));
+        format!("{}/navigator", base.trim_end_matches('/'))
+    }
+
+    pub fn get_scripts(&self) -> String {
+        directory(&self.storage.scripts)
+    }
+
+    pub fn get_backup(&self) -> String {
+        directory(&self.storage.backup)
+    }
+
+    /// Killmails older than the retention are skipped
+    pub fn get_allowed(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now() - chrono::Duration::weeks(self.database.retention_weeks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn defaults() {
+        let config = Config::from_toml("").unwrap();
+        assert_eq!(Config::default(), config);
+        assert_eq!(4, config.services.resolvers);
+        assert_eq!(15, config.server.timeout);
+        assert_eq!("http://127.0.0.1:8088/navigator", config.get_root());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn from_toml() {
+        let config = Config::from_toml(r#"
+            [server]
+            interface = "0.0.0.0:80"
+            base_url = "https://zkb.example.com/"
+
+            [storage]
+            scripts = "js"
+
+            [market]
+            regions = [10000002, 10000043]
+
+            [features]
+            pricer = false
+        "#).unwrap();
+        assert_eq!("https://zkb.example.com/navigator", config.get_root());
+        assert_eq!("js/", config.get_scripts());
+        assert_eq!(vec![10000002, 10000043], config.market.regions);
+        assert_eq!((true, false), (config.features.monitor, config.features.pricer));
+        assert_eq!(32, config.database.retention_weeks);
+
+        let error = Config::from_toml("[server]\nport = 80").unwrap_err();
+        assert!(error.contains("port"));
+    }
+
+    #[test]
+    fn overrides() {
+        let mut vars = HashMap::new();
+        vars.insert("ZKB_INTERFACE", "10.0.0.1:8080");
+        vars.insert("ZKB_MARKET_REGIONS", "10000002, 10000030");
+        vars.insert("ZKB_FEATURES", "monitor,notifier");
+        let mut config = Config::default();
+        config.apply_env(&|name| vars.get(name).map(|value| value.to_string())).unwrap();
+        assert_eq!("10.0.0.1:8080", config.server.interface);
+        assert_eq!(vec![10000002, 10000030], config.market.regions);
+        assert_eq!((true, true, false, false),
+            (config.features.monitor, config.features.notifier, config.features.pricer, config.features.ingestor));
+
+        config.set("server.timeout", "30").unwrap();
+        assert_eq!(30, config.server.timeout);
+        assert_eq!(Err(String::from("Invalid value 'soon' for server.timeout")), config.set("server.timeout", "soon"));
+        assert_eq!(Err(String::from("Unknown setting 'server.port'")), config.set("server.port", "80"));
+        vars.insert("ZKB_RESOLVERS", "many");
+        assert_eq!(Err(String::from("ZKB_RESOLVERS: Invalid value 'many' for services.resolvers")),
+            config.apply_env(&|name| vars.get(name).map(|value| value.to_string())));
+    }
+
+    #[test]
+    fn validate() {
+        let mut config = Config::default();
+        config.set("server.interface", "localhost").unwrap();
+        config.set("server.base_url", "zkb.example.com").unwrap();
+        config.set("services.resolvers", "0").unwrap();
+        config.set("market.regions", "30000142").unwrap();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(4, errors.len());
+        assert!(errors[0].starts_with("server.interface"));
+        assert!(errors[3].contains("30000142"));
+    }
+}
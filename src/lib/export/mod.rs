@@ -0,0 +1,323 @@
+use crate::api;
+use crate::api::stats::Entity;
+use crate::models;
+use crate::models::{Connection, DateTime};
+
+use std::fs::File;
+use std::io::Write;
+
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// Killmails are loaded and written by chunks to keep the memory flat
+pub const CHUNK_SIZE: usize = 500;
+/// The range served by HTTP is short, the longer ones are exported by the command line
+pub const EXPORT_DAYS: i64 = 7;
+
+/// Parquet is known to the builds with the parquet feature only
+#[cfg(feature = "parquet")]
+pub const FORMATS: &[&str] = &["csv", "jsonl", "parquet"];
+#[cfg(not(feature = "parquet"))]
+pub const FORMATS: &[&str] = &["csv", "jsonl"];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    /// The row per the victim, the each attacker and the each item
+    Csv,
+    /// The killmail per line in the ESI shape with the items in their containers
+    Jsonl,
+    /// The same rows as the CSV ones
+    Parquet,
+}
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(Format::Csv),
+            "jsonl" => Some(Format::Jsonl),
+            "parquet" if cfg!(feature = "parquet") => Some(Format::Parquet),
+            _ => None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Csv => "text/csv; charset=UTF-8",
+            Format::Jsonl => "application/x-ndjson; charset=UTF-8",
+            Format::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+/// Time range and the optional actor or area involved
+#[derive(Debug, PartialEq, Clone)]
+pub struct Filter {
+    pub entity: Option<Entity>,
+    pub start: DateTime,
+    pub end: DateTime,
+}
+
+/// Flattened participant or item of the killmail
+#[derive(Debug, PartialEq, Clone)]
+pub struct Row {
+    pub killmail_id: i32,
+    pub killmail_time: DateTime,
+    pub solar_system_id: i32,
+    pub moon_id: Option<i32>,
+    pub war_id: Option<i32>,
+    /// victim, attacker or item
+    pub role: &'static str,
+    pub character_id: Option<i32>,
+    pub corporation_id: Option<i32>,
+    pub alliance_id: Option<i32>,
+    pub faction_id: Option<i32>,
+    pub ship_type_id: Option<i32>,
+    /// Taken by the victim, done by the attacker
+    pub damage: Option<i32>,
+    pub final_blow: Option<bool>,
+    pub security_status: Option<f32>,
+    pub weapon_type_id: Option<i32>,
+    pub item_type_id: Option<i32>,
+    pub flag: Option<i32>,
+    pub singleton: Option<i32>,
+    pub quantity_destroyed: Option<i32>,
+    pub quantity_dropped: Option<i32>,
+}
+
+pub const CSV_HEADER: &str = "killmail_id,killmail_time,solar_system_id,moon_id,war_id,role,\
+    character_id,corporation_id,alliance_id,faction_id,ship_type_id,damage,final_blow,security_status,\
+    weapon_type_id,item_type_id,flag,singleton,quantity_destroyed,quantity_dropped";
+
+fn field<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|value| value.to_string()).unwrap_or_default()
+}
+
+impl Row {
+    fn new(killmail: &api::Killmail, role: &'static str) -> Self {
+        Self {
+            killmail_id: killmail.killmail_id,
+            killmail_time: killmail.killmail_time.naive_utc(),
+            solar_system_id: killmail.solar_system_id,
+            moon_id: killmail.moon_id,
+            war_id: killmail.war_id,
+            role,
+            character_id: None,
+            corporation_id: None,
+            alliance_id: None,
+            faction_id: None,
+            ship_type_id: None,
+            damage: None,
+            final_blow: None,
+            security_status: None,
+            weapon_type_id: None,
+            item_type_id: None,
+            flag: None,
+            singleton: None,
+            quantity_destroyed: None,
+            quantity_dropped: None,
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        vec![
+            self.killmail_id.to_string(),
+            self.killmail_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            self.solar_system_id.to_string(),
+            field(&self.moon_id),
+            field(&self.war_id),
+            self.role.to_string(),
+            field(&self.character_id),
+            field(&self.corporation_id),
+            field(&self.alliance_id),
+            field(&self.faction_id),
+            field(&self.ship_type_id),
+            field(&self.damage),
+            field(&self.final_blow),
+            field(&self.security_status),
+            field(&self.weapon_type_id),
+            field(&self.item_type_id),
+            field(&self.flag),
+            field(&self.singleton),
+            field(&self.quantity_destroyed),
+            field(&self.quantity_dropped),
+        ].join(",")
+    }
+}
+
+fn item_row(killmail: &api::Killmail, item: &api::Item) -> Row {
+    Row {
+        item_type_id: Some(item.item_type_id),
+        flag: Some(item.flag),
+        singleton: Some(item.singleton),
+        quantity_destroyed: item.quantity_destroyed,
+        quantity_dropped: item.quantity_dropped,
+        ..Row::new(killmail, "item")
+    }
+}
+
+/// The victim first, then the attackers and the items
+pub fn get_rows(killmail: &api::Killmail) -> Vec<Row> {
+    let victim = &killmail.victim;
+    let mut rows = vec![Row {
+        character_id: victim.character_id,
+        corporation_id: victim.corporation_id,
+        alliance_id: victim.alliance_id,
+        faction_id: victim.faction_id,
+        ship_type_id: Some(victim.ship_type_id),
+        damage: Some(victim.damage_taken),
+        ..Row::new(killmail, "victim")
+    }];
+    for attacker in &killmail.attackers {
+        rows.push(Row {
+            character_id: attacker.character_id,
+            corporation_id: attacker.corporation_id,
+            alliance_id: attacker.alliance_id,
+            faction_id: attacker.faction_id,
+            ship_type_id: attacker.ship_type_id,
+            damage: Some(attacker.damage_done),
+            final_blow: Some(attacker.final_blow),
+            security_status: Some(attacker.security_status),
+            weapon_type_id: attacker.weapon_type_id,
+            ..Row::new(killmail, "attacker")
+        });
+    }
+    if let Some(items) = &victim.items {
+        for item in items {
+            rows.push(item_row(killmail, item));
+            if let Some(items) = &item.items {
+                for item in items {
+                    rows.push(item_row(killmail, item));
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Text chunk of the CSV or JSONL export, the header is written by the caller
+pub fn to_text(format: Format, killmails: &Vec<api::Killmail>) -> String {
+    let mut output = String::new();
+    for killmail in killmails {
+        match format {
+            Format::Csv => {
+                for row in get_rows(killmail) {
+                    output += &row.to_csv();
+                    output += "\n";
+                }
+            },
+            Format::Jsonl => {
+                output += &serde_json::to_string(killmail).ok().unwrap_or_default();
+                output += "\n";
+            },
+            Format::Parquet => warn!("Parquet is not a text format"),
+        }
+    }
+    output
+}
+
+/// Streams the export into the file or to the stdout, returns the count of killmails.
+/// Parquet needs the file and the parquet feature.
+pub fn run(conn: &Connection, format: Format, filter: &Filter, path: Option<String>) -> Result<usize, String> {
+    let ids = models::export::load_ids(conn, &filter.entity, &filter.start, &filter.end).map_err(|e| e.to_string())?;
+    let chunks = ids.chunks(CHUNK_SIZE).map(|chunk| models::export::load(conn, &chunk.to_vec()));
+    if format == Format::Parquet {
+        return write_parquet(path, chunks);
+    }
+    let mut output: Box<dyn Write> = match path {
+        Some(path) => Box::new(File::create(&path).map_err(|e| e.to_string())?),
+        None => Box::new(std::io::stdout()),
+    };
+    if format == Format::Csv {
+        writeln!(output, "{}", CSV_HEADER).map_err(|e| e.to_string())?;
+    }
+    let mut count = 0;
+    for chunk in chunks {
+        let killmails = chunk.map_err(|e| e.to_string())?;
+        output.write_all(to_text(format, &killmails).as_bytes()).map_err(|e| e.to_string())?;
+        count += killmails.len();
+    }
+    Ok(count)
+}
+
+#[cfg(feature = "parquet")]
+pub fn write_parquet<I>(path: Option<String>, chunks: I) -> Result<usize, String>
+    where I: Iterator<Item = models::QueryResult<Vec<api::Killmail>>>
+{
+    let path = path.ok_or(String::from("Parquet export needs the output file"))?;
+    let mut writer = self::parquet::Writer::new(File::create(&path).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    let mut count = 0;
+    for chunk in chunks {
+        let killmails = chunk.map_err(|e| e.to_string())?;
+        writer.write(&killmails).map_err(|e| e.to_string())?;
+        count += killmails.len();
+    }
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn write_parquet<I>(_path: Option<String>, _chunks: I) -> Result<usize, String>
+    where I: Iterator<Item = models::QueryResult<Vec<api::Killmail>>>
+{
+    Err(String::from("Parquet export needs the parquet feature"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn killmail() -> api::Killmail {
+        let json = r#"{
+            "killmail_id": 78146996,
+            "killmail_time": "2019-08-07T10:28:41Z",
+            "solar_system_id": 30045352,
+            "victim": {
+                "ship_type_id": 587, "damage_taken": 1500, "character_id": 1, "corporation_id": 2,
+                "items": [{"item_type_id": 3178, "singleton": 0, "flag": 27, "quantity_destroyed": 1}]
+            },
+            "attackers": [
+                {"ship_type_id": 16227, "character_id": 3, "corporation_id": 4, "damage_done": 1000, "final_blow": true, "security_status": 5.0},
+                {"ship_type_id": 16227, "character_id": 5, "corporation_id": 4, "damage_done": 500, "final_blow": false, "security_status": -1.5}
+            ]
+        }"#;
+        api::Killmail::try_from(String::from(json)).unwrap()
+    }
+
+    #[test]
+    fn rows() {
+        let rows = get_rows(&killmail());
+        assert_eq!(4, rows.len());
+        assert_eq!(vec!["victim", "attacker", "attacker", "item"], rows.iter().map(|row| row.role).collect::<Vec<&str>>());
+        assert_eq!(Some(1500), rows[0].damage);
+        assert_eq!(Some(true), rows[1].final_blow);
+        assert_eq!(Some(3178), rows[3].item_type_id);
+        assert!(rows.iter().all(|row| row.killmail_id == 78146996));
+    }
+
+    #[test]
+    fn csv() {
+        let text = to_text(Format::Csv, &vec![killmail()]);
+        let columns = CSV_HEADER.split(',').count();
+        assert_eq!(4, text.lines().count());
+        assert!(text.lines().all(|line| line.split(',').count() == columns));
+        assert!(text.starts_with("78146996,2019-08-07 10:28:41,30045352,,,victim,1,2,,,587,1500,"));
+    }
+
+    #[test]
+    fn jsonl() {
+        let killmails = vec![killmail(), killmail()];
+        let text = to_text(Format::Jsonl, &killmails);
+        let parsed: Vec<api::Killmail> = text.lines()
+            .map(|line| api::Killmail::try_from(String::from(line)).unwrap())
+            .collect();
+        assert_eq!(killmails, parsed);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::api;
+use super::{Row, get_rows};
+
+use parquet_format_safe::thrift::protocol::TCompactOutputProtocol;
+use parquet_format_safe::{ColumnChunk, ColumnMetaData, CompressionCodec, ConvertedType, DataPageHeader, Encoding};
+use parquet_format_safe::{FieldRepetitionType, FileMetaData, PageHeader, PageType, RowGroup, SchemaElement, Type};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+pub type Result<T> = std::result::Result<T, String>;
+
+const MAGIC: &[u8] = b"PAR1";
+
+/// The stable schema, the columns follow the CSV header: the name, the type, is it optional and the annotation
+const COLUMNS: [(&str, Type, bool, Option<ConvertedType>); 20] = [
+    ("killmail_id", Type::INT32, false, None),
+    ("killmail_time", Type::INT64, false, Some(ConvertedType::TIMESTAMP_MILLIS)),
+    ("solar_system_id", Type::INT32, false, None),
+    ("moon_id", Type::INT32, true, None),
+    ("war_id", Type::INT32, true, None),
+    ("role", Type::BYTE_ARRAY, false, Some(ConvertedType::UTF8)),
+    ("character_id", Type::INT32, true, None),
+    ("corporation_id", Type::INT32, true, None),
+    ("alliance_id", Type::INT32, true, None),
+    ("faction_id", Type::INT32, true, None),
+    ("ship_type_id", Type::INT32, true, None),
+    ("damage", Type::INT32, true, None),
+    ("final_blow", Type::BOOLEAN, true, None),
+    ("security_status", Type::FLOAT, true, None),
+    ("weapon_type_id", Type::INT32, true, None),
+    ("item_type_id", Type::INT32, true, None),
+    ("flag", Type::INT32, true, None),
+    ("singleton", Type::INT32, true, None),
+    ("quantity_destroyed", Type::INT32, true, None),
+    ("quantity_dropped", Type::INT32, true, None),
+];
+
+fn get_int(row: &Row, index: usize) -> Option<i32> {
+    match index {
+        0 => Some(row.killmail_id),
+        2 => Some(row.solar_system_id),
+        3 => row.moon_id,
+        4 => row.war_id,
+        6 => row.character_id,
+        7 => row.corporation_id,
+        8 => row.alliance_id,
+        9 => row.faction_id,
+        10 => row.ship_type_id,
+        11 => row.damage,
+        14 => row.weapon_type_id,
+        15 => row.item_type_id,
+        16 => row.flag,
+        17 => row.singleton,
+        18 => row.quantity_destroyed,
+        19 => row.quantity_dropped,
+        _ => None
+    }
+}
+
+/// The PLAIN encoded present values of the column with the flags of the present ones
+fn get_values(rows: &Vec<Row>, index: usize) -> (Vec<u8>, Vec<bool>) {
+    let mut values = Vec::new();
+    let mut present = Vec::new();
+    let mut bits = Vec::new();
+    for row in rows {
+        let exists = match COLUMNS[index].1 {
+            Type::INT64 => {
+                values.extend_from_slice(&row.killmail_time.timestamp_millis().to_le_bytes());
+                true
+            },
+            Type::BYTE_ARRAY => {
+                values.extend_from_slice(&(row.role.len() as u32).to_le_bytes());
+                values.extend_from_slice(row.role.as_bytes());
+                true
+            },
+            Type::BOOLEAN => row.final_blow.map(|value| bits.push(value)).is_some(),
+            Type::FLOAT => row.security_status.map(|value| values.extend_from_slice(&value.to_le_bytes())).is_some(),
+            _ => get_int(row, index).map(|value| values.extend_from_slice(&value.to_le_bytes())).is_some(),
+        };
+        present.push(exists);
+    }
+    if !bits.is_empty() {
+        values = pack(&bits);
+    }
+    (values, present)
+}
+
+/// Eight flags to the byte, the first one is the lowest bit
+fn pack(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| chunk.iter().enumerate().fold(0u8, |byte, (bit, set)| if *set { byte | 1 << bit } else { byte }))
+        .collect()
+}
+
+fn put_varint(output: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        output.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+/// The definition levels of the optional column as the single bit-packed run of the RLE hybrid encoding
+fn get_levels(present: &[bool]) -> Vec<u8> {
+    let packed = pack(present);
+    let mut run = Vec::new();
+    put_varint(&mut run, (packed.len() as u32) << 1 | 1);
+    run.extend_from_slice(&packed);
+    let mut levels = (run.len() as u32).to_le_bytes().to_vec();
+    levels.extend_from_slice(&run);
+    levels
+}
+
+fn to_thrift<F>(write: F) -> Result<Vec<u8>>
+    where F: FnOnce(&mut TCompactOutputProtocol<&mut Vec<u8>>) -> parquet_format_safe::thrift::Result<usize>
+{
+    let mut bytes = Vec::new();
+    write(&mut TCompactOutputProtocol::new(&mut bytes)).map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Writes the each chunk of killmails as the row group of the uncompressed single page columns
+pub struct Writer {
+    output: BufWriter<File>,
+    offset: i64,
+    num_rows: i64,
+    row_groups: Vec<RowGroup>,
+}
+impl Writer {
+    pub fn new(file: File) -> Result<Self> {
+        let mut output = BufWriter::new(file);
+        output.write_all(MAGIC).map_err(|e| e.to_string())?;
+        Ok(Self { output, offset: MAGIC.len() as i64, num_rows: 0, row_groups: Vec::new() })
+    }
+
+    fn write_column(&mut self, rows: &Vec<Row>, index: usize) -> Result<ColumnChunk> {
+        let (name, column_type, optional, _) = COLUMNS[index];
+        let (values, present) = get_values(rows, index);
+        let mut page = if optional { get_levels(&present) } else { Vec::new() };
+        page.extend_from_slice(&values);
+        let data = DataPageHeader::new(rows.len() as i32, Encoding::PLAIN, Encoding::RLE, Encoding::RLE, None);
+        let header = PageHeader::new(PageType::DATA_PAGE, page.len() as i32, page.len() as i32, None, data, None, None, None);
+        let header = to_thrift(|protocol| header.write_to_out_protocol(protocol))?;
+        self.output.write_all(&header).map_err(|e| e.to_string())?;
+        self.output.write_all(&page).map_err(|e| e.to_string())?;
+
+        let size = (header.len() + page.len()) as i64;
+        let meta = ColumnMetaData::new(column_type, vec![Encoding::PLAIN, Encoding::RLE], vec![name.to_string()], CompressionCodec::UNCOMPRESSED,
+            rows.len() as i64, size, size, None, self.offset, None, None, None, None, None);
+        let chunk = ColumnChunk::new(None, self.offset, meta, None, None, None, None, None, None);
+        self.offset += size;
+        Ok(chunk)
+    }
+
+    pub fn write(&mut self, killmails: &[api::Killmail]) -> Result<usize> {
+        let rows: Vec<Row> = killmails.iter().flat_map(get_rows).collect();
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        let start = self.offset;
+        let mut columns = Vec::new();
+        for index in 0..COLUMNS.len() {
+            columns.push(self.write_column(&rows, index)?);
+        }
+        let size = self.offset - start;
+        self.row_groups.push(RowGroup::new(columns, size, rows.len() as i64, None, start, size, self.row_groups.len() as i16));
+        self.num_rows += rows.len() as i64;
+        Ok(rows.len())
+    }
+
+    fn get_schema() -> Vec<SchemaElement> {
+        let mut schema = vec![SchemaElement::new(None, None, None, String::from("killmail_rows"), COLUMNS.len() as i32, None, None, None, None, None)];
+        for (name, column_type, optional, converted) in COLUMNS.iter() {
+            let repetition = if *optional { FieldRepetitionType::OPTIONAL } else { FieldRepetitionType::REQUIRED };
+            schema.push(SchemaElement::new(*column_type, None, repetition, name.to_string(), None, *converted, None, None, None, None));
+        }
+        schema
+    }
+
+    /// The footer holds the metadata with its length
+    pub fn close(mut self) -> Result<()> {
+        let created_by = format!("zkb version {}", env!("CARGO_PKG_VERSION"));
+        let meta = FileMetaData::new(1, Self::get_schema(), self.num_rows, self.row_groups, None, created_by, None, None, None);
+        let footer = to_thrift(|protocol| meta.write_to_out_protocol(protocol))?;
+        self.output.write_all(&footer).map_err(|e| e.to_string())?;
+        self.output.write_all(&(footer.len() as u32).to_le_bytes()).map_err(|e| e.to_string())?;
+        self.output.write_all(MAGIC).map_err(|e| e.to_string())?;
+        self.output.flush().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet_format_safe::thrift::protocol::TCompactInputProtocol;
+    use std::convert::TryFrom;
+    use std::io::Read;
+
+    #[test]
+    fn levels() {
+        assert_eq!(vec![0b101], pack(&[true, false, true]));
+        assert_eq!(vec![3, 0, 0, 0, 0x05, 0xff, 0x01], get_levels(&[true; 9]));
+    }
+
+    #[test]
+    fn write() {
+        let json = r#"{"killmail_id": 1, "killmail_time": "2019-08-07T10:28:41Z", "solar_system_id": 30045352,
+            "victim": {"ship_type_id": 587, "damage_taken": 100},
+            "attackers": [{"ship_type_id": 587, "damage_done": 100, "final_blow": true, "security_status": 0.5}]}"#;
+        let killmail = api::Killmail::try_from(String::from(json)).unwrap();
+        let path = std::env::temp_dir().join(format!("{}.parquet", crate::create_id()));
+        let mut writer = Writer::new(File::create(&path).unwrap()).unwrap();
+        assert_eq!(2, writer.write(&[killmail]).unwrap());
+        writer.close().unwrap();
+
+        let mut content = Vec::new();
+        File::open(&path).unwrap().read_to_end(&mut content).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(b"PAR1", &content[..4]);
+        assert_eq!(b"PAR1", &content[content.len() - 4..]);
+
+        let length = u32::from_le_bytes([content[content.len() - 8], content[content.len() - 7], content[content.len() - 6], content[content.len() - 5]]) as usize;
+        let footer = &content[content.len() - 8 - length..content.len() - 8];
+        let meta = FileMetaData::read_from_in_protocol(&mut TCompactInputProtocol::new(footer, usize::MAX)).unwrap();
+        assert_eq!(2, meta.num_rows);
+        assert_eq!(COLUMNS.len() + 1, meta.schema.len());
+        assert_eq!(COLUMNS.len(), meta.row_groups[0].columns.len());
+    }
+}
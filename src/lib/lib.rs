@@ -8,6 +8,8 @@ extern crate log;
 extern crate lazy_static;
 extern crate uuid;
 extern crate separator;
+extern crate futures;
+extern crate bytes;
 
 use uuid::Uuid;
 
@@ -16,6 +18,17 @@ pub mod models;
 pub mod provider;
 pub mod services;
 pub mod reports;
+pub mod navigation;
+pub mod statistics;
+pub mod alerts;
+pub mod webhooks;
+pub mod export;
+pub mod valuation;
+pub mod market;
+pub mod config;
+pub mod backfill;
+pub mod sde;
+pub mod metrics;
 
 pub use models::schema;
 
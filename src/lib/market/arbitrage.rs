@@ -0,0 +1,314 @@
+use crate::api::{Order, Orders};
+use crate::navigation::{Graph, Kills, RouteQuery, Safety};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Sales tax of the character without the Accounting skill
+pub const SALES_TAX: f32 = 0.05;
+/// Broker fee is not paid by the instant sale to the buy order, set it to account for the relisting
+pub const BROKER_FEE: f32 = 0.0;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Fees {
+    pub sales_tax: f32,
+    pub broker_fee: f32,
+}
+impl Default for Fees {
+    fn default() -> Self {
+        Self { sales_tax: SALES_TAX, broker_fee: BROKER_FEE }
+    }
+}
+impl Fees {
+    /// Income from the sale after the tax and the fee
+    pub fn net(&self, gross: f64) -> f64 {
+        gross * (1.0 - self.sales_tax.max(0.0) as f64 - self.broker_fee.max(0.0) as f64)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Rank {
+    Profit,
+    /// Profit per m3 of the cargo
+    Density,
+    /// The least kills on the route, then the profit
+    Risk,
+}
+impl Rank {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "profit" => Some(Rank::Profit),
+            "density" | "m3" => Some(Rank::Density),
+            "risk" => Some(Rank::Risk),
+            _ => None
+        }
+    }
+}
+
+/// Buying the sell orders in the source system and selling to the buy orders in the destination one
+#[derive(Debug, PartialEq, Clone)]
+pub struct Deal {
+    pub type_id: i32,
+    pub src_system_id: i32,
+    pub dst_system_id: i32,
+    pub quantity: i64,
+    /// Paid for the sell orders
+    pub cost: f64,
+    /// Received from the buy orders after the fees
+    pub income: f64,
+    /// Volume of the whole quantity in m3
+    pub volume: Option<f64>,
+    pub jumps: Option<u32>,
+    /// Kills on the route for the risk window
+    pub kills: Option<i32>,
+}
+impl Deal {
+    pub fn get_profit(&self) -> f64 {
+        self.income - self.cost
+    }
+
+    pub fn get_margin(&self) -> f64 {
+        if self.cost > 0.0 { self.get_profit() / self.cost } else { 0.0 }
+    }
+
+    pub fn get_density(&self) -> Option<f64> {
+        self.volume.filter(|volume| *volume > 0.0).map(|volume| self.get_profit() / volume)
+    }
+
+    pub fn set_volume(&mut self, unit_volume: Option<f32>) {
+        self.volume = unit_volume.map(|volume| volume as f64 * self.quantity as f64);
+    }
+}
+
+struct Lot {
+    price: f64,
+    remain: i64,
+    min_volume: i64,
+}
+
+fn get_lots(orders: &Vec<&Order>) -> Vec<Lot> {
+    orders.iter()
+        .map(|order| Lot { price: order.price as f64, remain: order.volume_remain as i64, min_volume: order.min_volume as i64 })
+        .collect()
+}
+
+/// Walks the cheapest sells and the highest buys while the each unit is still profitable
+fn match_orders(sells: &Vec<&Order>, buys: &Vec<&Order>, fees: &Fees) -> Option<(i64, f64, f64)> {
+    let mut sells = get_lots(sells);
+    let mut buys = get_lots(buys);
+    sells.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap_or(Ordering::Equal));
+    buys.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap_or(Ordering::Equal));
+    let (mut quantity, mut cost, mut income) = (0, 0.0, 0.0);
+    let (mut src, mut dst) = (0, 0);
+    while src < sells.len() && dst < buys.len() {
+        if fees.net(buys[dst].price) <= sells[src].price {
+            break;
+        }
+        let count = sells[src].remain.min(buys[dst].remain);
+        if count < buys[dst].min_volume {
+            dst += 1;
+            continue;
+        }
+        quantity += count;
+        cost += count as f64 * sells[src].price;
+        income += fees.net(count as f64 * buys[dst].price);
+        sells[src].remain -= count;
+        buys[dst].remain -= count;
+        buys[dst].min_volume = 1;
+        if sells[src].remain == 0 {
+            src += 1;
+        }
+        if buys[dst].remain == 0 {
+            dst += 1;
+        }
+    }
+    if quantity > 0 { Some((quantity, cost, income)) } else { None }
+}
+
+fn group(orders: &Orders, is_buy: bool) -> HashMap<(i32, i32), Vec<&Order>> {
+    let mut result: HashMap<(i32, i32), Vec<&Order>> = HashMap::new();
+    for order in orders.iter().filter(|order| order.is_buy_order == is_buy) {
+        result.entry((order.type_id, order.system_id)).or_default().push(order);
+    }
+    result
+}
+
+/// Matches the source sell orders against the destination buy orders of the same type
+/// for the each pair of the different systems
+pub fn find_deals(sources: &Orders, destinations: &Orders, fees: &Fees) -> Vec<Deal> {
+    let sells = group(sources, false);
+    let mut buys: HashMap<i32, Vec<(i32, Vec<&Order>)>> = HashMap::new();
+    for ((type_id, system_id), orders) in group(destinations, true) {
+        buys.entry(type_id).or_default().push((system_id, orders));
+    }
+    let mut deals = Vec::new();
+    for ((type_id, src_system_id), sell_orders) in &sells {
+        for (dst_system_id, buy_orders) in buys.get(type_id).into_iter().flatten() {
+            if src_system_id == dst_system_id {
+                continue;
+            }
+            if let Some((quantity, cost, income)) = match_orders(sell_orders, buy_orders, fees) {
+                deals.push(Deal {
+                    type_id: *type_id,
+                    src_system_id: *src_system_id,
+                    dst_system_id: *dst_system_id,
+                    quantity,
+                    cost,
+                    income,
+                    volume: None,
+                    jumps: None,
+                    kills: None,
+                });
+            }
+        }
+    }
+    sort(&mut deals, Rank::Profit);
+    deals
+}
+
+/// Sets the cargo volume by the unit volume of the type
+pub fn set_volumes<V>(deals: &mut [Deal], unit_volume: &V)
+    where V: Fn(&i32) -> Option<f32>
+{
+    let mut volumes: HashMap<i32, Option<f32>> = HashMap::new();
+    for deal in deals.iter_mut() {
+        let volume = *volumes.entry(deal.type_id).or_insert_with(|| unit_volume(&deal.type_id));
+        deal.set_volume(volume);
+    }
+}
+
+/// Sets the jumps and the kills of the shortest route between the systems
+pub fn set_routes(deals: &mut [Deal], graph: &Graph, kills: &Kills) {
+    let mut routes: HashMap<(i32, i32), Option<(u32, i32)>> = HashMap::new();
+    for deal in deals.iter_mut() {
+        let route = *routes.entry((deal.src_system_id, deal.dst_system_id)).or_insert_with(|| {
+            graph.find(&RouteQuery::new(deal.src_system_id, deal.dst_system_id, Safety::Shortest), kills)
+                .map(|hops| (hops.len().saturating_sub(1) as u32, hops.iter().map(|hop| hop.kills).sum()))
+        });
+        deal.jumps = route.map(|(jumps, _)| jumps);
+        deal.kills = route.map(|(_, kills)| kills);
+    }
+}
+
+/// The deals without the volume or the route go last
+pub fn sort(deals: &mut [Deal], rank: Rank) {
+    let by_profit = |a: &Deal, b: &Deal| b.get_profit().partial_cmp(&a.get_profit()).unwrap_or(Ordering::Equal);
+    match rank {
+        Rank::Profit => deals.sort_by(by_profit),
+        Rank::Density => deals.sort_by(|a, b| match (a.get_density(), b.get_density()) {
+            (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => by_profit(a, b),
+        }),
+        Rank::Risk => deals.sort_by(|a, b| {
+            let risk = |deal: &Deal| deal.kills.unwrap_or(i32::MAX);
+            risk(a).cmp(&risk(b)).then_with(|| by_profit(a, b))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn order(type_id: i32, system_id: i32, is_buy_order: bool, price: f32, volume: i32) -> Order {
+        Order {
+            order_id: 1,
+            type_id,
+            location_id: system_id as i64 * 10,
+            system_id,
+            volume_total: volume,
+            volume_remain: volume,
+            min_volume: 1,
+            price,
+            is_buy_order,
+            duration: 90,
+            issued: Utc::now(),
+            range: String::from("station"),
+        }
+    }
+
+    #[test]
+    fn fees() {
+        let fees = Fees { sales_tax: 0.05, broker_fee: 0.03 };
+        assert!((fees.net(100.0) - 92.0).abs() < 1e-6);
+        assert_eq!(Some(Rank::Density), Rank::from_name("m3"));
+    }
+
+    #[test]
+    fn deals() {
+        let sources = vec![
+            order(34, 1, false, 10.0, 100),
+            order(34, 1, false, 12.0, 100),
+            order(34, 1, false, 20.0, 100),
+            order(35, 1, false, 10.0, 100),
+        ];
+        let destinations = vec![
+            order(34, 2, true, 15.0, 150),
+            order(34, 2, true, 11.0, 100),
+            order(35, 2, true, 10.0, 100),
+            order(34, 1, true, 100.0, 100),
+            order(34, 2, false, 1.0, 100),
+        ];
+        let fees = Fees { sales_tax: 0.0, broker_fee: 0.0 };
+        let deals = find_deals(&sources, &destinations, &fees);
+        assert_eq!(1, deals.len());
+        let deal = &deals[0];
+        assert_eq!((34, 1, 2), (deal.type_id, deal.src_system_id, deal.dst_system_id));
+        assert_eq!(150, deal.quantity);
+        assert!((deal.cost - 1600.0).abs() < 1e-6);
+        assert!((deal.get_profit() - 650.0).abs() < 1e-6);
+
+        let taxed = find_deals(&sources, &destinations, &Fees { sales_tax: 0.5, broker_fee: 0.0 });
+        assert!(taxed.is_empty());
+    }
+
+    #[test]
+    fn min_volume() {
+        let sources = vec![order(34, 1, false, 10.0, 5)];
+        let mut buy = order(34, 2, true, 20.0, 100);
+        buy.min_volume = 10;
+        let deals = find_deals(&sources, &vec![buy], &Fees::default());
+        assert!(deals.is_empty());
+    }
+
+    #[test]
+    fn routes() {
+        let mut graph = Graph::new();
+        for id in 1..4 {
+            graph.add_system(id, 0.9);
+        }
+        graph.add_link(1, 2);
+        graph.add_link(2, 3);
+        let mut kills = Kills::new();
+        kills.insert(2, 3);
+        kills.insert(3, 1);
+        let sources = vec![order(34, 1, false, 10.0, 10), order(34, 4, false, 10.0, 10)];
+        let mut deals = find_deals(&sources, &vec![order(34, 3, true, 20.0, 10)], &Fees::default());
+        set_routes(&mut deals, &graph, &kills);
+        set_volumes(&mut deals, &|_| Some(0.5));
+        sort(&mut deals, Rank::Risk);
+        assert_eq!(2, deals.len());
+        assert_eq!((1, Some(2), Some(4)), (deals[0].src_system_id, deals[0].jumps, deals[0].kills));
+        assert_eq!((4, None, None), (deals[1].src_system_id, deals[1].jumps, deals[1].kills));
+        assert_eq!(Some(5.0), deals[0].volume);
+    }
+
+    #[test]
+    fn ranks() {
+        let deal = |type_id: i32, profit: f64, volume: Option<f64>, kills: Option<i32>| Deal {
+            type_id, src_system_id: 1, dst_system_id: 2, quantity: 1,
+            cost: 0.0, income: profit, volume, jumps: None, kills,
+        };
+        let mut deals = vec![deal(1, 100.0, Some(100.0), Some(5)), deal(2, 50.0, Some(1.0), None), deal(3, 10.0, None, Some(0))];
+        let ids = |deals: &Vec<Deal>| deals.iter().map(|deal| deal.type_id).collect::<Vec<i32>>();
+        sort(&mut deals, Rank::Profit);
+        assert_eq!(vec![1, 2, 3], ids(&deals));
+        sort(&mut deals, Rank::Density);
+        assert_eq!(vec![2, 1, 3], ids(&deals));
+        sort(&mut deals, Rank::Risk);
+        assert_eq!(vec![3, 1, 2], ids(&deals));
+    }
+}
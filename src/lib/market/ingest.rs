@@ -0,0 +1,228 @@
+use crate::api;
+use crate::models::{Connection, DateTime, QueryResult};
+use crate::models::market::{MarketOrder, NewOrderEvent};
+
+use std::collections::HashMap;
+
+/// Rows written at once, the database thread serves the other messages between the batches
+pub const BATCH: usize = 1000;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Lifecycle {
+    New,
+    /// The price or the remaining volume changed
+    Updated,
+    /// Vanished being the best order of its side at the location
+    Filled,
+    /// Vanished otherwise or expired
+    Cancelled,
+}
+impl Lifecycle {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Lifecycle::New => "new",
+            Lifecycle::Updated => "updated",
+            Lifecycle::Filled => "filled",
+            Lifecycle::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Counts of the events of the snapshot
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Summary {
+    pub orders: usize,
+    pub new: usize,
+    pub updated: usize,
+    pub filled: usize,
+    pub cancelled: usize,
+}
+impl Summary {
+    fn add(&mut self, lifecycle: Lifecycle) {
+        match lifecycle {
+            Lifecycle::New => self.new += 1,
+            Lifecycle::Updated => self.updated += 1,
+            Lifecycle::Filled => self.filled += 1,
+            Lifecycle::Cancelled => self.cancelled += 1,
+        }
+    }
+}
+
+fn event(order: &MarketOrder, lifecycle: Lifecycle, price: f32, traded: i32, now: &DateTime) -> NewOrderEvent {
+    NewOrderEvent {
+        order_id: order.order_id,
+        region_id: order.region_id,
+        type_id: order.type_id,
+        is_buy_order: order.is_buy_order,
+        event: String::from(lifecycle.get_name()),
+        price,
+        traded,
+        event_time: *now,
+    }
+}
+
+/// The best price of the each type, location and side
+fn get_best(orders: &Vec<MarketOrder>) -> HashMap<(i32, i64, bool), f32> {
+    let mut best: HashMap<(i32, i64, bool), f32> = HashMap::new();
+    for order in orders {
+        let price = best.entry((order.type_id, order.location_id, order.is_buy_order)).or_insert(order.price);
+        *price = if order.is_buy_order { price.max(order.price) } else { price.min(order.price) };
+    }
+    best
+}
+
+/// The new snapshot of the region and the lifecycle events since the previous one
+pub fn diff(region_id: &i32, previous: &Vec<MarketOrder>, current: &Vec<api::Order>, now: &DateTime) -> (Vec<MarketOrder>, Vec<NewOrderEvent>) {
+    let known: HashMap<i64, &MarketOrder> = previous.iter().map(|order| (order.order_id, order)).collect();
+    let mut orders = Vec::new();
+    let mut events = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for order in current {
+        if !seen.insert(order.order_id) {
+            continue;
+        }
+        match known.get(&order.order_id) {
+            Some(before) => {
+                let stored = MarketOrder::new(order, region_id, &before.first_seen, now);
+                if before.price != stored.price || before.volume_remain != stored.volume_remain {
+                    let traded = (before.volume_remain - stored.volume_remain).max(0);
+                    events.push(event(&stored, Lifecycle::Updated, stored.price, traded, now));
+                }
+                orders.push(stored);
+            },
+            None => {
+                let stored = MarketOrder::new(order, region_id, now, now);
+                events.push(event(&stored, Lifecycle::New, stored.price, 0, now));
+                orders.push(stored);
+            }
+        }
+    }
+    let best = get_best(previous);
+    for before in previous.iter().filter(|order| !seen.contains(&order.order_id)) {
+        let key = (before.type_id, before.location_id, before.is_buy_order);
+        let is_best = best.get(&key).map(|price| *price == before.price).unwrap_or(false);
+        if is_best && !before.is_expired(now) {
+            events.push(event(before, Lifecycle::Filled, before.price, before.volume_remain, now));
+        } else {
+            events.push(event(before, Lifecycle::Cancelled, before.price, 0, now));
+        }
+    }
+    (orders, events)
+}
+
+/// The snapshot of the region written by the batches,
+/// the orders missing in it are deleted after the last batch
+pub struct Snapshot {
+    pub region_id: i32,
+    pub now: DateTime,
+    pub summary: Summary,
+    orders: Vec<MarketOrder>,
+    events: Vec<NewOrderEvent>,
+}
+impl Snapshot {
+    pub fn new(conn: &Connection, region_id: &i32, current: &Vec<api::Order>, now: &DateTime) -> QueryResult<Self> {
+        let previous = MarketOrder::load_region(conn, region_id)?;
+        let (orders, events) = diff(region_id, &previous, current, now);
+        let mut summary = Summary { orders: orders.len(), ..Summary::default() };
+        for event in &events {
+            match event.event.as_ref() {
+                "new" => summary.add(Lifecycle::New),
+                "updated" => summary.add(Lifecycle::Updated),
+                "filled" => summary.add(Lifecycle::Filled),
+                _ => summary.add(Lifecycle::Cancelled),
+            }
+        }
+        Ok(Self { region_id: *region_id, now: *now, summary, orders, events })
+    }
+
+    /// Writes the next batch, true when the snapshot is complete
+    pub fn step(&mut self, conn: &Connection) -> QueryResult<bool> {
+        if self.orders.is_empty() && self.events.is_empty() {
+            MarketOrder::delete_unseen(conn, &self.region_id, &self.now)?;
+            return Ok(true);
+        }
+        let orders = self.orders.split_off(self.orders.len().saturating_sub(BATCH));
+        let events = self.events.split_off(self.events.len().saturating_sub(BATCH));
+        MarketOrder::save_batch(conn, &orders, &events)?;
+        Ok(false)
+    }
+}
+
+/// Stores the snapshot of the region at once, the previous one is replaced
+pub fn save(conn: &Connection, region_id: &i32, current: &Vec<api::Order>, now: &DateTime) -> QueryResult<Summary> {
+    let mut snapshot = Snapshot::new(conn, region_id, current, now)?;
+    while !snapshot.step(conn)? {}
+    Ok(snapshot.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    fn order(order_id: i64, is_buy_order: bool, price: f32, volume_remain: i32) -> api::Order {
+        api::Order {
+            order_id,
+            type_id: 34,
+            location_id: 60003760,
+            system_id: 30000142,
+            volume_total: 100,
+            volume_remain,
+            min_volume: 1,
+            price,
+            is_buy_order,
+            duration: 90,
+            issued: Utc.ymd(2019, 11, 1).and_hms(0, 0, 0),
+            range: String::from("station"),
+        }
+    }
+
+    fn time(day: u32) -> DateTime {
+        NaiveDate::from_ymd(2019, 11, day).and_hms(12, 0, 0)
+    }
+
+    fn names(events: &[NewOrderEvent]) -> Vec<(i64, String, i32)> {
+        let mut names: Vec<(i64, String, i32)> = events.iter().map(|event| (event.order_id, event.event.clone(), event.traded)).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn lifecycle() {
+        let first = vec![
+            order(1, false, 10.0, 100),
+            order(2, false, 11.0, 100),
+            order(3, false, 12.0, 100),
+            order(4, true, 9.0, 100),
+        ];
+        let (previous, events) = diff(&10000002, &Vec::new(), &first, &time(2));
+        assert_eq!(4, previous.len());
+        assert!(events.iter().all(|event| event.event == "new"));
+
+        let second = vec![
+            order(2, false, 11.0, 60),
+            order(4, true, 9.5, 100),
+            order(5, false, 13.0, 100),
+            order(5, false, 13.0, 100),
+        ];
+        let (orders, events) = diff(&10000002, &previous, &second, &time(3));
+        assert_eq!(3, orders.len());
+        assert_eq!(vec![
+            (1, String::from("filled"), 100),
+            (2, String::from("updated"), 40),
+            (3, String::from("cancelled"), 0),
+            (4, String::from("updated"), 0),
+            (5, String::from("new"), 0),
+        ], names(&events));
+        assert_eq!(time(2), orders.iter().find(|order| order.order_id == 2).unwrap().first_seen);
+        assert!(orders.iter().all(|order| order.last_seen == time(3)));
+    }
+
+    #[test]
+    fn expired() {
+        let (previous, _) = diff(&10000002, &Vec::new(), &vec![order(1, false, 10.0, 100)], &time(2));
+        let later = Utc.ymd(2020, 3, 1).and_hms(0, 0, 0).naive_utc();
+        let (_, events) = diff(&10000002, &previous, &Vec::new(), &later);
+        assert_eq!(vec![(1, String::from("cancelled"), 0)], names(&events));
+    }
+}
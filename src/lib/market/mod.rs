@@ -0,0 +1,53 @@
+use crate::api::{Order, Orders, OrderType};
+
+pub mod arbitrage;
+pub mod ingest;
+
+/// Market orders are loaded by region, the system narrows them down
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Location {
+    System { system_id: i32, region_id: i32 },
+    Region(i32),
+}
+impl Location {
+    pub fn get_region_id(&self) -> i32 {
+        match self {
+            Location::System { region_id, .. } => *region_id,
+            Location::Region(region_id) => *region_id,
+        }
+    }
+
+    pub fn contains(&self, order: &Order) -> bool {
+        self.contains_system(&order.system_id)
+    }
+
+    /// The system of the region is not checked
+    pub fn contains_system(&self, id: &i32) -> bool {
+        match self {
+            Location::System { system_id, .. } => id == system_id,
+            Location::Region(_) => true,
+        }
+    }
+}
+
+/// Orders of the locations, all types are loaded when the list of types is empty
+pub fn load_orders(locations: &[Location], order_type: &OrderType, types: &Vec<i32>) -> Orders {
+    let mut regions: Vec<i32> = locations.iter().map(Location::get_region_id).collect();
+    regions.sort();
+    regions.dedup();
+    let mut orders = Orders::new();
+    for region_id in &regions {
+        let mut loaded = Orders::new();
+        if types.is_empty() {
+            loaded.append(&mut Order::load(region_id, *order_type, None).unwrap_or_default());
+        } else {
+            for type_id in types {
+                loaded.append(&mut Order::load(region_id, *order_type, Some(*type_id)).unwrap_or_default());
+            }
+        }
+        orders.extend(loaded.into_iter().filter(|order| {
+            locations.iter().any(|location| location.get_region_id() == *region_id && location.contains(order))
+        }));
+    }
+    orders
+}
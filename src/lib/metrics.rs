@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Write};
+use std::sync::Mutex;
+
+/// Upper bounds of the latency buckets in seconds
+pub const BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+const HELP: &[(&str, &str)] = &[
+    ("zkb_killmails_ingested_total", "Killmails saved to the database, rate()*60 gives the killmails per minute"),
+    ("zkb_redisq_polls_total", "RedisQ polls by the result: killmail, empty or failed"),
+    ("zkb_api_requests_total", "ESI and zKillboard requests by the host and the HTTP status, error when no response was received"),
+    ("zkb_report_load_seconds", "Time the reports wait for the database thread by the query category"),
+    ("zkb_cache_requests_total", "Provider cache lookups by the cache and the result: hit or miss"),
+    ("zkb_queue_length", "Messages waiting in the queue"),
+    ("zkb_database_size_bytes", "Size of the database file"),
+    ("zkb_http_requests_total", "Served pages by the route pattern"),
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; BUCKETS.len()];
+        }
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<String, BTreeMap<String, u64>>,
+    histograms: BTreeMap<String, BTreeMap<String, Histogram>>,
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+/// The metric computed by the scrape, e.g. the queue lengths
+#[derive(Debug, PartialEq, Clone)]
+pub struct Family {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub samples: Vec<(String, f64)>,
+}
+impl Family {
+    pub fn gauge(name: &'static str) -> Self {
+        Self { name, kind: "gauge", samples: Vec::new() }
+    }
+
+    pub fn counter(name: &'static str) -> Self {
+        Self { name, kind: "counter", samples: Vec::new() }
+    }
+
+    pub fn add(mut self, labels: &[(&str, &str)], value: f64) -> Self {
+        self.samples.push((get_labels(labels), value));
+        self
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', r#"\""#).replace('\n', r"\n")
+}
+
+pub fn get_labels(labels: &[(&str, &str)]) -> String {
+    labels.iter().map(|(name, value)| format!(r#"{}="{}""#, name, escape(value))).collect::<Vec<_>>().join(",")
+}
+
+/// Takes the name of the enum variant without formatting its content
+pub fn get_variant<T: Debug>(value: &T) -> String {
+    struct Variant(String);
+    impl Write for Variant {
+        fn write_str(&mut self, text: &str) -> fmt::Result {
+            for c in text.chars() {
+                if !c.is_alphanumeric() && c != '_' {
+                    return Err(fmt::Error);
+                }
+                self.0.push(c);
+            }
+            Ok(())
+        }
+    }
+    let mut variant = Variant(String::new());
+    let _ = write!(variant, "{:?}", value);
+    variant.0
+}
+
+pub fn increment(name: &str, labels: &[(&str, &str)]) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        let counter = registry.counters.entry(name.to_string()).or_default().entry(get_labels(labels)).or_insert(0);
+        *counter += 1;
+    }
+}
+
+pub fn observe(name: &str, labels: &[(&str, &str)], seconds: f64) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        registry.histograms.entry(name.to_string()).or_default().entry(get_labels(labels)).or_default().observe(seconds);
+    }
+}
+
+pub fn cache(name: &str, hit: bool) {
+    increment("zkb_cache_requests_total", &[("cache", name), ("result", if hit { "hit" } else { "miss" })]);
+}
+
+fn get_sample(name: &str, labels: &str) -> String {
+    if labels.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}{{{}}}", name, labels)
+    }
+}
+
+fn write_header(output: &mut String, name: &str, kind: &str) {
+    let help = HELP.iter().find(|(known, _)| *known == name).map(|(_, help)| *help).unwrap_or(name);
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, kind);
+}
+
+fn join(labels: &str, more: &str) -> String {
+    if labels.is_empty() { more.to_string() } else { format!("{},{}", labels, more) }
+}
+
+/// The Prometheus text format of the collected metrics and the given families
+pub fn render(families: &[Family]) -> String {
+    let mut output = String::new();
+    if let Ok(registry) = REGISTRY.lock() {
+        for (name, samples) in &registry.counters {
+            write_header(&mut output, name, "counter");
+            for (labels, value) in samples {
+                let _ = writeln!(output, "{} {}", get_sample(name, labels), value);
+            }
+        }
+        for (name, samples) in &registry.histograms {
+            write_header(&mut output, name, "histogram");
+            let bucket = format!("{}_bucket", name);
+            for (labels, histogram) in samples {
+                for (count, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                    let _ = writeln!(output, "{} {}", get_sample(&bucket, &join(labels, &format!(r#"le="{}""#, bound))), count);
+                }
+                let _ = writeln!(output, "{} {}", get_sample(&bucket, &join(labels, r#"le="+Inf""#)), histogram.count);
+                let _ = writeln!(output, "{} {}", get_sample(&format!("{}_sum", name), labels), histogram.sum);
+                let _ = writeln!(output, "{} {}", get_sample(&format!("{}_count", name), labels), histogram.count);
+            }
+        }
+    }
+    for family in families {
+        write_header(&mut output, family.name, family.kind);
+        for (labels, value) in &family.samples {
+            let _ = writeln!(output, "{} {}", get_sample(family.name, labels), value);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants() {
+        assert_eq!("Some", get_variant(&Some(vec![1, 2, 3])));
+        assert_eq!("None", get_variant(&None::<i32>));
+        assert_eq!("Ok", get_variant(&Ok::<(i32, i32), ()>((1, 2))));
+        assert_eq!(r#"queue="database",path="a\"b""#, get_labels(&[("queue", "database"), ("path", "a\"b")]));
+    }
+
+    #[test]
+    fn text_format() {
+        increment("test_polls_total", &[("result", "empty")]);
+        increment("test_polls_total", &[("result", "empty")]);
+        observe("test_load_seconds", &[("category", "Killmail")], 0.25);
+        observe("test_load_seconds", &[("category", "Killmail")], 3.0);
+        let output = render(&[Family::gauge("zkb_queue_length").add(&[("queue", "resolver")], 7.0)]);
+        assert!(output.contains("# TYPE test_polls_total counter\n"));
+        assert!(output.contains("test_polls_total{result=\"empty\"} 2\n"));
+        assert!(output.contains("# TYPE test_load_seconds histogram\n"));
+        assert!(output.contains("test_load_seconds_bucket{category=\"Killmail\",le=\"0.1\"} 0\n"));
+        assert!(output.contains("test_load_seconds_bucket{category=\"Killmail\",le=\"0.25\"} 1\n"));
+        assert!(output.contains("test_load_seconds_bucket{category=\"Killmail\",le=\"5\"} 2\n"));
+        assert!(output.contains("test_load_seconds_bucket{category=\"Killmail\",le=\"+Inf\"} 2\n"));
+        assert!(output.contains("test_load_seconds_sum{category=\"Killmail\"} 3.25\n"));
+        assert!(output.contains("test_load_seconds_count{category=\"Killmail\"} 2\n"));
+        assert!(output.contains("# HELP zkb_queue_length Messages waiting in the queue\n"));
+        assert!(output.contains("zkb_queue_length{queue=\"resolver\"} 7\n"));
+    }
+}
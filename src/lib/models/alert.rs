@@ -0,0 +1,29 @@
+use crate::schema::alerts;
+use super::{Integer, DateTime, Connection, QueryResult};
+
+/// Killmail matched by the rule
+#[derive(Queryable, Debug, PartialEq, Clone)]
+pub struct Alert {
+    pub alert_id: Integer,
+    pub rule_id: Integer,
+    pub killmail_id: Integer,
+    pub alert_time: DateTime,
+}
+impl Alert {
+    pub fn save(conn: &Connection, rule_id: &Integer, killmail_id: &Integer) -> QueryResult<usize>  {
+        use diesel::prelude::*;
+        let alert_time = chrono::Utc::now().naive_utc();
+        diesel::insert_into(alerts::table)
+            .values((alerts::rule_id.eq(rule_id), alerts::killmail_id.eq(killmail_id), alerts::alert_time.eq(alert_time)))
+            .execute(conn)
+    }
+
+    /// The latest alerts first
+    pub fn load_last(conn: &Connection, limit: &Integer) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        alerts::table
+            .order(alerts::alert_id.desc())
+            .limit(*limit as i64)
+            .load(conn)
+    }
+}
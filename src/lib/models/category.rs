@@ -16,4 +16,15 @@ impl Category {
             .select(table::category_id)
             .load(conn)
     }
+
+    /// The id of the category with the exact name, the missing one is created
+    pub fn get_or_create(conn: &Connection, name: &str) -> QueryResult<Integer> {
+        use diesel::prelude::*;
+        use crate::schema::categories::dsl as table;
+        diesel::insert_into(table::categories).values(table::category_name.eq(name)).execute(conn)?;
+        table::categories
+            .filter(table::category_name.eq(name))
+            .select(table::category_id)
+            .first(conn)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,131 @@
+use crate::api;
+use crate::api::stats::Entity;
+use crate::schema::{killmails, victims, attackers, items};
+use super::participant::Participant;
+use super::{Integer, OptInteger, Float, Bool, DateTime, Connection, QueryResult};
+
+use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+
+type KillmailRow = (Integer, DateTime, Integer, OptInteger, OptInteger);
+type VictimRow = (Integer, Integer, Integer, OptInteger, OptInteger, OptInteger, OptInteger, Option<f64>, Option<f64>, Option<f64>);
+type AttackerRow = (Integer, Float, Bool, Integer, OptInteger, OptInteger, OptInteger, OptInteger, OptInteger, OptInteger);
+type ItemRow = (Integer, Integer, Integer, Integer, OptInteger, OptInteger, OptInteger);
+
+/// Ids of the killmails from the start up to the end, optionally involving the entity
+pub fn load_ids(conn: &Connection, entity: &Option<Entity>, start: &DateTime, end: &DateTime) -> QueryResult<Vec<Integer>> {
+    use diesel::prelude::*;
+    match entity {
+        Some(entity) => Participant::load_ids_between(conn, entity, start, end),
+        None => killmails::table
+            .filter(killmails::killmail_time.ge(start))
+            .filter(killmails::killmail_time.lt(end))
+            .select(killmails::killmail_id)
+            .order(killmails::killmail_id)
+            .load(conn),
+    }
+}
+
+/// Puts the items into their containers, the container is the index among the items of the killmail
+fn nest(rows: Vec<(OptInteger, api::Item)>) -> Vec<api::Item> {
+    let mut items: Vec<api::Item> = Vec::new();
+    let mut positions: HashMap<Integer, usize> = HashMap::new();
+    for (index, (container, item)) in rows.into_iter().enumerate() {
+        match container.and_then(|container| positions.get(&container)) {
+            Some(position) => items[*position].items.get_or_insert(Vec::new()).push(item),
+            None => {
+                positions.insert(index as Integer, items.len());
+                items.push(item);
+            }
+        }
+    }
+    items
+}
+
+/// Killmails in the ESI shape, the killmails saved before the structure was stored have no position and flat items
+pub fn load(conn: &Connection, ids: &Vec<Integer>) -> QueryResult<Vec<api::Killmail>> {
+    use diesel::prelude::*;
+
+    let mut victims: HashMap<Integer, api::Victim> = victims::table
+        .filter(victims::killmail_id.eq_any(ids))
+        .select((victims::killmail_id, victims::ship_type_id, victims::damage_taken,
+                 victims::alliance_id, victims::character_id, victims::corporation_id, victims::faction_id,
+                 victims::position_x, victims::position_y, victims::position_z))
+        .load::<VictimRow>(conn)?
+        .into_iter()
+        .map(|row| (row.0, api::Victim {
+            ship_type_id: row.1,
+            damage_taken: row.2,
+            alliance_id: row.3,
+            character_id: row.4,
+            corporation_id: row.5,
+            faction_id: row.6,
+            items: None,
+            position: match (row.7, row.8, row.9) {
+                (Some(x), Some(y), Some(z)) => Some(api::Position { x, y, z }),
+                _ => None,
+            },
+        }))
+        .collect();
+
+    let mut attackers_map: HashMap<Integer, Vec<api::Attacker>> = HashMap::new();
+    let rows = attackers::table
+        .filter(attackers::killmail_id.eq_any(ids))
+        .select((attackers::killmail_id, attackers::security_status, attackers::final_blow, attackers::damage_done,
+                 attackers::ship_type_id, attackers::alliance_id, attackers::character_id, attackers::corporation_id,
+                 attackers::faction_id, attackers::weapon_type_id))
+        .order(attackers::attacker_id)
+        .load::<AttackerRow>(conn)?;
+    for row in rows {
+        attackers_map.entry(row.0).or_default().push(api::Attacker {
+            security_status: row.1,
+            final_blow: row.2,
+            damage_done: row.3,
+            ship_type_id: row.4,
+            alliance_id: row.5,
+            character_id: row.6,
+            corporation_id: row.7,
+            faction_id: row.8,
+            weapon_type_id: row.9,
+        });
+    }
+
+    let rows = items::table
+        .filter(items::killmail_id.eq_any(ids))
+        .select((items::killmail_id, items::item_type_id, items::singleton, items::flag,
+                 items::quantity_destroyed, items::quantity_dropped, items::container))
+        .order(items::item_id)
+        .load::<ItemRow>(conn)?;
+    let mut items_map: HashMap<Integer, Vec<(OptInteger, api::Item)>> = HashMap::new();
+    for row in rows {
+        items_map.entry(row.0).or_default().push((row.6, api::Item {
+            item_type_id: row.1,
+            singleton: row.2,
+            flag: row.3,
+            quantity_destroyed: row.4,
+            quantity_dropped: row.5,
+            items: None,
+        }));
+    }
+    for (killmail_id, rows) in items_map {
+        if let Some(victim) = victims.get_mut(&killmail_id) {
+            victim.items = Some(nest(rows));
+        }
+    }
+
+    let rows = killmails::table
+        .filter(killmails::killmail_id.eq_any(ids))
+        .order(killmails::killmail_id)
+        .load::<KillmailRow>(conn)?;
+    Ok(rows.into_iter()
+        .filter_map(|row| victims.remove(&row.0).map(|victim| api::Killmail {
+            killmail_id: row.0,
+            killmail_time: Utc.from_utc_datetime(&row.1),
+            solar_system_id: row.2,
+            moon_id: row.3,
+            war_id: row.4,
+            victim,
+            attackers: attackers_map.remove(&row.0).unwrap_or_default(),
+        }))
+        .collect())
+}
@@ -13,6 +13,8 @@ pub struct Item {
     pub flag: Integer,
     pub quantity_destroyed: OptInteger,
     pub quantity_dropped: OptInteger,
+    /// Index of the container among the items of the killmail
+    pub container: OptInteger,
 }
 
 impl From<&api::Item> for Item{
@@ -24,6 +26,7 @@ impl From<&api::Item> for Item{
             flag: src.flag,
             quantity_destroyed: src.quantity_destroyed,
             quantity_dropped: src.quantity_dropped,
+            container: None,
         }
     }
 }
@@ -62,5 +65,11 @@ impl ItemNamed {
         use diesel::prelude::*;
         named_items::table.filter(named_items::killmail_id.eq(id)).load(conn)
     }
+
+    /// Items of the killmails by the single query
+    pub fn load_all(conn: &Connection, ids: &Vec<Integer>) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        named_items::table.filter(named_items::killmail_id.eq_any(ids)).order(named_items::item_id).load(conn)
+    }
 }
 
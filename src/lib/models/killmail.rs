@@ -76,6 +76,14 @@ impl KillmailNamed {
         named_killmails::table.filter(named_killmails::killmail_id.eq(id)).first(conn)
     }
 
+    pub fn load_ids(conn: &Connection, ids: &Vec<Integer>) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        named_killmails::table
+            .filter(named_killmails::killmail_id.eq_any(ids))
+            .order(named_killmails::killmail_time.desc())
+            .load(conn)
+    }
+
     pub fn load_system_history(conn: &Connection, system_id: &Integer, minutes: &Integer) -> QueryResult<Vec<Self>> {
         use diesel::prelude::*;
         let start = DateTime::from((Utc::now() - Duration::minutes(*minutes as i64)).naive_utc());
@@ -342,5 +350,71 @@ impl KillmailNamed {
             .order(named_killmails::killmail_time.desc())
             .load(conn)
     }
+
+    pub fn load_system_ids_for_last_minutes(conn: &Connection, minutes: &Integer) -> QueryResult<Vec<Integer>> {
+        use diesel::prelude::*;
+
+        let start = DateTime::from((Utc::now() - Duration::minutes(*minutes as i64)).naive_utc());
+        info!("Load killmails systems after {}", &start);
+        killmails::table
+            .filter(killmails::killmail_time.gt(start))
+            .select(killmails::solar_system_id)
+            .load(conn)
+    }
 }
 
+#[derive(Queryable, Debug, PartialEq, Clone)]
+pub struct SystemKill {
+    pub killmail_id: Integer,
+    pub killmail_time: DateTime,
+    pub system_id: Integer,
+    pub ship_id: OptInteger,
+}
+impl SystemKill {
+    /// Loads kills with attackers ships for the whole set of systems by the single query,
+    /// the kills without the stored attackers have no ship
+    pub fn load(conn: &Connection, ids: &Vec<Integer>, minutes: &Integer) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+
+        let start = DateTime::from((Utc::now() - Duration::minutes(*minutes as i64)).naive_utc());
+        info!("Load killmails for {} systems after {}", ids.len(), &start);
+        killmails::table.left_join(attackers::table.on(attackers::killmail_id.eq(killmails::killmail_id)))
+            .filter(killmails::killmail_time.gt(start))
+            .filter(killmails::solar_system_id.eq_any(ids))
+            .select((
+                killmails::killmail_id,
+                killmails::killmail_time,
+                killmails::solar_system_id,
+                attackers::ship_type_id.nullable(),
+             ))
+            .order(killmails::killmail_time.desc())
+            .load(conn)
+    }
+}
+
+#[derive(Queryable, Debug, PartialEq, Clone)]
+pub struct SystemLoss {
+    pub killmail_id: Integer,
+    pub killmail_time: DateTime,
+    pub system_id: Integer,
+    pub ship_id: Integer,
+}
+impl SystemLoss {
+    /// Loads victims ships for the whole set of systems by the single query
+    pub fn load(conn: &Connection, ids: &Vec<Integer>, minutes: &Integer) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+
+        let start = DateTime::from((Utc::now() - Duration::minutes(*minutes as i64)).naive_utc());
+        info!("Load losses for {} systems after {}", ids.len(), &start);
+        killmails::table.inner_join(victims::table.on(victims::killmail_id.eq(killmails::killmail_id)))
+            .filter(killmails::killmail_time.gt(start))
+            .filter(killmails::solar_system_id.eq_any(ids))
+            .select((
+                killmails::killmail_id,
+                killmails::killmail_time,
+                killmails::solar_system_id,
+                victims::ship_type_id,
+             ))
+            .load(conn)
+    }
+}
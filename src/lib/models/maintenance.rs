@@ -0,0 +1,137 @@
+use crate::schema::{alerts, attackers, items, killmails, objects, order_events, victims};
+use super::{Connection, DateTime, QueryResult};
+
+use diesel::sql_types::Text;
+
+/// The migrations embedded into the binary, the directory names under migrations/
+pub const MIGRATIONS: &[&str] = &[
+    "2019-10-04-101951_hashes",
+    "2019-10-04-102000_killamils",
+    "2019-11-01-120000_stations",
+    "2019-11-15-120000_watchlists",
+    "2019-11-22-120000_prices",
+    "2019-11-29-120000_market",
+    "2019-12-06-120000_structure",
+];
+
+#[derive(QueryableByName, Debug)]
+struct IntegrityCheck {
+    #[sql_type = "Text"]
+    integrity_check: String,
+}
+
+#[derive(QueryableByName, Debug)]
+struct MigrationVersion {
+    #[sql_type = "Text"]
+    version: String,
+}
+
+/// Sizes of the main tables and the stored period
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stats {
+    pub killmails: i64,
+    pub objects: i64,
+    pub oldest: Option<DateTime>,
+    pub latest: Option<DateTime>,
+}
+
+/// Rows deleted by the prune
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Pruned {
+    pub killmails: usize,
+    pub victims: usize,
+    pub attackers: usize,
+    pub items: usize,
+    pub alerts: usize,
+    pub order_events: usize,
+}
+
+/// The problems found by SQLite, the empty list when the database is fine
+pub fn integrity(conn: &Connection) -> QueryResult<Vec<String>> {
+    use diesel::prelude::*;
+    let rows: Vec<IntegrityCheck> = diesel::sql_query("PRAGMA integrity_check").load(conn)?;
+    Ok(rows.into_iter().map(|row| row.integrity_check).filter(|row| row != "ok").collect())
+}
+
+/// Versions of the applied migrations, the oldest first
+pub fn migrations(conn: &Connection) -> QueryResult<Vec<String>> {
+    use diesel::prelude::*;
+    let rows: Vec<MigrationVersion> = diesel::sql_query("SELECT version FROM __diesel_schema_migrations ORDER BY version").load(conn)?;
+    Ok(rows.into_iter().map(|row| row.version).collect())
+}
+
+/// The version diesel stores for the migration directory: the digits of its date part
+pub fn get_version(migration: &str) -> String {
+    migration.split('_').next().unwrap_or_default().chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// The embedded migrations which are not applied yet
+pub fn pending(conn: &Connection) -> QueryResult<Vec<&'static str>> {
+    let applied = migrations(conn)?;
+    Ok(MIGRATIONS.iter().cloned().filter(|migration| !applied.contains(&get_version(migration))).collect())
+}
+
+pub fn stats(conn: &Connection) -> QueryResult<Stats> {
+    use diesel::prelude::*;
+    use diesel::dsl::count_star;
+    Ok(Stats {
+        killmails: killmails::table.select(count_star()).first(conn)?,
+        objects: objects::table.select(count_star()).first(conn)?,
+        oldest: killmails::table.select(diesel::dsl::min(killmails::killmail_time)).first(conn)?,
+        latest: killmails::table.select(diesel::dsl::max(killmails::killmail_time)).first(conn)?,
+    })
+}
+
+/// Killmails older than the time
+pub fn count_before(conn: &Connection, before: &DateTime) -> QueryResult<i64> {
+    use diesel::prelude::*;
+    use diesel::dsl::count_star;
+    killmails::table.filter(killmails::killmail_time.lt(before)).select(count_star()).first(conn)
+}
+
+/// Deletes the killmails older than the time with their participants, items and alerts,
+/// the market events are kept for the same period
+pub fn prune(conn: &Connection, before: &DateTime) -> QueryResult<Pruned> {
+    use diesel::prelude::*;
+    use diesel::connection::Connection;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let old = || killmails::table.filter(killmails::killmail_time.lt(before)).select(killmails::killmail_id);
+        Ok(Pruned {
+            victims: diesel::delete(victims::table.filter(victims::killmail_id.eq_any(old()))).execute(conn)?,
+            attackers: diesel::delete(attackers::table.filter(attackers::killmail_id.eq_any(old()))).execute(conn)?,
+            items: diesel::delete(items::table.filter(items::killmail_id.eq_any(old()))).execute(conn)?,
+            alerts: diesel::delete(alerts::table.filter(alerts::killmail_id.eq_any(old()))).execute(conn)?,
+            killmails: diesel::delete(killmails::table.filter(killmails::killmail_time.lt(before))).execute(conn)?,
+            order_events: diesel::delete(order_events::table.filter(order_events::event_time.lt(before))).execute(conn)?,
+        })
+    })
+}
+
+/// Returns the freed pages to the file system
+pub fn vacuum(conn: &Connection) -> QueryResult<()> {
+    use diesel::prelude::*;
+    diesel::sql_query("VACUUM").execute(conn).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        assert_eq!("20191004101951", get_version("2019-10-04-101951_hashes"));
+        assert_eq!("20191206120000", get_version(MIGRATIONS.last().unwrap()));
+    }
+
+    #[test]
+    fn embedded() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        let mut names: Vec<String> = std::fs::read_dir(path).unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("up.sql").exists())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(MIGRATIONS.to_vec(), names);
+    }
+}
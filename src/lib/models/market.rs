@@ -0,0 +1,163 @@
+use crate::api;
+use crate::schema::{market_orders, order_events};
+use super::{Integer, Float, Bool, DateTime, Connection, QueryResult};
+
+use chrono::{TimeZone, Utc};
+
+/// The order as it was seen by the last snapshot of the region
+#[derive(Queryable, Insertable, Debug, PartialEq, Clone)]
+#[table_name = "market_orders"]
+pub struct MarketOrder {
+    pub order_id: i64,
+    pub region_id: Integer,
+    pub type_id: Integer,
+    pub location_id: i64,
+    pub system_id: Integer,
+    pub is_buy_order: Bool,
+    pub price: Float,
+    pub volume_total: Integer,
+    pub volume_remain: Integer,
+    pub min_volume: Integer,
+    pub duration: Integer,
+    pub issued: DateTime,
+    pub order_range: String,
+    pub first_seen: DateTime,
+    pub last_seen: DateTime,
+}
+impl MarketOrder {
+    pub fn new(order: &api::Order, region_id: &Integer, first_seen: &DateTime, now: &DateTime) -> Self {
+        Self {
+            order_id: order.order_id,
+            region_id: *region_id,
+            type_id: order.type_id,
+            location_id: order.location_id,
+            system_id: order.system_id,
+            is_buy_order: order.is_buy_order,
+            price: order.price,
+            volume_total: order.volume_total,
+            volume_remain: order.volume_remain,
+            min_volume: order.min_volume,
+            duration: order.duration,
+            issued: order.issued.naive_utc(),
+            order_range: order.range.clone(),
+            first_seen: *first_seen,
+            last_seen: *now,
+        }
+    }
+
+    pub fn to_api(&self) -> api::Order {
+        api::Order {
+            order_id: self.order_id,
+            type_id: self.type_id,
+            location_id: self.location_id,
+            system_id: self.system_id,
+            volume_total: self.volume_total,
+            volume_remain: self.volume_remain,
+            min_volume: self.min_volume,
+            price: self.price,
+            is_buy_order: self.is_buy_order,
+            duration: self.duration,
+            issued: Utc.from_utc_datetime(&self.issued),
+            range: self.order_range.clone(),
+        }
+    }
+
+    /// The order is not listed after the duration in days
+    pub fn is_expired(&self, now: &DateTime) -> bool {
+        self.issued + chrono::Duration::days(self.duration as i64) <= *now
+    }
+
+    pub fn load_region(conn: &Connection, region_id: &Integer) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        market_orders::table
+            .filter(market_orders::region_id.eq(region_id))
+            .load(conn)
+    }
+
+    /// Orders of the regions, all types when the list is empty
+    pub fn load(conn: &Connection, regions: &Vec<Integer>, types: &Vec<Integer>, is_buy: &Bool) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        let query = market_orders::table
+            .filter(market_orders::region_id.eq_any(regions))
+            .filter(market_orders::is_buy_order.eq(is_buy));
+        if types.is_empty() {
+            query.load(conn)
+        } else {
+            query.filter(market_orders::type_id.eq_any(types)).load(conn)
+        }
+    }
+
+    /// Regions with the stored snapshot
+    pub fn load_regions(conn: &Connection) -> QueryResult<Vec<Integer>> {
+        use diesel::prelude::*;
+        market_orders::table
+            .select(market_orders::region_id)
+            .distinct()
+            .load(conn)
+    }
+
+    /// Stores the part of the snapshot, the orders seen before are replaced, and appends the lifecycle events
+    pub fn save_batch(conn: &Connection, orders: &[Self], events: &[NewOrderEvent]) -> QueryResult<usize> {
+        use diesel::prelude::*;
+        use diesel::connection::Connection;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let mut count = 0;
+            for chunk in orders.chunks(500) {
+                count += diesel::replace_into(market_orders::table).values(chunk).execute(conn)?;
+            }
+            for chunk in events.chunks(500) {
+                diesel::insert_into(order_events::table).values(chunk).execute(conn)?;
+            }
+            Ok(count)
+        })
+    }
+
+    /// Deletes the orders of the region that the snapshot taken at the time did not list
+    pub fn delete_unseen(conn: &Connection, region_id: &Integer, now: &DateTime) -> QueryResult<usize> {
+        use diesel::prelude::*;
+        diesel::delete(market_orders::table
+            .filter(market_orders::region_id.eq(region_id))
+            .filter(market_orders::last_seen.lt(now)))
+            .execute(conn)
+    }
+}
+
+/// Change of the order between the snapshots
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[table_name = "order_events"]
+pub struct NewOrderEvent {
+    pub order_id: i64,
+    pub region_id: Integer,
+    pub type_id: Integer,
+    pub is_buy_order: Bool,
+    /// new, updated, filled or cancelled
+    pub event: String,
+    pub price: Float,
+    /// Estimated quantity traded since the previous snapshot
+    pub traded: Integer,
+    pub event_time: DateTime,
+}
+
+/// Estimated traded quantity of the type
+#[derive(QueryableByName, Debug, PartialEq, Clone)]
+pub struct TypeVolume {
+    #[sql_type = "diesel::sql_types::Integer"]
+    pub type_id: Integer,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::BigInt>"]
+    pub traded: Option<i64>,
+}
+impl TypeVolume {
+    /// The most traded types of the region since the time
+    pub fn load(conn: &Connection, region_id: &Integer, since: &DateTime, limit: &Integer) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        use diesel::sql_types::{Integer, Timestamp};
+        diesel::sql_query(
+            "SELECT type_id, SUM(traded) AS traded FROM order_events \
+             WHERE region_id = ? AND event_time >= ? AND traded > 0 \
+             GROUP BY type_id ORDER BY SUM(traded) DESC LIMIT ?")
+            .bind::<Integer, _>(region_id)
+            .bind::<Timestamp, _>(since)
+            .bind::<Integer, _>(limit)
+            .load(conn)
+    }
+}
@@ -13,6 +13,23 @@ pub mod region;
 pub mod constellation;
 pub mod system;
 pub mod observatory;
+// The diesel 1.4 derives put their impls into the functions
+#[allow(non_local_definitions)]
+pub mod station;
+pub mod participant;
+#[allow(non_local_definitions)]
+pub mod watchlist;
+#[allow(non_local_definitions)]
+pub mod rule;
+#[allow(non_local_definitions)]
+pub mod alert;
+pub mod export;
+#[allow(non_local_definitions)]
+pub mod price;
+#[allow(non_local_definitions)]
+pub mod market;
+#[allow(non_local_definitions)]
+pub mod maintenance;
 
 
 pub use diesel::sqlite::SqliteConnection as Connection;
@@ -31,9 +48,13 @@ pub type QueryResult<T> = std::result::Result<T, diesel::result::Error>;
 pub struct DB;
 impl DB {
     pub fn connection() -> Connection {
-        use crate::diesel::Connection;
         let url = std::env::var("DATABASE_URL").expect("DB_URL environment variable required");
-        Connection::establish(&url).expect(&format!("Error connection to {}", url))
+        Self::connect(&url)
+    }
+
+    pub fn connect(url: &str) -> Connection {
+        use crate::diesel::Connection;
+        Connection::establish(url).unwrap_or_else(|_| panic!("Error connection to {}", url))
     }
 
     // pub fn get_saved_killmails(conn: &Connection, date: &Date) -> HashSet<Integer> {
@@ -191,11 +212,13 @@ fn get_items(killmail: &api::Killmail) -> Vec<item::Item> {
         for item in items {
             let mut obj = item::Item::from(item);
             obj.killmail_id = killmail.killmail_id;
+            let container = result.len() as Integer;
             result.push(obj);
             if let Some(ref subitems) = item.items {
                 for item in subitems {
                     let mut obj = item::Item::from(item);
                     obj.killmail_id = killmail.killmail_id;
+                    obj.container = Some(container);
                     result.push(obj);
                 }
             }
@@ -26,6 +26,30 @@ impl Object {
             .load(conn)
     }
 
+    /// Replaces the stored names of the objects
+    pub fn save_all(conn: &Connection, objects: &[Self]) -> QueryResult<usize> {
+        use diesel::prelude::*;
+        use diesel::connection::Connection;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let mut count = 0;
+            for chunk in objects.chunks(500) {
+                count += diesel::replace_into(objects::table).values(chunk).execute(conn)?;
+            }
+            Ok(count)
+        })
+    }
+
+    /// Objects of the category with the names, the case is ignored
+    pub fn find_names(conn: &Connection, category_id: &Integer, names: &[String]) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        use crate::schema::objects::dsl as table;
+        sql_function!(fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text);
+        let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+        table::objects
+            .filter(table::category_id.eq(category_id).and(lower(table::object_name).eq_any(names)))
+            .load(conn)
+    }
+
     pub fn load(conn: &Connection, id: &Integer) -> QueryResult<Self> {
         use diesel::prelude::*;
         use crate::schema::objects::dsl as table;
@@ -17,6 +17,11 @@ impl Observatory {
         observatories::table.find(id).first(conn)
     }
 
+    pub fn load_system_ids(conn: &Connection) -> QueryResult<Vec<Integer>> {
+        use diesel::prelude::*;
+        observatories::table.select(observatories::system_id).load(conn)
+    }
+
     pub fn save(conn: &Connection, id: &Integer) -> QueryResult<usize>  {
         use diesel::prelude::*;
         diesel::insert_into(observatories::table).values(Self::new(id)).execute(conn)
@@ -0,0 +1,135 @@
+use chrono::{Duration, Utc};
+
+use crate::api::stats::Entity;
+use crate::schema::victims;
+use crate::schema::attackers;
+use crate::schema::killmails;
+use crate::schema::named_killmails;
+use super::{Integer, OptInteger, DateTime, Connection, QueryResult};
+
+type Row = (Integer, DateTime, Integer, OptInteger, OptInteger, OptInteger, OptInteger, OptInteger);
+
+/// Victim or attacker of the killmail with the killmail place and time
+#[derive(Debug, PartialEq, Clone)]
+pub struct Participant {
+    pub killmail_id: Integer,
+    pub killmail_time: DateTime,
+    pub system_id: Integer,
+    pub is_victim: bool,
+    pub ship_id: OptInteger,
+    pub character_id: OptInteger,
+    pub corporation_id: OptInteger,
+    pub alliance_id: OptInteger,
+    pub faction_id: OptInteger,
+}
+impl Participant {
+    fn from_row(row: Row, is_victim: bool) -> Self {
+        let (killmail_id, killmail_time, system_id, ship_id, character_id, corporation_id, alliance_id, faction_id) = row;
+        Self {
+            killmail_id,
+            killmail_time,
+            system_id,
+            is_victim,
+            ship_id,
+            character_id,
+            corporation_id,
+            alliance_id,
+            faction_id,
+        }
+    }
+
+    /// Ids of the killmails where the entity was involved during the last minutes
+    pub fn load_ids(conn: &Connection, entity: &Entity, minutes: &Integer) -> QueryResult<Vec<Integer>> {
+        let end = Utc::now().naive_utc();
+        let start = end - Duration::minutes(*minutes as i64);
+        Self::load_ids_between(conn, entity, &start, &end)
+    }
+
+    /// Ids of the killmails where the entity was involved between the start and the end
+    pub fn load_ids_between(conn: &Connection, entity: &Entity, start: &DateTime, end: &DateTime) -> QueryResult<Vec<Integer>> {
+        use diesel::prelude::*;
+
+        info!("Load killmails ids for {:?} between {} and {}", entity, start, end);
+        let attackers = attackers::table.inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+            .filter(killmails::killmail_time.ge(start))
+            .filter(killmails::killmail_time.lt(end))
+            .select(killmails::killmail_id)
+            .distinct();
+        let victims = victims::table.inner_join(killmails::table.on(killmails::killmail_id.eq(victims::killmail_id)))
+            .filter(killmails::killmail_time.ge(start))
+            .filter(killmails::killmail_time.lt(end))
+            .select(killmails::killmail_id)
+            .distinct();
+        let area = named_killmails::table
+            .filter(named_killmails::killmail_time.ge(start))
+            .filter(named_killmails::killmail_time.lt(end))
+            .select(named_killmails::killmail_id);
+
+        let mut ids: Vec<Integer> = match entity {
+            Entity::Character(id) => {
+                let mut ids = attackers.filter(attackers::character_id.eq(id)).load(conn)?;
+                ids.append(&mut victims.filter(victims::character_id.eq(id)).load(conn)?);
+                ids
+            },
+            Entity::Corporation(id) => {
+                let mut ids = attackers.filter(attackers::corporation_id.eq(id)).load(conn)?;
+                ids.append(&mut victims.filter(victims::corporation_id.eq(id)).load(conn)?);
+                ids
+            },
+            Entity::Alliance(id) => {
+                let mut ids = attackers.filter(attackers::alliance_id.eq(id)).load(conn)?;
+                ids.append(&mut victims.filter(victims::alliance_id.eq(id)).load(conn)?);
+                ids
+            },
+            Entity::Faction(id) => {
+                let mut ids = attackers.filter(attackers::faction_id.eq(id)).load(conn)?;
+                ids.append(&mut victims.filter(victims::faction_id.eq(id)).load(conn)?);
+                ids
+            },
+            Entity::System(id) => area.filter(named_killmails::system_id.eq(id)).load(conn)?,
+            Entity::Constellation(id) => area.filter(named_killmails::constellation_id.eq(id)).load(conn)?,
+            Entity::Region(id) => area.filter(named_killmails::region_id.eq(id)).load(conn)?,
+            Entity::Ship(_) | Entity::Group(_) => Vec::new(),
+        };
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    /// All victims and attackers of the killmails
+    pub fn load(conn: &Connection, ids: &Vec<Integer>) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+
+        info!("Load participants of {} killmails", ids.len());
+        let victims: Vec<Row> = victims::table.inner_join(killmails::table.on(killmails::killmail_id.eq(victims::killmail_id)))
+            .filter(killmails::killmail_id.eq_any(ids))
+            .select((
+                killmails::killmail_id,
+                killmails::killmail_time,
+                killmails::solar_system_id,
+                victims::ship_type_id.nullable(),
+                victims::character_id,
+                victims::corporation_id,
+                victims::alliance_id,
+                victims::faction_id,
+             ))
+            .load(conn)?;
+        let attackers: Vec<Row> = attackers::table.inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+            .filter(killmails::killmail_id.eq_any(ids))
+            .select((
+                killmails::killmail_id,
+                killmails::killmail_time,
+                killmails::solar_system_id,
+                attackers::ship_type_id,
+                attackers::character_id,
+                attackers::corporation_id,
+                attackers::alliance_id,
+                attackers::faction_id,
+             ))
+            .load(conn)?;
+
+        let mut participants: Vec<Self> = victims.into_iter().map(|row| Self::from_row(row, true)).collect();
+        participants.extend(attackers.into_iter().map(|row| Self::from_row(row, false)));
+        Ok(participants)
+    }
+}
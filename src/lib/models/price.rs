@@ -0,0 +1,48 @@
+use crate::schema::prices;
+use super::{Integer, Date, Connection, QueryResult};
+
+/// Daily snapshot of the type prices, buy and sell are the best orders at the reference hub
+#[derive(Queryable, Insertable, Debug, PartialEq, Clone)]
+#[table_name = "prices"]
+pub struct Price {
+    pub type_id: Integer,
+    pub price_date: Date,
+    pub average: Option<f32>,
+    pub adjusted: Option<f32>,
+    pub buy: Option<f32>,
+    pub sell: Option<f32>,
+}
+impl Price {
+    /// The snapshot of the same day replaces the previous one
+    pub fn save_all(conn: &Connection, prices: &[Self]) -> QueryResult<usize> {
+        use diesel::prelude::*;
+        use diesel::connection::Connection;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let mut count = 0;
+            for chunk in prices.chunks(500) {
+                count += diesel::insert_into(prices::table).values(chunk).execute(conn)?;
+            }
+            Ok(count)
+        })
+    }
+
+    /// Snapshots of the types within the days around the dates
+    pub fn load_around(conn: &Connection, ids: &Vec<Integer>, first: &Date, last: &Date, days: &Integer) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        let delta = chrono::Duration::days(*days as i64);
+        prices::table
+            .filter(prices::type_id.eq_any(ids))
+            .filter(prices::price_date.between(*first - delta, *last + delta))
+            .load(conn)
+    }
+
+    /// Snapshots of the type since the date, the oldest first
+    pub fn load_history(conn: &Connection, type_id: &Integer, since: &Date) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        prices::table
+            .filter(prices::type_id.eq(type_id))
+            .filter(prices::price_date.ge(since))
+            .order(prices::price_date)
+            .load(conn)
+    }
+}
@@ -0,0 +1,43 @@
+use crate::schema::rules;
+use super::{Integer, OptInteger, Bool, Connection, QueryResult};
+
+/// All the set conditions must match the killmail
+#[derive(Queryable, Debug, PartialEq, Clone)]
+pub struct Rule {
+    pub rule_id: Integer,
+    pub name: String,
+    /// Any participant, the ship or the system is on the watchlist
+    pub watchlist_id: OptInteger,
+    /// Killmail is within the jumps from the system
+    pub system_id: OptInteger,
+    pub jumps: OptInteger,
+    /// Victim is a capital ship
+    pub capitals: Bool,
+}
+impl Rule {
+    pub fn load_all(conn: &Connection) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        rules::table.order(rules::rule_id).load(conn)
+    }
+
+    pub fn delete(conn: &Connection, id: &Integer) -> QueryResult<usize>  {
+        use diesel::prelude::*;
+        diesel::delete(rules::table.find(id)).execute(conn)
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[table_name = "rules"]
+pub struct NewRule {
+    pub name: String,
+    pub watchlist_id: OptInteger,
+    pub system_id: OptInteger,
+    pub jumps: OptInteger,
+    pub capitals: Bool,
+}
+impl NewRule {
+    pub fn save(&self, conn: &Connection) -> QueryResult<usize>  {
+        use diesel::prelude::*;
+        diesel::insert_into(rules::table).values(self).execute(conn)
+    }
+}
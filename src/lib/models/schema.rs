@@ -52,6 +52,7 @@ table! {
         flag -> Integer,
         quantity_destroyed -> Nullable<Integer>,
         quantity_dropped -> Nullable<Integer>,
+        container -> Nullable<Integer>,
     }
 }
 
@@ -117,6 +118,9 @@ table! {
         character_id -> Nullable<Integer>,
         corporation_id -> Nullable<Integer>,
         faction_id -> Nullable<Integer>,
+        position_x -> Nullable<Double>,
+        position_y -> Nullable<Double>,
+        position_z -> Nullable<Double>,
     }
 }
 
@@ -147,12 +151,54 @@ table! {
     }
 }
 
+table! {
+    stations (station_id) {
+        station_id -> Integer,
+        system_id -> Integer,
+    }
+}
+
 table! {
     observatories (system_id) {
         system_id -> Integer,
     }
 }
 
+table! {
+    watchlists (watchlist_id) {
+        watchlist_id -> Integer,
+        name -> Text,
+    }
+}
+
+table! {
+    watched (watchlist_id, category, object_id) {
+        watchlist_id -> Integer,
+        category -> Text,
+        object_id -> Integer,
+    }
+}
+
+table! {
+    rules (rule_id) {
+        rule_id -> Integer,
+        name -> Text,
+        watchlist_id -> Nullable<Integer>,
+        system_id -> Nullable<Integer>,
+        jumps -> Nullable<Integer>,
+        capitals -> Bool,
+    }
+}
+
+table! {
+    alerts (alert_id) {
+        alert_id -> Integer,
+        rule_id -> Integer,
+        killmail_id -> Integer,
+        alert_time -> Timestamp,
+    }
+}
+
 table! {
     named_systems (system_id) {
     	system_id -> Integer,
@@ -220,28 +266,6 @@ table! {
     }
 }
 
-table! {
-    observatory_path (s0_id, s2_id, s3_id, s4_id, s5_id) {
-        s0_id -> Integer,
-        s0_name -> Nullable<Text>,
-        s1_id -> Integer,
-        s1_name -> Nullable<Text>,
-        s1_jo -> Bool,
-        s2_id -> Integer,
-        s2_name -> Nullable<Text>,
-        s2_jo -> Bool,
-        s3_id -> Integer,
-        s3_name -> Nullable<Text>,
-        s3_jo -> Bool,
-        s4_id -> Integer,
-        s4_name -> Nullable<Text>,
-        s4_jo -> Bool,
-        s5_id -> Integer,
-        s5_name -> Nullable<Text>,
-        s5_jo -> Bool,
-    }
-}
-
 table! {
     combat_participants (killmail_id, killmail_id) {
         killmail_id -> Integer,
@@ -278,12 +302,58 @@ table! {
     }
 }
 
+table! {
+    prices (type_id, price_date) {
+        type_id -> Integer,
+        price_date -> Date,
+        average -> Nullable<Float>,
+        adjusted -> Nullable<Float>,
+        buy -> Nullable<Float>,
+        sell -> Nullable<Float>,
+    }
+}
+
+table! {
+    market_orders (order_id) {
+        order_id -> BigInt,
+        region_id -> Integer,
+        type_id -> Integer,
+        location_id -> BigInt,
+        system_id -> Integer,
+        is_buy_order -> Bool,
+        price -> Float,
+        volume_total -> Integer,
+        volume_remain -> Integer,
+        min_volume -> Integer,
+        duration -> Integer,
+        issued -> Timestamp,
+        order_range -> Text,
+        first_seen -> Timestamp,
+        last_seen -> Timestamp,
+    }
+}
+
+table! {
+    order_events (event_id) {
+        event_id -> Integer,
+        order_id -> BigInt,
+        region_id -> Integer,
+        type_id -> Integer,
+        is_buy_order -> Bool,
+        event -> Text,
+        price -> Float,
+        traded -> Integer,
+        event_time -> Timestamp,
+    }
+}
+
 joinable!(attackers -> killmails (killmail_id));
 joinable!(items -> killmails (killmail_id));
 joinable!(objects -> categories (category_id));
 joinable!(victims -> killmails (killmail_id));
 joinable!(systems -> constellations (constellation_id));
 joinable!(stargates -> systems (system_id));
+joinable!(watched -> watchlists (watchlist_id));
 
 allow_tables_to_appear_in_same_query!(
     attackers,
@@ -299,4 +369,13 @@ allow_tables_to_appear_in_same_query!(
     named_killmails,
     named_constellations,
     neighbors_constellations,
+    watchlists,
+    watched,
+    alerts,
+    rules,
+    prices,
+    market_orders,
+    order_events,
+    stations,
+    systems,
 );
@@ -48,6 +48,14 @@ pub struct SystemLink {
     pub dst_system_id: Integer,
 }
 impl SystemLink {
+    pub fn load_all(conn: &Connection) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        stargates::table
+            .select((stargates::system_id, stargates::dst_system_id))
+            .distinct()
+            .load(conn)
+    }
+
     // pub fn load(conn: &Connection, constellation_id: &Integer, minutes: &Integer) -> QueryResult<Vec<Self>> {
     //     use diesel::prelude::*;
 
@@ -0,0 +1,41 @@
+use crate::api;
+use crate::schema::stations;
+use super::{Connection, QueryResult, Integer};
+
+#[derive(Insertable)]
+#[table_name = "stations"]
+pub struct Station {
+    pub station_id: Integer,
+    pub system_id: Integer,
+}
+impl Station {
+    pub fn save(conn: &Connection, object: &api::system::System) -> QueryResult<usize>  {
+        use crate::diesel::RunQueryDsl;
+        let stations: Vec<Self> = object.stations.clone().unwrap_or_default()
+            .into_iter()
+            .map(|station_id| Self{station_id, system_id: object.system_id})
+            .collect();
+        if stations.is_empty() {
+            return Ok(0);
+        }
+        diesel::insert_into(stations::table).values(&stations).execute(conn)
+    }
+
+    /// Systems saved before the stations were stored and the systems without stations
+    pub fn load_unknown_system_ids(conn: &Connection) -> QueryResult<Vec<Integer>> {
+        use diesel::prelude::*;
+        use crate::schema::systems;
+        systems::table
+            .select(systems::system_id)
+            .filter(diesel::dsl::not(systems::system_id.eq_any(stations::table.select(stations::system_id))))
+            .load(conn)
+    }
+
+    pub fn load_system_ids(conn: &Connection) -> QueryResult<Vec<Integer>> {
+        use diesel::prelude::*;
+        stations::table
+            .select(stations::system_id)
+            .distinct()
+            .load(conn)
+    }
+}
@@ -3,7 +3,6 @@ use crate::api;
 use crate::schema::systems;
 use crate::schema::named_systems;
 use crate::schema::neighbors_systems;
-use crate::schema::observatory_path;
 use super::{Connection, QueryResult, Integer, OptInteger, OptString, Float};
 
 #[derive(Insertable)]
@@ -86,7 +85,7 @@ impl SystemNeighbors {
     }
 }
 
-#[derive(Queryable, Associations, Debug, PartialEq)]
+#[derive(Queryable, Associations, Debug, PartialEq, Clone)]
 #[table_name = "named_systems"]
 pub struct SystemNamed {
     pub system_id: Integer,
@@ -133,6 +132,12 @@ impl SystemNamed {
         named_systems::table.find(id).first(conn)
     }
 
+    pub fn load_all(conn: &Connection) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        info!("Load all systems");
+        named_systems::table.load(conn)
+    }
+
     pub fn load_from_constellation(conn: &Connection, id: &Integer, query: &SystemFilter) -> QueryResult<Vec<Self>> {
         use diesel::prelude::*;
         info!("Load systems from constellation {}", &id);
@@ -197,34 +202,3 @@ impl SystemNamed {
         }
     }
 }
-
-#[derive(Queryable, Associations, Debug, PartialEq)]
-#[table_name = "observatory_path"]
-pub struct ObservatoryPath {
-    pub s0_id: Integer,
-    pub s0_name: OptString,
-    pub s1_id: Integer,
-    pub s1_name: OptString,
-    pub s1_jo: bool,
-    pub s2_id: Integer,
-    pub s2_name: OptString,
-    pub s2_jo: bool,
-    pub s3_id: Integer,
-    pub s3_name: OptString,
-    pub s3_jo: bool,
-    pub s4_id: Integer,
-    pub s4_name: OptString,
-    pub s4_jo: bool,
-    pub s5_id: Integer,
-    pub s5_name: OptString,
-    pub s5_jo: bool,
-}
-impl ObservatoryPath {
-        pub fn load(conn: &Connection, id: &Integer) -> QueryResult<Vec<Self>> {
-        use diesel::prelude::*;
-        info!("Load system {}", &id);
-        observatory_path::table
-            .filter(observatory_path::s0_id.eq(id))
-            .load(conn)
-    }
-}
\ No newline at end of file
@@ -14,6 +14,9 @@ pub struct Victim {
     pub character_id: OptInteger,
     pub corporation_id: OptInteger,
     pub faction_id: OptInteger,
+    pub position_x: Option<f64>,
+    pub position_y: Option<f64>,
+    pub position_z: Option<f64>,
 }
 impl From<&api::Killmail> for Victim{
     fn from(src: &api::Killmail) -> Self {
@@ -25,6 +28,9 @@ impl From<&api::Killmail> for Victim{
             character_id: src.victim.character_id,
             corporation_id: src.victim.corporation_id,
             faction_id: src.victim.faction_id,
+            position_x: src.victim.position.as_ref().map(|position| position.x),
+            position_y: src.victim.position.as_ref().map(|position| position.y),
+            position_z: src.victim.position.as_ref().map(|position| position.z),
         }
     }
 }
@@ -75,5 +81,11 @@ impl VictimNamed {
         use diesel::prelude::*;
         named_victims::table.filter(named_victims::killmail_id.eq(id)).first(conn)
     }
+
+    /// Victims of the killmails by the single query
+    pub fn load_all(conn: &Connection, ids: &Vec<Integer>) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        named_victims::table.filter(named_victims::killmail_id.eq_any(ids)).load(conn)
+    }
 }
 
@@ -0,0 +1,61 @@
+use crate::schema::watchlists;
+use crate::schema::watched;
+use super::{Integer, Connection, QueryResult};
+
+#[derive(Queryable, Debug, PartialEq, Clone)]
+pub struct Watchlist {
+    pub watchlist_id: Integer,
+    pub name: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "watchlists"]
+struct NewWatchlist<'a> {
+    name: &'a str,
+}
+
+impl Watchlist {
+    /// Creates the watchlist if it does not exist yet
+    pub fn save(conn: &Connection, name: &String) -> QueryResult<Self> {
+        use diesel::prelude::*;
+        diesel::insert_into(watchlists::table).values(NewWatchlist { name }).execute(conn)?;
+        watchlists::table.filter(watchlists::name.eq(name)).first(conn)
+    }
+
+    pub fn load_all(conn: &Connection) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        watchlists::table.order(watchlists::name).load(conn)
+    }
+}
+
+/// Character, corporation, alliance, system or ship type on the watchlist
+#[derive(Queryable, Insertable, Debug, PartialEq, Clone)]
+#[table_name = "watched"]
+pub struct Watched {
+    pub watchlist_id: Integer,
+    pub category: String,
+    pub object_id: Integer,
+}
+impl Watched {
+    pub fn save(conn: &Connection, list: &String, category: &str, id: &Integer) -> QueryResult<usize>  {
+        use diesel::prelude::*;
+        let watchlist = Watchlist::save(conn, list)?;
+        let item = Self { watchlist_id: watchlist.watchlist_id, category: category.to_owned(), object_id: *id };
+        diesel::insert_into(watched::table).values(&item).execute(conn)
+    }
+
+    pub fn delete(conn: &Connection, list: &String, category: &String, id: &Integer) -> QueryResult<usize>  {
+        use diesel::prelude::*;
+        let ids = watchlists::table.filter(watchlists::name.eq(list)).select(watchlists::watchlist_id);
+        diesel::delete(watched::table
+            .filter(watched::watchlist_id.eq_any(ids))
+            .filter(watched::category.eq(category))
+            .filter(watched::object_id.eq(id)))
+        .execute(conn)
+    }
+
+    pub fn load_all(conn: &Connection) -> QueryResult<Vec<Self>> {
+        use diesel::prelude::*;
+        watched::table.load(conn)
+    }
+}
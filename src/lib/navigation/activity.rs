@@ -0,0 +1,149 @@
+use crate::models::{Integer, DateTime};
+use crate::models::killmail::SystemLoss;
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Width of the single timeline bucket
+pub const BUCKET_MINUTES: i64 = 10;
+/// The heatmaps and the timelines look a week back at most
+pub const MAX_MINUTES: Integer = 7 * 24 * 60;
+
+const CAPSULES: [Integer; 2] = [670, 33328];
+
+pub fn is_capsule(ship_id: &Integer) -> bool {
+    CAPSULES.contains(ship_id)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Metric {
+    Kills,
+    /// Average price of the destroyed hulls
+    Isk,
+    Pods,
+}
+impl Metric {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "kills" => Some(Metric::Kills),
+            "isk" => Some(Metric::Isk),
+            "pods" => Some(Metric::Pods),
+            _ => None
+        }
+    }
+}
+
+/// The requested period limited to a week
+pub fn clamp_minutes(minutes: &Integer) -> Integer {
+    (*minutes).clamp(1, MAX_MINUTES)
+}
+
+/// Metric value by system
+pub fn get_heat<P>(losses: &Vec<SystemLoss>, metric: &Metric, price: &P) -> HashMap<Integer, f32>
+    where P: Fn(&Integer) -> f32
+{
+    let mut heat = HashMap::new();
+    for loss in losses {
+        let value = match metric {
+            Metric::Kills => 1.0,
+            Metric::Isk => price(&loss.ship_id),
+            Metric::Pods => if is_capsule(&loss.ship_id) { 1.0 } else { 0.0 },
+        };
+        *heat.entry(loss.system_id).or_insert(0.0) += value;
+    }
+    heat
+}
+
+/// White for the cold systems up to the red for the hottest one
+pub fn get_heat_color(ratio: f32) -> String {
+    let ratio = ratio.clamp(0.0, 1.0);
+    let level = (255.0 * (1.0 - ratio)).round() as u8;
+    format!("#FF{:02X}{:02X}", level, level)
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Timeline {
+    pub start: DateTime,
+    pub bucket_minutes: i64,
+    /// Kills count by system for the each bucket, the oldest bucket is the first one
+    pub systems: HashMap<Integer, Vec<Integer>>,
+}
+impl Timeline {
+    pub fn new(losses: &Vec<SystemLoss>, now: &DateTime, minutes: &Integer) -> Self {
+        let buckets = ((*minutes as i64 + BUCKET_MINUTES - 1) / BUCKET_MINUTES).max(1);
+        let start = *now - chrono::Duration::minutes(buckets * BUCKET_MINUTES);
+        let mut systems = HashMap::new();
+        for loss in losses {
+            if loss.killmail_time >= start && loss.killmail_time <= *now {
+                // The kill at exactly now belongs to the last bucket
+                let index = ((loss.killmail_time - start).num_minutes() / BUCKET_MINUTES).min(buckets - 1);
+                let counts = systems.entry(loss.system_id).or_insert(vec![0; buckets as usize]);
+                counts[index as usize] += 1;
+            }
+        }
+        Self {
+            start,
+            bucket_minutes: BUCKET_MINUTES,
+            systems,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    fn now() -> DateTime {
+        NaiveDate::from_ymd(2019, 10, 1).and_hms(12, 0, 0)
+    }
+
+    fn loss(system_id: Integer, minutes: i64, ship_id: Integer) -> SystemLoss {
+        SystemLoss {
+            killmail_id: 0,
+            killmail_time: now() - Duration::minutes(minutes),
+            system_id,
+            ship_id,
+        }
+    }
+
+    fn losses() -> Vec<SystemLoss> {
+        vec![loss(1, 5, 670), loss(1, 15, 587), loss(2, 25, 33328), loss(2, 59, 24690)]
+    }
+
+    #[test]
+    fn heat() {
+        let price = |ship_id: &Integer| -> f32 { if *ship_id == 24690 { 200.0 } else { 10.0 } };
+        let kills = get_heat(&losses(), &Metric::Kills, &price);
+        assert_eq!(Some(&2.0), kills.get(&1));
+        let pods = get_heat(&losses(), &Metric::Pods, &price);
+        assert_eq!(Some(&1.0), pods.get(&2));
+        let isk = get_heat(&losses(), &Metric::Isk, &price);
+        assert_eq!(Some(&210.0), isk.get(&2));
+    }
+
+    #[test]
+    fn timeline() {
+        let timeline = Timeline::new(&losses(), &now(), &60);
+        assert_eq!(now() - Duration::minutes(60), timeline.start);
+        assert_eq!(&vec![0, 0, 0, 0, 1, 1], timeline.systems.get(&1).unwrap());
+        assert_eq!(&vec![1, 0, 0, 1, 0, 0], timeline.systems.get(&2).unwrap());
+
+        let timeline = Timeline::new(&vec![loss(3, 0, 587), loss(3, 60, 587), loss(3, -1, 587)], &now(), &60);
+        assert_eq!(&vec![1, 0, 0, 0, 0, 1], timeline.systems.get(&3).unwrap());
+    }
+
+    #[test]
+    fn periods() {
+        assert_eq!(1, clamp_minutes(&-5));
+        assert_eq!(60, clamp_minutes(&60));
+        assert_eq!(MAX_MINUTES, clamp_minutes(&(30 * 24 * 60)));
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!("#FFFFFF", get_heat_color(0.0));
+        assert_eq!("#FF0000", get_heat_color(1.0));
+        assert_eq!("#FF0000", get_heat_color(3.0));
+    }
+}
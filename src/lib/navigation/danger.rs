@@ -0,0 +1,238 @@
+use crate::api;
+use crate::provider;
+use crate::models::{Integer, DateTime};
+use crate::models::killmail::SystemKill;
+use crate::navigation::Hop;
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Interdictors (Sabre, Flycatcher, ...)
+const INTERDICTOR_GROUP: Integer = 541;
+/// Heavy Interdiction Cruisers
+const HEAVY_INTERDICTOR_GROUP: Integer = 894;
+/// Regular battleships, the usual gate-camp hulls
+const BATTLESHIP_GROUP: Integer = 27;
+/// The groups the ship classes are resolved with
+const GROUPS: [Integer; 3] = [INTERDICTOR_GROUP, HEAVY_INTERDICTOR_GROUP, BATTLESHIP_GROUP];
+
+lazy_static! {
+    /// Classes of the ships resolved with all groups loaded
+    static ref CLASSES: Mutex<HashMap<Integer, ShipClass>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+pub enum ShipClass {
+    Interdictor,
+    HeavyInterdictor,
+    Battleship,
+    Other,
+}
+impl ShipClass {
+    /// Loads the groups from ESI into the provider cache, true when all of them are there
+    pub fn load_groups() -> bool {
+        GROUPS.iter().all(|group_id| provider::get_group(group_id, &|id| api::group::Group::new(id)).is_some())
+    }
+
+    /// None when the group is not loaded yet, the report never waits for ESI
+    fn contains(group_id: &Integer, ship_id: &Integer) -> Option<bool> {
+        provider::get_group(group_id, &|_| None)
+            .map(|group| group.types.contains(ship_id))
+    }
+
+    /// None when the ship is in none of the loaded groups and some group is not loaded
+    fn classify(ship_id: &Integer) -> Option<Self> {
+        let groups = [
+            (INTERDICTOR_GROUP, ShipClass::Interdictor),
+            (HEAVY_INTERDICTOR_GROUP, ShipClass::HeavyInterdictor),
+            (BATTLESHIP_GROUP, ShipClass::Battleship),
+        ];
+        let mut complete = true;
+        for (group_id, class) in &groups {
+            match Self::contains(group_id, ship_id) {
+                Some(true) => return Some(*class),
+                Some(false) => {},
+                None => complete = false,
+            }
+        }
+        if complete { Some(ShipClass::Other) } else { None }
+    }
+
+    /// Resolves the class through the cached groups, the class is cached once all groups are loaded
+    pub fn from_ship(ship_id: &Integer) -> Self {
+        if let Some(class) = CLASSES.lock().ok().and_then(|classes| classes.get(ship_id).cloned()) {
+            return class;
+        }
+        match Self::classify(ship_id) {
+            Some(class) => {
+                if let Ok(mut classes) = CLASSES.lock() {
+                    classes.insert(*ship_id, class);
+                }
+                class
+            },
+            None => ShipClass::Other,
+        }
+    }
+
+    fn get_weight(&self) -> f32 {
+        match self {
+            ShipClass::Interdictor => 2.0,
+            ShipClass::HeavyInterdictor => 2.0,
+            ShipClass::Battleship => 1.0,
+            ShipClass::Other => 0.0,
+        }
+    }
+}
+
+/// Weight of the single kill: the older kill, the lower weight
+fn get_kill_weight(age_minutes: i64) -> f32 {
+    if age_minutes < 10 { 3.0 }
+    else if age_minutes < 60 { 1.0 }
+    else if age_minutes < 360 { 0.25 }
+    else { 0.1 }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct HopDanger {
+    pub system_id: Integer,
+    pub security_status: f32,
+    /// Kills count for the each of the `RouteDanger::windows`
+    pub kills: Vec<Integer>,
+    pub last_kill: Option<DateTime>,
+    /// Count of the kills with the ship class on the attackers side
+    pub interdictors: Integer,
+    pub heavy_interdictors: Integer,
+    pub battleships: Integer,
+    pub risk: f32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct RouteDanger {
+    /// History windows in minutes
+    pub windows: Vec<Integer>,
+    pub hops: Vec<HopDanger>,
+    pub risk: f32,
+}
+impl RouteDanger {
+    pub fn new<C>(route: &Vec<Hop>, windows: &[Integer], kills: &Vec<SystemKill>, now: &DateTime, classify: &C) -> Self
+        where C: Fn(&Integer) -> ShipClass
+    {
+        let mut by_system: HashMap<Integer, Vec<&SystemKill>> = HashMap::new();
+        for kill in kills {
+            by_system.entry(kill.system_id).or_default().push(kill);
+        }
+
+        let mut hops = Vec::new();
+        for hop in route {
+            let rows = by_system.remove(&hop.system_id).unwrap_or_default();
+            let mut danger = HopDanger {
+                system_id: hop.system_id,
+                security_status: hop.security_status,
+                kills: vec![0; windows.len()],
+                last_kill: None,
+                interdictors: 0,
+                heavy_interdictors: 0,
+                battleships: 0,
+                risk: 0.0,
+            };
+            let mut seen = HashSet::new();
+            let mut classes = HashSet::new();
+            for row in rows {
+                let age = (*now - row.killmail_time).num_minutes();
+                if seen.insert(row.killmail_id) {
+                    for (index, window) in windows.iter().enumerate() {
+                        if age < *window as i64 {
+                            danger.kills[index] += 1;
+                        }
+                    }
+                    if danger.last_kill.map(|last| last < row.killmail_time).unwrap_or(true) {
+                        danger.last_kill = Some(row.killmail_time);
+                    }
+                    danger.risk += get_kill_weight(age);
+                }
+                if let Some(ship_id) = row.ship_id {
+                    let class = classify(&ship_id);
+                    if class != ShipClass::Other && classes.insert((row.killmail_id, class)) {
+                        match class {
+                            ShipClass::Interdictor => danger.interdictors += 1,
+                            ShipClass::HeavyInterdictor => danger.heavy_interdictors += 1,
+                            ShipClass::Battleship => danger.battleships += 1,
+                            ShipClass::Other => {},
+                        }
+                        danger.risk += class.get_weight();
+                    }
+                }
+            }
+            hops.push(danger);
+        }
+        let risk = hops.iter().map(|hop| hop.risk).sum();
+        Self {
+            windows: windows.to_owned(),
+            hops,
+            risk,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    fn now() -> DateTime {
+        NaiveDate::from_ymd(2019, 10, 1).and_hms(12, 0, 0)
+    }
+
+    fn kill(killmail_id: Integer, system_id: Integer, minutes: i64, ship_id: Integer) -> SystemKill {
+        SystemKill {
+            killmail_id,
+            killmail_time: now() - Duration::minutes(minutes),
+            system_id,
+            ship_id: Some(ship_id),
+        }
+    }
+
+    fn classify(ship_id: &Integer) -> ShipClass {
+        match ship_id {
+            22456 => ShipClass::Interdictor,
+            24690 => ShipClass::Battleship,
+            _ => ShipClass::Other,
+        }
+    }
+
+    fn route() -> Vec<Hop> {
+        vec![
+            Hop { system_id: 1, security_status: 1.0, kills: 0 },
+            Hop { system_id: 2, security_status: 0.4, kills: 0 },
+        ]
+    }
+
+    #[test]
+    fn empty() {
+        let danger = RouteDanger::new(&route(), &[10, 60], &Vec::new(), &now(), &classify);
+        assert_eq!(2, danger.hops.len());
+        assert_eq!(vec![0, 0], danger.hops[0].kills);
+        assert!(danger.hops[1].last_kill.is_none());
+        assert_eq!(0.0, danger.risk);
+    }
+
+    #[test]
+    fn windows() {
+        let kills = vec![
+            kill(100, 2, 5, 22456),
+            kill(100, 2, 5, 22456),
+            kill(100, 2, 5, 24690),
+            kill(101, 2, 30, 587),
+            kill(102, 3, 1, 22456),
+        ];
+        let danger = RouteDanger::new(&route(), &[10, 60], &kills, &now(), &classify);
+        let hop = &danger.hops[1];
+        assert_eq!(vec![1, 2], hop.kills);
+        assert_eq!(Some(now() - Duration::minutes(5)), hop.last_kill);
+        assert_eq!(1, hop.interdictors);
+        assert_eq!(1, hop.battleships);
+        assert_eq!(3.0 + 1.0 + 2.0 + 1.0, hop.risk);
+        assert_eq!(hop.risk, danger.risk);
+    }
+}
@@ -0,0 +1,24 @@
+pub mod route;
+pub mod danger;
+pub mod search;
+pub mod universe;
+pub mod activity;
+
+pub use route::{Safety, RouteQuery, Hop, Graph, Kills};
+pub use danger::{ShipClass, HopDanger, RouteDanger};
+pub use search::{Predicate, SearchQuery, Found};
+pub use universe::Universe;
+pub use activity::{Metric, Timeline};
+
+/// Security status as it is displayed in game (rounded to one decimal)
+pub fn get_security(status: f32) -> f32 {
+    (10.0 * status).round() / 10.0
+}
+
+pub fn is_high_sec(status: f32) -> bool {
+    get_security(status) >= 0.5
+}
+
+pub fn is_null_sec(status: f32) -> bool {
+    get_security(status) <= 0.0
+}
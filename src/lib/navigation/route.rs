@@ -0,0 +1,293 @@
+use crate::models;
+use crate::models::{Connection, QueryResult, Integer};
+use crate::navigation;
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Cost of the single jump, all other weights are expressed in the jumps
+const JUMP_COST: u64 = 1000;
+/// Extra cost for the system which does not match to the safety mode
+const SECURITY_PENALTY: u64 = 50 * JUMP_COST;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Safety {
+    Shortest,
+    PreferHighSec,
+    AvoidNullSec,
+}
+/// The "shortest" and any other unknown flag take the shortest route
+impl From<&str> for Safety {
+    fn from(flag: &str) -> Self {
+        match flag {
+            "safe" | "secure" | "s" | "highsec" => Safety::PreferHighSec,
+            "avoidnull" | "nonull" | "n" => Safety::AvoidNullSec,
+            _ => Safety::Shortest,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RouteQuery {
+    pub departure: Integer,
+    pub destination: Integer,
+    pub safety: Safety,
+    /// Systems which should not be used as intermediate points
+    pub avoid: Vec<Integer>,
+    /// Kills history window used for the danger weight
+    pub minutes: Integer,
+    /// Extra weight (in jumps) of the each kill in the system
+    pub danger: f32,
+}
+impl RouteQuery {
+    pub fn new(departure: Integer, destination: Integer, safety: Safety) -> Self {
+        Self {
+            departure,
+            destination,
+            safety,
+            avoid: Vec::new(),
+            minutes: 60,
+            danger: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Hop {
+    pub system_id: Integer,
+    pub security_status: f32,
+    pub kills: Integer,
+}
+
+/// Kills count by system
+pub type Kills = HashMap<Integer, Integer>;
+
+/// Counts kills for the last `minutes` by system
+pub fn load_kills(conn: &Connection, minutes: &Integer) -> QueryResult<Kills> {
+    let mut kills = Kills::new();
+    for system_id in models::killmail::KillmailNamed::load_system_ids_for_last_minutes(conn, minutes)? {
+        *kills.entry(system_id).or_insert(0) += 1;
+    }
+    Ok(kills)
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Graph {
+    links: HashMap<Integer, Vec<Integer>>,
+    security: HashMap<Integer, f32>,
+}
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_system(&mut self, system_id: Integer, security_status: f32) {
+        self.security.insert(system_id, security_status);
+    }
+
+    pub fn add_link(&mut self, from: Integer, to: Integer) {
+        let neighbors = self.links.entry(from).or_default();
+        if !neighbors.contains(&to) {
+            neighbors.push(to);
+        }
+    }
+
+    pub fn get_systems(&self) -> Vec<Integer> {
+        self.security.keys().cloned().collect()
+    }
+
+    pub fn get_neighbors(&self, system_id: &Integer) -> &[Integer] {
+        self.links.get(system_id).map(|neighbors| neighbors.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn get_security_status(&self, system_id: &Integer) -> Option<f32> {
+        self.security.get(system_id).cloned()
+    }
+
+    /// Breadth-first jumps count between the systems, None when it is further than the max
+    pub fn get_jumps(&self, src: &Integer, dst: &Integer, max: u32) -> Option<u32> {
+        let mut visited = HashSet::new();
+        let mut queue = std::collections::VecDeque::new();
+        visited.insert(*src);
+        queue.push_back((*src, 0));
+        while let Some((system_id, jumps)) = queue.pop_front() {
+            if system_id == *dst {
+                return Some(jumps);
+            }
+            if jumps >= max {
+                continue;
+            }
+            for neighbor in self.get_neighbors(&system_id) {
+                if visited.insert(*neighbor) {
+                    queue.push_back((*neighbor, jumps + 1));
+                }
+            }
+        }
+        None
+    }
+
+    fn get_hop(&self, system_id: Integer, kills: &Kills) -> Hop {
+        Hop {
+            system_id,
+            security_status: self.security.get(&system_id).cloned().unwrap_or_default(),
+            kills: kills.get(&system_id).cloned().unwrap_or_default(),
+        }
+    }
+
+    fn get_cost(&self, system_id: Integer, query: &RouteQuery, kills: &Kills) -> u64 {
+        let status = self.security.get(&system_id).cloned().unwrap_or_default();
+        let penalty = match query.safety {
+            Safety::Shortest => 0,
+            Safety::PreferHighSec => if navigation::is_high_sec(status) { 0 } else { SECURITY_PENALTY },
+            Safety::AvoidNullSec => if navigation::is_null_sec(status) { SECURITY_PENALTY } else { 0 },
+        };
+        let count = kills.get(&system_id).cloned().unwrap_or_default();
+        let danger = (count as f32 * query.danger.max(0.0) * JUMP_COST as f32) as u64;
+        JUMP_COST + penalty + danger
+    }
+
+    /// Dijkstra search over the stargates graph. Returns hops including departure and destination.
+    pub fn find(&self, query: &RouteQuery, kills: &Kills) -> Option<Vec<Hop>> {
+        let src = query.departure;
+        let dst = query.destination;
+        if src == dst {
+            return Some(vec![self.get_hop(src, kills)]);
+        }
+        let avoid: HashSet<Integer> = query.avoid.iter().cloned().filter(|id| *id != src && *id != dst).collect();
+        let mut costs: HashMap<Integer, u64> = HashMap::new();
+        let mut previous: HashMap<Integer, Integer> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        costs.insert(src, 0);
+        heap.push(State { cost: 0, system_id: src });
+        while let Some(State { cost, system_id }) = heap.pop() {
+            if system_id == dst {
+                break;
+            }
+            if cost > costs.get(&system_id).cloned().unwrap_or(u64::MAX) {
+                continue;
+            }
+            if let Some(neighbors) = self.links.get(&system_id) {
+                for neighbor in neighbors {
+                    if avoid.contains(neighbor) {
+                        continue;
+                    }
+                    let next = cost + self.get_cost(*neighbor, query, kills);
+                    if next < costs.get(neighbor).cloned().unwrap_or(u64::MAX) {
+                        costs.insert(*neighbor, next);
+                        previous.insert(*neighbor, system_id);
+                        heap.push(State { cost: next, system_id: *neighbor });
+                    }
+                }
+            }
+        }
+
+        if !previous.contains_key(&dst) {
+            return None;
+        }
+        let mut path = vec![dst];
+        let mut current = dst;
+        while let Some(prev) = previous.get(&current) {
+            path.push(*prev);
+            current = *prev;
+        }
+        path.reverse();
+        Some(path.into_iter().map(|id| self.get_hop(id, kills)).collect())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct State {
+    cost: u64,
+    system_id: Integer,
+}
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap, so the order is flipped
+        other.cost.cmp(&self.cost).then_with(|| self.system_id.cmp(&other.system_id))
+    }
+}
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  1(1.0) - 2(0.1) - 3(1.0)
+    //    \                /
+    //     4(0.9) - 5(0.8)
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.add_system(1, 1.0);
+        graph.add_system(2, 0.1);
+        graph.add_system(3, 1.0);
+        graph.add_system(4, 0.9);
+        graph.add_system(5, 0.8);
+        for (from, to) in [(1, 2), (2, 3), (1, 4), (4, 5), (5, 3)] {
+            graph.add_link(from, to);
+            graph.add_link(to, from);
+        }
+        graph
+    }
+
+    fn ids(hops: Option<Vec<Hop>>) -> Vec<Integer> {
+        hops.unwrap_or_default().iter().map(|hop| hop.system_id).collect()
+    }
+
+    #[test]
+    fn shortest() {
+        let query = RouteQuery::new(1, 3, Safety::Shortest);
+        assert_eq!(vec![1, 2, 3], ids(graph().find(&query, &Kills::new())));
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(Safety::Shortest, Safety::from("insecure"));
+        assert_eq!(Safety::Shortest, Safety::from("u"));
+        assert_eq!(Safety::Shortest, Safety::from("shortest"));
+        assert_eq!(Safety::PreferHighSec, Safety::from("secure"));
+        assert_eq!(Safety::AvoidNullSec, Safety::from("n"));
+    }
+
+    #[test]
+    fn prefer_high_sec() {
+        let query = RouteQuery::new(1, 3, Safety::PreferHighSec);
+        assert_eq!(vec![1, 4, 5, 3], ids(graph().find(&query, &Kills::new())));
+    }
+
+    #[test]
+    fn avoid_list() {
+        let mut query = RouteQuery::new(1, 3, Safety::Shortest);
+        query.avoid.push(2);
+        assert_eq!(vec![1, 4, 5, 3], ids(graph().find(&query, &Kills::new())));
+        query.avoid.push(5);
+        assert!(graph().find(&query, &Kills::new()).is_none());
+    }
+
+    #[test]
+    fn danger() {
+        let graph = graph();
+        let mut kills = Kills::new();
+        kills.insert(2, 2);
+        let mut query = RouteQuery::new(1, 3, Safety::Shortest);
+        query.danger = 1.0;
+        let hops = graph.find(&query, &kills).unwrap_or_default();
+        assert_eq!(vec![1, 4, 5, 3], hops.iter().map(|hop| hop.system_id).collect::<Vec<_>>());
+        query.danger = 0.0;
+        let hops = graph.find(&query, &kills).unwrap_or_default();
+        assert_eq!(2, hops[1].kills);
+    }
+
+    #[test]
+    fn jumps() {
+        let graph = graph();
+        assert_eq!(Some(0), graph.get_jumps(&1, &1, 0));
+        assert_eq!(Some(2), graph.get_jumps(&1, &3, 5));
+        assert_eq!(Some(2), graph.get_jumps(&4, &3, 2));
+        assert_eq!(None, graph.get_jumps(&4, &3, 1));
+        assert_eq!(None, graph.get_jumps(&1, &42, 5));
+    }
+}
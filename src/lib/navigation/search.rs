@@ -0,0 +1,210 @@
+use crate::models;
+use crate::models::{Connection, QueryResult, Integer};
+use crate::navigation;
+use crate::navigation::Graph;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Predicate {
+    Observatory,
+    /// Security status band (rounded as in game), both bounds are inclusive
+    Security((f32, f32)),
+    /// At least one kill during the last minutes
+    RecentKills(Integer),
+    /// No kills during the last minutes
+    NoKills(Integer),
+    Station,
+}
+impl Predicate {
+    /// Parses the predicate name used in the urls, `minutes` is used by the kills predicates
+    pub fn from_name(name: &str, minutes: Integer) -> Option<Self> {
+        match name {
+            "observatory" | "jo" => Some(Predicate::Observatory),
+            "highsec" => Some(Predicate::Security((0.5, 1.0))),
+            "lowsec" => Some(Predicate::Security((0.1, 0.4))),
+            "nullsec" => Some(Predicate::Security((-1.0, 0.0))),
+            "kills" => Some(Predicate::RecentKills(minutes)),
+            "quiet" => Some(Predicate::NoKills(minutes)),
+            "station" => Some(Predicate::Station),
+            _ => None
+        }
+    }
+
+    /// Set of the systems which match to the predicate
+    pub fn load(&self, conn: &Connection, graph: &Graph) -> QueryResult<Matcher> {
+        let matcher = match self {
+            Predicate::Observatory => {
+                Matcher::new(models::observatory::Observatory::load_system_ids(conn)?, false)
+            },
+            Predicate::Security((min, max)) => {
+                Matcher::security(graph, min, max)
+            },
+            Predicate::RecentKills(minutes) => {
+                Matcher::new(models::killmail::KillmailNamed::load_system_ids_for_last_minutes(conn, minutes)?, false)
+            },
+            Predicate::NoKills(minutes) => {
+                Matcher::new(models::killmail::KillmailNamed::load_system_ids_for_last_minutes(conn, minutes)?, true)
+            },
+            Predicate::Station => {
+                Matcher::new(models::station::Station::load_system_ids(conn)?, false)
+            },
+        };
+        Ok(matcher)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Matcher {
+    ids: HashSet<Integer>,
+    inverted: bool,
+}
+impl Matcher {
+    pub fn new(ids: Vec<Integer>, inverted: bool) -> Self {
+        Self {
+            ids: ids.into_iter().collect(),
+            inverted,
+        }
+    }
+
+    pub fn security(graph: &Graph, min: &f32, max: &f32) -> Self {
+        let ids = graph.get_systems()
+            .into_iter()
+            .filter(|id| {
+                let status = navigation::get_security(graph.get_security_status(id).unwrap_or_default());
+                status >= *min && status <= *max
+            })
+            .collect();
+        Self::new(ids, false)
+    }
+
+    pub fn matches(&self, system_id: &Integer) -> bool {
+        self.ids.contains(system_id) != self.inverted
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SearchQuery {
+    pub system_id: Integer,
+    pub predicate: Predicate,
+    /// Maximal distance in jumps
+    pub jumps: u32,
+    /// Maximal count of the found systems
+    pub limit: usize,
+}
+impl SearchQuery {
+    pub fn new(system_id: Integer, predicate: Predicate, jumps: u32, limit: usize) -> Self {
+        Self {
+            system_id,
+            predicate,
+            jumps,
+            limit,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Found {
+    pub system_id: Integer,
+    pub jumps: u32,
+    /// Systems from the next after departure up to the found one
+    pub path: Vec<Integer>,
+}
+
+/// Breadth-first search of the nearest systems which match to the predicate.
+/// The departure system is not checked, results are ordered by the distance.
+pub fn nearest(graph: &Graph, query: &SearchQuery, matcher: &Matcher) -> Vec<Found> {
+    let mut result = Vec::new();
+    let mut previous: HashMap<Integer, Integer> = HashMap::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(query.system_id);
+    queue.push_back((query.system_id, 0));
+    while let Some((system_id, jumps)) = queue.pop_front() {
+        if result.len() >= query.limit {
+            break;
+        }
+        if jumps > 0 && matcher.matches(&system_id) {
+            let mut path = vec![system_id];
+            let mut current = system_id;
+            while let Some(prev) = previous.get(&current) {
+                if *prev == query.system_id {
+                    break;
+                }
+                path.push(*prev);
+                current = *prev;
+            }
+            path.reverse();
+            result.push(Found { system_id, jumps, path });
+        }
+        if jumps >= query.jumps {
+            continue;
+        }
+        for neighbor in graph.get_neighbors(&system_id) {
+            if visited.insert(*neighbor) {
+                previous.insert(*neighbor, system_id);
+                queue.push_back((*neighbor, jumps + 1));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  1 - 2 - 3 - 4 - 5 - 6
+    //       \
+    //        7
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        for (id, status) in [(1, 1.0), (2, 0.5), (3, 0.3), (4, 0.1), (5, -0.2), (6, -0.5), (7, 0.44)] {
+            graph.add_system(id, status);
+        }
+        for (from, to) in [(1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (2, 7)] {
+            graph.add_link(from, to);
+            graph.add_link(to, from);
+        }
+        graph
+    }
+
+    fn ids(found: &[Found]) -> Vec<Integer> {
+        found.iter().map(|found| found.system_id).collect()
+    }
+
+    #[test]
+    fn nearest_ordered() {
+        let matcher = Matcher::new(vec![1, 4, 7], false);
+        let found = nearest(&graph(), &SearchQuery::new(1, Predicate::Observatory, 5, 5), &matcher);
+        assert_eq!(vec![7, 4], ids(&found));
+        assert_eq!(2, found[0].jumps);
+        assert_eq!(vec![2, 7], found[0].path);
+        assert_eq!(vec![2, 3, 4], found[1].path);
+    }
+
+    #[test]
+    fn jumps_and_limit() {
+        let matcher = Matcher::new(vec![6], false);
+        assert!(nearest(&graph(), &SearchQuery::new(1, Predicate::Observatory, 4, 5), &matcher).is_empty());
+        assert_eq!(vec![6], ids(&nearest(&graph(), &SearchQuery::new(1, Predicate::Observatory, 5, 5), &matcher)));
+
+        let matcher = Matcher::new(Vec::new(), true);
+        assert_eq!(vec![2], ids(&nearest(&graph(), &SearchQuery::new(1, Predicate::NoKills(60), 5, 1), &matcher)));
+    }
+
+    #[test]
+    fn security_band() {
+        let graph = graph();
+        let matcher = Matcher::security(&graph, &0.1, &0.4);
+        let found = nearest(&graph, &SearchQuery::new(1, Predicate::Security((0.1, 0.4)), 5, 5), &matcher);
+        assert_eq!(vec![3, 7, 4], ids(&found));
+    }
+
+    #[test]
+    fn predicate_names() {
+        assert_eq!(Some(Predicate::Observatory), Predicate::from_name("observatory", 60));
+        assert_eq!(Some(Predicate::NoKills(120)), Predicate::from_name("quiet", 120));
+        assert_eq!(None, Predicate::from_name("unknown", 60));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::models;
+use crate::models::{Connection, QueryResult, Integer};
+use crate::models::system::SystemNamed;
+use crate::navigation::Graph;
+
+use std::collections::HashMap;
+
+/// Systems with names, constellations, regions and the stargates graph.
+/// Loaded once by the database thread and refreshed on saving new systems and stargates.
+#[derive(Debug, PartialEq, Default)]
+pub struct Universe {
+    systems: HashMap<Integer, SystemNamed>,
+    /// Systems by the id of the system, its constellation and its region
+    members: HashMap<Integer, Vec<Integer>>,
+    graph: Graph,
+}
+impl Universe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(conn: &Connection) -> QueryResult<Self> {
+        let mut universe = Self::new();
+        for system in SystemNamed::load_all(conn)? {
+            universe.add_system(system);
+        }
+        for link in models::stargate::SystemLink::load_all(conn)? {
+            universe.add_link(link.system_id, link.dst_system_id);
+        }
+        Ok(universe)
+    }
+
+    pub fn add_system(&mut self, system: SystemNamed) {
+        let id = system.system_id;
+        if let Some(previous) = self.systems.get(&id) {
+            for key in &[previous.system_id, previous.constellation_id, previous.region_id] {
+                if let Some(ids) = self.members.get_mut(key) {
+                    ids.retain(|member| *member != id);
+                }
+            }
+        }
+        for key in &[system.system_id, system.constellation_id, system.region_id] {
+            self.members.entry(*key).or_default().push(id);
+        }
+        self.graph.add_system(id, system.security_status);
+        self.systems.insert(id, system);
+    }
+
+    pub fn add_link(&mut self, from: Integer, to: Integer) {
+        self.graph.add_link(from, to);
+    }
+
+    /// Names are resolved later than the systems are saved
+    pub fn set_name(&mut self, id: &Integer, name: &str) {
+        let systems = &mut self.systems;
+        let members = self.members.get(id).map(|ids| ids.as_slice()).unwrap_or_default();
+        for member in members {
+            let system = match systems.get_mut(member) {
+                Some(system) => system,
+                None => continue,
+            };
+            if system.system_id == *id {
+                system.system_name = Some(name.to_owned());
+            }
+            if system.constellation_id == *id {
+                system.constellation_name = Some(name.to_owned());
+            }
+            if system.region_id == *id {
+                system.region_name = Some(name.to_owned());
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    pub fn get_graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn get_system(&self, id: &Integer) -> Option<&SystemNamed> {
+        self.systems.get(id)
+    }
+
+    pub fn get_neighbors(&self, id: &Integer) -> &[Integer] {
+        self.graph.get_neighbors(id)
+    }
+
+    fn get_members<P: Fn(&SystemNamed) -> bool>(&self, id: &Integer, predicate: P) -> Vec<&SystemNamed> {
+        self.members.get(id)
+            .map(|ids| ids.iter().filter_map(|member| self.systems.get(member)).filter(|system| predicate(system)).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_constellation_systems(&self, id: &Integer) -> Vec<&SystemNamed> {
+        self.get_members(id, |system| system.constellation_id == *id)
+    }
+
+    pub fn get_region_systems(&self, id: &Integer) -> Vec<&SystemNamed> {
+        self.get_members(id, |system| system.region_id == *id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(system_id: Integer, constellation_id: Integer, region_id: Integer) -> SystemNamed {
+        SystemNamed {
+            system_id,
+            system_name: None,
+            constellation_id,
+            constellation_name: None,
+            region_id,
+            region_name: None,
+            security_status: 0.5,
+            observatory: None,
+        }
+    }
+
+    #[test]
+    fn areas() {
+        let mut universe = Universe::new();
+        universe.add_system(system(1, 10, 100));
+        universe.add_system(system(2, 10, 100));
+        universe.add_system(system(3, 20, 100));
+        universe.add_link(1, 3);
+        universe.set_name(&10, &String::from("Kimotoro"));
+        assert_eq!(3, universe.len());
+        assert_eq!(2, universe.get_constellation_systems(&10).len());
+        assert_eq!(3, universe.get_region_systems(&100).len());
+        assert_eq!(&[3], universe.get_neighbors(&1));
+        assert_eq!("Kimotoro", universe.get_system(&2).unwrap().get_name("constellation"));
+    }
+
+    #[test]
+    fn readded() {
+        let mut universe = Universe::new();
+        universe.add_system(system(1, 10, 100));
+        universe.add_system(system(1, 20, 100));
+        universe.set_name(&10, &String::from("Kimotoro"));
+        universe.set_name(&20, &String::from("Otanuomi"));
+        assert_eq!(1, universe.len());
+        assert!(universe.get_constellation_systems(&10).is_empty());
+        assert_eq!(1, universe.get_constellation_systems(&20).len());
+        assert_eq!(1, universe.get_region_systems(&100).len());
+        assert_eq!("Otanuomi", universe.get_system(&1).unwrap().get_name("constellation"));
+    }
+}
@@ -6,12 +6,16 @@ use crate::api::region::Region;
 use crate::api::constellation::Constellation;
 use crate::api::alliance::Alliance;
 use crate::api::corporation::Corporation;
+use crate::api::group::Group;
+use crate::api::item_type::ItemType;
 use std::collections::HashMap;
 use std::sync::Mutex;
 
 mod prices;
 pub use prices::get_avg_price;
 pub use prices::get_adj_price;
+pub use prices::{get_hub_price, load_hub_prices, set_snapshot_prices};
+pub use prices::set_prices;
 
 
 #[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
@@ -36,6 +40,8 @@ lazy_static! {
     static ref ROUTES: Mutex<HashMap<Route, Vec<i32>>> = Mutex::new(HashMap::new());
     static ref ALLIANCES: Mutex<HashMap<i32, Alliance>> = Mutex::new(HashMap::new());
     static ref CORPORATIONS: Mutex<HashMap<i32, Corporation>> = Mutex::new(HashMap::new());
+    static ref GROUPS: Mutex<HashMap<i32, Group>> = Mutex::new(HashMap::new());
+    static ref TYPES: Mutex<HashMap<i32, ItemType>> = Mutex::new(HashMap::new());
 }
 
 pub fn get_object<L>(key: &i32, loader: &L) -> Option<Object>
@@ -46,6 +52,7 @@ pub fn get_object<L>(key: &i32, loader: &L) -> Option<Object>
     } else {
         None
     };
+    crate::metrics::cache("objects", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -66,6 +73,7 @@ pub fn get_stargate<L>(key: &i32, loader: &L) -> Option<Stargate>
     } else {
         None
     };
+    crate::metrics::cache("stargates", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -88,6 +96,7 @@ pub fn get_system<L>(key: &i32, loader: &L) -> Option<System>
     } else {
         None
     };
+    crate::metrics::cache("systems", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -109,6 +118,7 @@ pub fn get_character<L>(key: &i32, loader: &L) -> Option<Character>
     } else {
         None
     };
+    crate::metrics::cache("characters", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -131,6 +141,7 @@ pub fn get_constellation<L>(key: &i32, loader: &L) -> Option<Constellation>
     } else {
         None
     };
+    crate::metrics::cache("constellations", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -152,6 +163,7 @@ pub fn get_region<L>(key: &i32, loader: &L) -> Option<Region>
     } else {
         None
     };
+    crate::metrics::cache("regions", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -173,6 +185,7 @@ pub fn get_route<L>(src: &i32, dst: &i32, loader: &L) -> Option<Vec<i32>>
     } else {
         None
     };
+    crate::metrics::cache("routes", route.is_some());
 
     if route.is_none() {
         if let Some(received) = loader(&src, &dst) {
@@ -194,6 +207,7 @@ pub fn get_alliance<L>(key: &i32, loader: &L) -> Option<Alliance>
     } else {
         None
     };
+    crate::metrics::cache("alliances", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -215,6 +229,7 @@ pub fn get_corporation<L>(key: &i32, loader: &L) -> Option<Corporation>
     } else {
         None
     };
+    crate::metrics::cache("corporations", object.is_some());
 
     if object.is_none() {
         if let Some(received) = loader(key) {
@@ -225,4 +240,48 @@ pub fn get_corporation<L>(key: &i32, loader: &L) -> Option<Corporation>
         }
     }
     return object;
-}
\ No newline at end of file
+}
+
+pub fn get_group<L>(key: &i32, loader: &L) -> Option<Group>
+    where
+        L: Fn(&i32)->Option<Group>
+{
+    let mut object = if let Ok(map) = GROUPS.try_lock() {
+        map.get(key).cloned()
+    } else {
+        None
+    };
+    crate::metrics::cache("groups", object.is_some());
+
+    if object.is_none() {
+        if let Some(received) = loader(key) {
+            object = Some(received.clone());
+            if let Ok(ref mut map) = GROUPS.try_lock() {
+                map.entry(*key).or_insert(received);
+            }
+        }
+    }
+    object
+}
+
+pub fn get_type<L>(key: &i32, loader: &L) -> Option<ItemType>
+    where
+        L: Fn(&i32)->Option<ItemType>
+{
+    let mut object = if let Ok(map) = TYPES.try_lock() {
+        map.get(key).cloned()
+    } else {
+        None
+    };
+    crate::metrics::cache("types", object.is_some());
+
+    if object.is_none() {
+        if let Some(received) = loader(key) {
+            object = Some(received.clone());
+            if let Ok(ref mut map) = TYPES.try_lock() {
+                map.entry(*key).or_insert(received);
+            }
+        }
+    }
+    object
+}
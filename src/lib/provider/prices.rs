@@ -1,9 +1,26 @@
 use crate::api::price::Prices;
+use crate::api::{Order, OrderType};
+use crate::valuation;
 
+use crossbeam_queue::SegQueue;
+use crossbeam_utils::thread::scope;
+use std::collections::HashMap;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// ESI caches the orders for five minutes
+pub const HUB_PRICE_TTL: Duration = Duration::from_secs(300);
+/// The types of the region are requested by so many threads
+pub const HUB_WORKERS: usize = 8;
+
+/// The type, the hub location and is it the buy price
+type HubKey = (i32, i64, bool);
 
 lazy_static! {
     static ref PRICES: Mutex<Prices> = Mutex::new(Prices::new());
+    static ref HUB_PRICES: Mutex<HashMap<HubKey, (Instant, Option<f32>)>> = Mutex::new(HashMap::new());
+    /// The best hub prices of the stored order snapshots by the region
+    static ref SNAPSHOT_PRICES: Mutex<HashMap<i32, HashMap<HubKey, f32>>> = Mutex::new(HashMap::new());
 }
 
 pub fn get_avg_price(id: &Option<i32>) -> Option<f32> {
@@ -24,13 +41,126 @@ pub fn get_adj_price(id: &Option<i32>) -> Option<f32> {
     return None
 }
 
+/// Replaces the current prices by the fresh ones, the hub prices are loaded again on demand
+pub fn set_prices(prices: Prices) {
+    if let Ok(ref mut current) = PRICES.lock() {
+        **current = prices;
+    }
+    if let Ok(ref mut map) = HUB_PRICES.lock() {
+        map.clear();
+    }
+}
+
+/// Replaces the hub prices of the region by the ones of its stored order snapshot
+pub fn set_snapshot_prices(region_id: &i32, orders: &[Order]) {
+    let mut prices = HashMap::new();
+    for (_, hub) in valuation::HUBS.iter().filter(|(_, hub)| hub.region_id == *region_id) {
+        for (id, (buy, sell)) in valuation::best_prices(orders, &hub.location_id) {
+            if let Some(price) = buy {
+                prices.insert((id, hub.location_id, true), price);
+            }
+            if let Some(price) = sell {
+                prices.insert((id, hub.location_id, false), price);
+            }
+        }
+    }
+    if let Ok(ref mut map) = SNAPSHOT_PRICES.lock() {
+        map.insert(*region_id, prices);
+    }
+}
+
+/// None when the region has no snapshot, ESI is asked then
+fn get_snapshot(id: &i32, region_id: &i32, location_id: &i64, is_buy: bool) -> Option<Option<f32>> {
+    SNAPSHOT_PRICES.lock().ok().and_then(|map| {
+        map.get(region_id).map(|prices| prices.get(&(*id, *location_id, is_buy)).cloned())
+    })
+}
+
+fn has_snapshot(region_id: &i32) -> bool {
+    SNAPSHOT_PRICES.lock().map(|map| map.contains_key(region_id)).unwrap_or(false)
+}
+
+fn get_cached(id: &i32, location_id: &i64, is_buy: bool, now: Instant) -> Option<Option<f32>> {
+    HUB_PRICES.try_lock().ok().and_then(|map| {
+        map.get(&(*id, *location_id, is_buy))
+            .filter(|(loaded, _)| now.duration_since(*loaded) < HUB_PRICE_TTL)
+            .map(|(_, price)| *price)
+    })
+}
+
+/// Loads the outdated hub prices of the types in the region by the parallel requests,
+/// the types without the orders are cached as None while the failed requests are not cached
+pub fn load_hub_prices(ids: &[i32], region_id: &i32, location_id: &i64, is_buy: bool) {
+    if has_snapshot(region_id) {
+        return;
+    }
+    let now = Instant::now();
+    let tasks = SegQueue::new();
+    let mut missing: Vec<i32> = ids.iter().cloned().filter(|id| get_cached(id, location_id, is_buy, now).is_none()).collect();
+    missing.sort_unstable();
+    missing.dedup();
+    for id in &missing {
+        tasks.push(*id);
+    }
+    let order_type = if is_buy { OrderType::BUY } else { OrderType::SELL };
+    let results = SegQueue::new();
+    let _ = scope(|scope| {
+        for _ in 0..HUB_WORKERS.min(missing.len()) {
+            scope.spawn(|_| {
+                while let Ok(id) = tasks.pop() {
+                    if let Some(orders) = Order::load(region_id, order_type, Some(id)) {
+                        results.push((id, valuation::best_price(&orders, location_id, is_buy)));
+                    }
+                }
+            });
+        }
+    });
+    if let Ok(ref mut map) = HUB_PRICES.lock() {
+        let loaded = Instant::now();
+        while let Ok((id, price)) = results.pop() {
+            map.insert((id, *location_id, is_buy), (loaded, price));
+        }
+    }
+}
+
+/// The best buy or sell price at the station, the stored snapshot of the region goes first
+pub fn get_hub_price(id: &i32, region_id: &i32, location_id: &i64, is_buy: bool) -> Option<f32> {
+    if let Some(price) = get_snapshot(id, region_id, location_id, is_buy) {
+        return price;
+    }
+    if let Some(price) = get_cached(id, location_id, is_buy, Instant::now()) {
+        crate::metrics::cache("hub_prices", true);
+        return price;
+    }
+    crate::metrics::cache("hub_prices", false);
+    load_hub_prices(&[*id], region_id, location_id, is_buy);
+    get_cached(id, location_id, is_buy, Instant::now()).unwrap_or_default()
+}
 
 #[cfg(test)]
 mod tests {
 
+    use super::*;
+
     #[test]
     fn from_get_prices() {
+        assert!(super::get_avg_price(&Some(3178)).is_some());
         assert!(super::get_adj_price(&Some(3178)).is_some());
-        assert!(super::get_adj_price(&Some(3178)).is_some());
+    }
+
+    #[test]
+    fn from_snapshot() {
+        let hub = valuation::DODIXIE;
+        let order = |price: f32, is_buy: bool| -> Order {
+            serde_json::from_value(serde_json::json!({
+                "order_id": 1, "type_id": 34, "location_id": hub.location_id, "system_id": hub.system_id,
+                "volume_total": 10, "volume_remain": 10, "min_volume": 1, "price": price, "is_buy_order": is_buy,
+                "duration": 90, "issued": "2020-01-01T00:00:00Z", "range": "station"
+            })).unwrap()
+        };
+        set_snapshot_prices(&hub.region_id, &[order(5.0, true), order(4.5, true), order(6.0, false)]);
+        assert_eq!(Some(5.0), get_hub_price(&34, &hub.region_id, &hub.location_id, true));
+        assert_eq!(Some(6.0), get_hub_price(&34, &hub.region_id, &hub.location_id, false));
+        assert_eq!(None, get_hub_price(&35, &hub.region_id, &hub.location_id, false));
     }
 }
\ No newline at end of file
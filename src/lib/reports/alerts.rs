@@ -0,0 +1,187 @@
+use crate::alerts::CATEGORIES;
+use crate::models::alert::Alert;
+use crate::models::rule::{Rule, NewRule};
+use crate::models::watchlist::{Watchlist, Watched};
+use crate::services::{Context, Category, Report, Message, Model};
+use crate::reports;
+use crate::reports::get_object_name;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Alerts shown on the page
+pub const ALERTS_LIMIT: i32 = 50;
+
+fn get_alerts(ctx: &Context) -> Vec<Alert> {
+    match reports::load(Category::Alerts(ALERTS_LIMIT), ctx) {
+        Report::Alerts(alerts) => alerts,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+fn get_watchlists(ctx: &Context) -> Vec<Watchlist> {
+    match reports::load(Category::Watchlists, ctx) {
+        Report::Watchlists(watchlists) => watchlists,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+fn get_watched(ctx: &Context) -> Vec<Watched> {
+    match reports::load(Category::Watched, ctx) {
+        Report::Watched(watched) => watched,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+fn get_rules(ctx: &Context) -> Vec<Rule> {
+    match reports::load(Category::Rules, ctx) {
+        Report::Rules(rules) => rules,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+fn get_href(category: &String, id: &i32, ctx: &Context) -> String {
+    let name = get_object_name(id);
+    if category == "ship" {
+        ctx.get_zkb_href("ship", *id, name)
+    } else {
+        ctx.get_api_href(category.clone(), *id, name)
+    }
+}
+
+fn write_alerts(output: &mut dyn Write, rules: &HashMap<i32, String>, ctx: &Context) {
+    let alerts = get_alerts(ctx);
+    if alerts.is_empty() {
+        reports::div(output, "No alerts yet");
+        return;
+    }
+    let style = "border: 1px solid black; padding: 2px 5px;";
+    reports::table_start(output, "Alerts", "border-collapse: collapse;", "Alerts");
+    reports::table_row_start(output, style);
+    reports::table_cell_head(output, "Alert Time", style, "Time");
+    reports::table_cell_head(output, "Rule Name", style, "Rule");
+    reports::table_cell_head(output, "Killmail", style, "Killmail");
+    reports::table_row_end(output);
+    for alert in &alerts {
+        reports::table_row_start(output, style);
+        reports::table_cell(output, "Alert Time", style, alert.alert_time.format("%Y-%m-%d %H:%M:%S").to_string());
+        reports::table_cell(output, "Rule Name", style, rules.get(&alert.rule_id).cloned().unwrap_or(alert.rule_id.to_string()));
+        reports::table_cell(output, "Killmail", style, ctx.get_api_href("killmail", alert.killmail_id, alert.killmail_id.to_string()));
+        reports::table_row_end(output);
+    }
+    reports::table_end(output);
+
+    let mut ids: Vec<i32> = alerts.iter().map(|alert| alert.killmail_id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    match reports::load(Category::Killmails(ids), ctx) {
+        Report::History(killmails) => {
+            reports::table_start(output, "Killmails", "border-collapse: collapse;", "");
+            reports::Killmail::write_head(output);
+            reports::Killmail::write_rows(output, &killmails, ctx);
+            reports::table_end(output);
+        },
+        report => {
+            reports::div(output, format!("Unexpected report {:?}", report));
+        }
+    }
+}
+
+fn write_watchlists(output: &mut dyn Write, watchlists: &Vec<Watchlist>, ctx: &Context) {
+    let watched = get_watched(ctx);
+    let style = "border: 1px solid black; padding: 2px 5px;";
+    for watchlist in watchlists {
+        reports::table_start(output, "Watchlist", "border-collapse: collapse;", format!("Watchlist {} ({})", watchlist.name, watchlist.watchlist_id));
+        for item in watched.iter().filter(|item| item.watchlist_id == watchlist.watchlist_id) {
+            reports::table_row_start(output, style);
+            reports::table_cell(output, "Category", style, item.category.clone());
+            reports::table_cell(output, "Name", style, get_href(&item.category, &item.object_id, ctx));
+            reports::table_cell(output, "Remove", style, reports::href(
+                format!("{}/watch/{}/{}/{}/del", ctx.get_root(), watchlist.name, item.category, item.object_id),
+                String::from("remove")));
+            reports::table_row_end(output);
+        }
+        reports::table_end(output);
+    }
+    reports::div(output, format!("Add with {}/watch/{{list}}/{{category}}/{{id}}/add, where the category is one of: {}",
+        ctx.get_root(), CATEGORIES.join(", ")));
+}
+
+fn write_rules(output: &mut dyn Write, rules: &Vec<Rule>, watchlists: &[Watchlist], ctx: &Context) {
+    let style = "border: 1px solid black; padding: 2px 5px;";
+    reports::table_start(output, "Rules", "border-collapse: collapse;", "Rules");
+    reports::table_row_start(output, style);
+    reports::table_cell_head(output, "Rule Name", style, "Name");
+    reports::table_cell_head(output, "Any participant is on the watchlist", style, "Watchlist");
+    reports::table_cell_head(output, "Within the jumps from the system", style, "Area");
+    reports::table_cell_head(output, "Victim is a capital ship", style, "Capitals");
+    reports::table_cell_head(output, "Remove", style, "");
+    reports::table_row_end(output);
+    for rule in rules {
+        let watchlist = rule.watchlist_id
+            .map(|id| watchlists.iter().find(|list| list.watchlist_id == id).map(|list| list.name.clone()).unwrap_or(id.to_string()))
+            .unwrap_or_default();
+        let area = rule.system_id
+            .map(|id| format!("{} jumps from {}", rule.jumps.unwrap_or_default(), ctx.get_api_href("system", id, get_object_name(&id))))
+            .unwrap_or_default();
+        reports::table_row_start(output, style);
+        reports::table_cell(output, "Rule Name", style, rule.name.clone());
+        reports::table_cell(output, "Watchlist", style, watchlist);
+        reports::table_cell(output, "Area", style, area);
+        reports::table_cell(output, "Capitals", style, if rule.capitals { "yes" } else { "" });
+        reports::table_cell(output, "Remove", style, reports::href(
+            format!("{}/rules/{}/del", ctx.get_root(), rule.rule_id),
+            String::from("remove")));
+        reports::table_row_end(output);
+    }
+    reports::table_end(output);
+    reports::div(output, format!("Add with {}/rules/add?name=...&watchlist=...&system=...&jumps=...&capitals=true", ctx.get_root()));
+}
+
+pub fn report(ctx: &Context) -> String {
+    let mut output = String::new();
+    let rules = get_rules(ctx);
+    let names: HashMap<i32, String> = rules.iter().map(|rule| (rule.rule_id, rule.name.clone())).collect();
+    let watchlists = get_watchlists(ctx);
+    write_alerts(&mut output, &names, ctx);
+    write_rules(&mut output, &rules, &watchlists, ctx);
+    write_watchlists(&mut output, &watchlists, ctx);
+    output
+}
+
+pub fn watch(list: String, category: String, id: i32, cmd: &str, ctx: &Context) -> String {
+    if !CATEGORIES.contains(&category.as_str()) {
+        return format!("Unknown category {}", category);
+    }
+    match cmd {
+        "add" => ctx.database.push(Message::Save(Model::Watched((list, category, id)))),
+        "del" => ctx.database.push(Message::Delete(Model::Watched((list, category, id)))),
+        _ => return format!("Unknown command {}", cmd),
+    }
+    String::from("Done")
+}
+
+pub fn rule_add(rule: NewRule, ctx: &Context) -> String {
+    if rule.watchlist_id.is_none() && rule.system_id.is_none() && !rule.capitals {
+        return String::from("The rule needs a watchlist, a system or the capitals flag");
+    }
+    ctx.database.push(Message::Save(Model::Rule(rule)));
+    String::from("Done")
+}
+
+pub fn rule_del(id: &i32, ctx: &Context) -> String {
+    ctx.database.push(Message::Delete(Model::RuleId(*id)));
+    String::from("Done")
+}
@@ -11,6 +11,8 @@ impl reports::Reportable for Alliance {
         let mut output = String::new();
         reports::lazy(&mut output, format!("desc/alliance/{}", id), &ctx);
         reports::lazy(&mut output, format!("stat/alliance/{}", id), &ctx);
+        reports::lazy(&mut output, format!("hulls/alliance/{}", id), ctx);
+        reports::lazy(&mut output, format!("doctrines/alliance/{}", id), ctx);
         reports::lazy(&mut output, format!("report/alliance/wins/{}/{}", id, 60), &ctx);
         reports::lazy(&mut output, format!("report/alliance/losses/{}/{}", id, 60), &ctx);
         reports::radar(&mut output, &ctx);
@@ -46,7 +48,15 @@ impl Alliance {
         return output;
     }
 
-    pub fn stat(id: &i32, ctx: &Context) -> String {
+    pub fn stat(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::report(api::stats::Entity::Alliance(*id), minutes, ctx)
+    }
+
+    pub fn doctrines(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::doctrines(api::stats::Entity::Alliance(*id), minutes, ctx)
+    }
+
+    pub fn zkb_stat(id: &i32, ctx: &Context) -> String {
         use api::stats::Stats;
         use api::stats::Entity;
         use api::stats::TopList;
@@ -0,0 +1,128 @@
+use crate::api::names::Names;
+use crate::services::{Context, Category, Report, Message, Api};
+use crate::reports;
+use crate::reports::escape;
+use crate::valuation::Source;
+use crate::valuation::appraisal::{self, Appraised, Entry};
+
+use crate::separator::Separatable;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Names per the database query and the ESI request
+const NAMES_CHUNK: usize = 500;
+
+fn load_local(names: Vec<String>, ctx: &Context) -> Vec<(String, i32)> {
+    match reports::load(Category::TypeNames(names), ctx) {
+        Report::Objects(objects) => objects.into_iter().map(|object| (object.object_name, object.object_id)).collect(),
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+/// The type ids keyed by appraisal::get_key, the names unknown to the objects table are asked from ESI
+pub fn resolve(entries: &[Entry], ctx: &Context) -> HashMap<String, i32> {
+    let mut ids = HashMap::new();
+    let names: Vec<String> = entries.iter().map(|entry| entry.name.clone()).collect();
+    for chunk in names.chunks(NAMES_CHUNK) {
+        for (name, id) in load_local(chunk.to_vec(), ctx) {
+            ids.insert(appraisal::get_key(&name), id);
+        }
+    }
+    let missing: Vec<String> = names.into_iter().filter(|name| !ids.contains_key(&appraisal::get_key(name))).collect();
+    for chunk in missing.chunks(NAMES_CHUNK) {
+        let types = Names::find(&chunk.to_vec()).and_then(|names| names.inventory_types).unwrap_or_default();
+        for item in types {
+            ctx.resolver.push(Message::Receive(Api::Object(item.id)));
+            ids.insert(appraisal::get_key(&item.name), item.id);
+        }
+    }
+    ids
+}
+
+pub fn appraise(text: &str, source: &Source, ctx: &Context) -> Vec<Appraised> {
+    let entries = appraisal::parse(text);
+    let ids = resolve(&entries, ctx);
+    source.preload(&ids.values().cloned().collect::<Vec<i32>>());
+    appraisal::appraise(&entries, &ids, &|id| source.get_price(id))
+}
+
+fn form(output: &mut dyn std::fmt::Write, text: &str, source: &Source, ctx: &Context) {
+    let options: Vec<String> = Source::get_all().iter()
+        .map(|item| format!(r#"<option value="{}"{}>{}</option>"#,
+            item.get_key(), if item == source { " selected" } else { "" }, item.get_title()))
+        .collect();
+    reports::write(output, format!(r#"
+        <form method="post" action="{root}/appraise">
+            <div><textarea name="text" rows="15" cols="100" placeholder="Cargo scan, contract contents, EFT fit or inventory list">{text}</textarea></div>
+            <div><select name="price">{options}</select> <input type="submit" value="Appraise"></div>
+        </form>"#,
+        root = ctx.get_root(),
+        text = escape(text),
+        options = options.join("")));
+}
+
+fn format_price(price: &Option<f32>) -> String {
+    price.map(|price| (price.max(0.0) as u64).separated_string()).unwrap_or(String::from("-"))
+}
+
+/// The form with the lines and the total of the pasted text
+pub fn report(text: &str, source: &Source, ctx: &Context) -> String {
+    let mut output = String::new();
+    form(&mut output, text, source, ctx);
+    if text.trim().is_empty() {
+        return output;
+    }
+    let items = appraise(text, source, ctx);
+    if items.is_empty() {
+        reports::div(&mut output, "No items were recognized");
+        return output;
+    }
+    reports::div(&mut output, format!("Valuation: <b>{}</b>, total: <b>{}</b> ISK", source.get_title(), appraisal::total(&items).separated_string()));
+    let unknown = items.iter().filter(|item| item.type_id.is_none()).count();
+    if unknown > 0 {
+        reports::div(&mut output, format!("Unknown items: {}", unknown));
+    }
+    let table_style = "border-collapse: collapse;";
+    let head_style = "border: 1px solid black; padding: 2px 5px; text-align: center;";
+    let text_style = "border: 1px solid black; padding: 2px 5px;";
+    let numeric_style = "border: 1px solid black; padding: 2px 5px; text-align: right;";
+    reports::table_start(&mut output, "Appraisal", table_style, "Appraisal");
+    reports::table_row_start(&mut output, "");
+    reports::table_cell_head(&mut output, "Item Type", head_style, "Item");
+    reports::table_cell_head(&mut output, "Quantity", head_style, "Quantity");
+    reports::table_cell_head(&mut output, "Unit price", head_style, "Price");
+    reports::table_cell_head(&mut output, "Quantity by the price", head_style, "Value");
+    reports::table_row_end(&mut output);
+    for item in &items {
+        let name = match item.type_id {
+            Some(id) => format!(r#"<a href="{}/prices/{}">{}</a>"#, ctx.get_root(), id, escape(&item.name)),
+            None => format!(r#"<span style="color: red;">{}</span>"#, escape(&item.name)),
+        };
+        reports::table_row_start(&mut output, "");
+        reports::table_cell(&mut output, "Item Type", text_style, name);
+        reports::table_cell(&mut output, "Quantity", numeric_style, item.quantity.separated_string());
+        reports::table_cell(&mut output, "Unit price", numeric_style, format_price(&item.price));
+        reports::table_cell(&mut output, "Quantity by the price", numeric_style, item.get_value().separated_string());
+        reports::table_row_end(&mut output);
+    }
+    reports::table_end(&mut output);
+    output
+}
+
+/// The lines and the total of the pasted text, the unknown items have no type id
+pub fn report_json(text: &str, source: &Source, ctx: &Context) -> String {
+    let items = appraise(text, source, ctx);
+    let lines: Vec<serde_json::Value> = items.iter()
+        .map(|item| json!({
+            "name": item.name,
+            "quantity": item.quantity,
+            "type_id": item.type_id,
+            "price": item.price,
+            "value": item.get_value(),
+        }))
+        .collect();
+    json!({ "source": source.get_key(), "total": appraisal::total(&items), "items": lines }).to_string()
+}
@@ -0,0 +1,109 @@
+use crate::api::stats::Entity;
+use crate::services::Context;
+use crate::statistics::{Association, Link};
+use crate::statistics::association;
+use crate::reports;
+use crate::reports::{Node, Edge};
+use crate::reports::get_object_name;
+use crate::reports::statistics::{get_class, load};
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Nodes and edges in the vis-network format
+#[derive(Serialize, Debug, PartialEq, Clone, Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// Green for the allies, red for the enemies
+fn get_color(together: u32, opposite: u32) -> String {
+    String::from(if together >= opposite { "#00A000" } else { "#D00000" })
+}
+
+fn get_title(together: u32, opposite: u32) -> String {
+    format!("together: {} opposite: {}", together, opposite)
+}
+
+/// The closest associates with the links between them and the entity
+fn get_associates(entity: &Entity, id: &i32, minutes: &i32, ctx: &Context) -> (Vec<Association>, Vec<Link>) {
+    let participants = load(entity, minutes, ctx);
+    let associates = Association::rank(entity, id, &participants);
+    let mut ids: HashSet<i32> = associates.iter().map(|associate| associate.id).collect();
+    ids.insert(*id);
+    let links = association::get_links(entity, &participants, &ids);
+    (associates, links)
+}
+
+/// The entity with the closest associates and the links between all of them
+pub fn get_graph(entity: &Entity, id: &i32, minutes: &i32, ctx: &Context) -> Graph {
+    let class = get_class(entity);
+    let (associates, links) = get_associates(entity, id, minutes, ctx);
+
+    let mut nodes = vec![Node::actor(*id, get_object_name(id), String::from(class), 3, String::from(class), String::from("#FFFF80"))];
+    for associate in &associates {
+        let title = get_title(associate.together, associate.opposite);
+        let color = get_color(associate.together, associate.opposite);
+        nodes.push(Node::actor(associate.id, get_object_name(&associate.id), String::from(class), 1, title, color));
+    }
+    let edges = links.into_iter()
+        .map(|link| Edge::colored(link.from, link.to, get_color(link.together, link.opposite), get_title(link.together, link.opposite)))
+        .collect();
+    Graph { nodes, edges }
+}
+
+fn graph(output: &mut dyn Write, class: &str, id: &i32, minutes: &i32, ctx: &Context) {
+    std::fmt::write(
+        output,
+        format_args!(r##"
+            <script type="text/javascript" src="https://unpkg.com/vis-network/standalone/umd/vis-network.min.js"></script>
+            <style type="text/css"> #network {{ width: 95%; height: 600px; border: 1px solid lightgray; }} </style>
+            <div id = "network">...</div>
+            <script type="text/javascript">
+                const start = async function() {{
+                    var graph = await fetch("{root}/json/associates/{class}/{id}/{minutes}").then(response => response.json());
+                    var container = document.getElementById('network');
+                    var data = {{ nodes: new vis.DataSet(graph.nodes), edges: new vis.DataSet(graph.edges) }};
+                    var network = new vis.Network(container, data, {{ clickToUse: true }});
+                    network.on("doubleClick", function(params) {{
+                        window.open("{root}/api/{class}/" + params.nodes, "_self");
+                    }} );
+                }}
+                start();
+            </script>
+        "##,
+        root=ctx.get_root(),
+        class=class,
+        id=id,
+        minutes=minutes,
+    )).expect(reports::FAIL);
+}
+
+/// Ranked associates table with the graph below
+pub fn report(entity: &Entity, id: &i32, minutes: &i32, ctx: &Context) -> String {
+    let mut output = String::new();
+    let class = get_class(entity);
+    let associates = Association::rank(entity, id, &load(entity, minutes, ctx));
+    let table_style = "border-collapse: collapse;";
+    let text_style  = "border: 1px solid black; padding: 1px 5px;";
+    reports::div(&mut output, format!("Associates of {} for the last {} minutes", ctx.get_api_href(class, *id, get_object_name(id)), minutes));
+    reports::table_start(&mut output, "Associates", table_style, "");
+    reports::table_row_start(&mut output, "");
+    reports::table_cell_head(&mut output, "name", text_style, "Name");
+    reports::table_cell_head(&mut output, "same side", text_style, "Together");
+    reports::table_cell_head(&mut output, "opposite sides", text_style, "Opposite");
+    reports::table_row_end(&mut output);
+    for associate in &associates {
+        let style = format!("{} color: {};", text_style, get_color(associate.together, associate.opposite));
+        reports::table_row_start(&mut output, "");
+        reports::table_cell(&mut output, "name", &style, ctx.get_actor_desc(class, associate.id, get_object_name(&associate.id)));
+        reports::table_cell(&mut output, "same side", &style, associate.together.to_string());
+        reports::table_cell(&mut output, "opposite sides", &style, associate.opposite.to_string());
+        reports::table_row_end(&mut output);
+    }
+    reports::table_end(&mut output);
+    graph(&mut output, class, id, minutes, ctx);
+    output
+}
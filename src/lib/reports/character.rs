@@ -46,7 +46,11 @@ impl Character {
         }
     }
 
-    pub fn stat(id: &i32, ctx: &Context) -> String {
+    pub fn stat(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::report(api::stats::Entity::Character(*id), minutes, ctx)
+    }
+
+    pub fn zkb_stat(id: &i32, ctx: &Context) -> String {
         use api::stats::Stats;
         use api::stats::Entity;
         use api::stats::TopList;
@@ -74,6 +78,7 @@ impl reports::Reportable for Character {
         let mut output = String::new();
         Self::info(&mut output, &id, ctx);
         reports::lazy(&mut output, format!("stat/character/{}", id), &ctx);
+        reports::lazy(&mut output, format!("hulls/character/{}", id), ctx);
         reports::lazy(&mut output, format!("report/character/wins/{}/{}", id, 60), &ctx);
         reports::lazy(&mut output, format!("report/character/losses/{}/{}", id, 60), &ctx);
         reports::radar(&mut output, &ctx);
@@ -24,6 +24,7 @@ impl reports::ReportableEx for Constellation {
                 Self::systems(&mut output, id, &ctx);
                 reports::Region::constellations(&mut output, &constellation.region_id, &ctx);
                 reports::map(&mut output, id, 0, "constellation", &ctx);                
+                reports::live(&mut output, format!("area={}", id), ctx);
                 reports::lazy(&mut output, format!("history/constellation/{}/{}", id, 60), &ctx);
                 reports::lazy(&mut output, format!("stat/constellation/{}", id), &ctx);
                 reports::div(&mut output, "");
@@ -112,7 +113,11 @@ impl Constellation {
         }
     }
 
-    pub fn stat(id: &i32, ctx: &Context) -> String {
+    pub fn stat(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::report(api::stats::Entity::Constellation(*id), minutes, ctx)
+    }
+
+    pub fn zkb_stat(id: &i32, ctx: &Context) -> String {
         use api::stats::Stats;
         use api::stats::Entity;
         use api::stats::TopList;
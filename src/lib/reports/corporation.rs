@@ -11,6 +11,8 @@ impl reports::Reportable for Corporation {
         let mut output = String::new();
         reports::lazy(&mut output, format!("desc/corporation/{}", id), &ctx);
         reports::lazy(&mut output, format!("stat/corporation/{}", id), &ctx);
+        reports::lazy(&mut output, format!("hulls/corporation/{}", id), ctx);
+        reports::lazy(&mut output, format!("doctrines/corporation/{}", id), ctx);
         reports::lazy(&mut output, format!("report/corporation/wins/{}/{}", id, 60), &ctx);
         reports::lazy(&mut output, format!("report/corporation/losses/{}/{}", id, 60), &ctx);
         reports::radar(&mut output, &ctx);
@@ -51,7 +53,15 @@ impl Corporation {
         return output;
     }
 
-    pub fn stat(id: &i32, ctx: &Context) -> String {
+    pub fn stat(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::report(api::stats::Entity::Corporation(*id), minutes, ctx)
+    }
+
+    pub fn doctrines(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::doctrines(api::stats::Entity::Corporation(*id), minutes, ctx)
+    }
+
+    pub fn zkb_stat(id: &i32, ctx: &Context) -> String {
         use api::stats::Stats;
         use api::stats::Entity;
         use api::stats::TopList;
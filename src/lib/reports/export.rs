@@ -0,0 +1,48 @@
+use crate::api;
+use crate::export::{self, Format, Filter, CSV_HEADER, CHUNK_SIZE};
+use crate::services::{Context, Category, Report};
+use crate::reports;
+
+use std::fs;
+
+/// Ids of the killmails to export split by chunks
+pub fn get_chunks(filter: Filter, ctx: &Context) -> Vec<Vec<i32>> {
+    match reports::load(Category::ExportIds(filter), ctx) {
+        Report::Ids(ids) => ids.chunks(CHUNK_SIZE).map(|chunk| chunk.to_vec()).collect(),
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+fn get_killmails(ids: Vec<i32>, ctx: &Context) -> Vec<api::Killmail> {
+    match reports::load(Category::Export(ids), ctx) {
+        Report::Killmails(killmails) => killmails,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+pub fn get_header(format: Format) -> String {
+    match format {
+        Format::Csv => format!("{}\n", CSV_HEADER),
+        _ => String::new(),
+    }
+}
+
+pub fn get_text(format: Format, ids: Vec<i32>, ctx: &Context) -> String {
+    export::to_text(format, &get_killmails(ids, ctx))
+}
+
+/// The parquet footer is written at the end, so the file is built before the response
+pub fn get_parquet(chunks: Vec<Vec<i32>>, ctx: &Context) -> Result<Vec<u8>, String> {
+    let path = std::env::temp_dir().join(format!("{}.parquet", crate::create_id()));
+    let name = path.to_string_lossy().to_string();
+    let result = export::write_parquet(Some(name), chunks.into_iter().map(|ids| Ok(get_killmails(ids, ctx))))
+        .and_then(|_| fs::read(&path).map_err(|e| e.to_string()));
+    let _ = fs::remove_file(&path);
+    result
+}
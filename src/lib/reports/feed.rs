@@ -0,0 +1,171 @@
+use crate::models::DateTime;
+use crate::models::killmail::KillmailNamed;
+use crate::services::{Context, Category, Report, Area, Actor};
+use crate::reports;
+use crate::reports::{escape, get_object_name};
+use crate::reports::killmail::Details;
+use crate::valuation;
+
+use crate::separator::Separatable;
+use std::fmt::Write;
+
+/// Kills of the actor are rare enough to look the whole week back
+const ACTOR_MINUTES: i32 = 7 * 24 * 60;
+/// Busy areas produce enough entries in a day
+const AREA_MINUTES: i32 = 24 * 60;
+/// Entries in the feed, the latest ones
+const FEED_LIMIT: usize = 50;
+
+fn timestamp(time: &DateTime) -> String {
+    format!("{}Z", time.format("%Y-%m-%dT%H:%M:%S"))
+}
+
+fn load(category: Category, ctx: &Context) -> Vec<KillmailNamed> {
+    match reports::load(category, ctx) {
+        Report::History(mut killmails) => {
+            killmails.sort_by_key(|killmail| std::cmp::Reverse(killmail.killmail_time));
+            killmails.truncate(FEED_LIMIT);
+            killmails
+        },
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+fn write_head(output: &mut dyn Write, url: &str, title: &str, updated: &DateTime) {
+    reports::write(output, format!(r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+<id>{url}</id>
+<title>{title}</title>
+<updated>{updated}</updated>
+<link rel="self" href="{url}"/>
+<author><name>zkb navigator</name></author>"#,
+        url = escape(url),
+        title = escape(title),
+        updated = timestamp(updated)));
+}
+
+fn write_item(output: &mut dyn Write, link: &str, title: &str, updated: &DateTime, summary: &str) {
+    reports::write(output, format!(r#"<entry>
+    <id>{link}</id>
+    <title>{title}</title>
+    <updated>{updated}</updated>
+    <link href="{link}"/>
+    <summary>{summary}</summary>
+</entry>"#,
+        link = escape(link),
+        title = escape(title),
+        updated = timestamp(updated),
+        summary = escape(summary)));
+}
+
+fn write_entry(output: &mut dyn Write, killmail: &KillmailNamed, kind: &str, details: &mut Details, ctx: &Context) {
+    let killmail_id = killmail.killmail_id;
+    let (victim, items) = details.take(&killmail_id);
+    let value = reports::Killmail::get_value_by(&killmail.killmail_time, &items, &victim, &valuation::Source::default(), &details.prices).total();
+    let ship = victim.as_ref()
+        .map(|victim| victim.ship_name.clone().unwrap_or(victim.ship_id.to_string()))
+        .unwrap_or_default();
+    let pilot = victim.as_ref()
+        .and_then(|victim| victim.character_name.clone().or(victim.corporation_name.clone()))
+        .unwrap_or_default();
+    let location = vec![killmail.get_name("system"), killmail.get_name("constellation"), killmail.get_name("region")]
+        .into_iter()
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<String>>()
+        .join(" / ");
+    let link = format!("{}/api/killmail/{}", ctx.get_root(), killmail_id);
+    let title = format!("{}: {} {} in {}", kind, ship, pilot, killmail.get_name("system"));
+    let summary = format!("{} ISK, {} destroyed in {}", value.separated_string(), ship, location);
+    write_item(output, &link, &title, &killmail.killmail_time, &summary);
+}
+
+fn write_feed(title: String, path: String, entries: Vec<(KillmailNamed, &str)>, ctx: &Context) -> String {
+    let mut output = String::new();
+    let mut entries = entries;
+    entries.sort_by_key(|(killmail, _)| std::cmp::Reverse(killmail.killmail_time));
+    entries.truncate(FEED_LIMIT);
+    let updated = entries.first()
+        .map(|(killmail, _)| killmail.killmail_time)
+        .unwrap_or(chrono::Utc::now().naive_utc());
+    let url = format!("{}/feed/{}", ctx.get_root(), path);
+    write_head(&mut output, &url, &title, &updated);
+    let (killmails, kinds): (Vec<KillmailNamed>, Vec<&str>) = entries.into_iter().unzip();
+    let mut details = Details::load(&killmails, ctx);
+    for (killmail, kind) in killmails.iter().zip(kinds) {
+        write_entry(&mut output, killmail, kind, &mut details, ctx);
+    }
+    reports::write(&mut output, "</feed>");
+    output
+}
+
+fn get_actor(class: &str, id: i32) -> Option<Actor> {
+    match class {
+        "character" => Some(Actor::Character(id)),
+        "corporation" => Some(Actor::Corporation(id)),
+        "alliance" => Some(Actor::Alliance(id)),
+        _ => None
+    }
+}
+
+fn get_area(class: &str, id: i32) -> Option<Area> {
+    match class {
+        "system" => Some(Area::System(id)),
+        "constellation" => Some(Area::Constellation(id)),
+        "region" => Some(Area::Region(id)),
+        _ => None
+    }
+}
+
+/// Atom feed of the recent kills and losses, None for the unknown class
+pub fn report(class: &str, id: i32, ctx: &Context) -> Option<String> {
+    let title = || format!("{} {}", get_object_name(&id), class);
+    let path = format!("{}/{}.atom", class, id);
+    if let Some(actor) = get_actor(class, id) {
+        let mut entries: Vec<(KillmailNamed, &str)> = load(Category::Wins((actor.clone(), ACTOR_MINUTES)), ctx).into_iter().map(|killmail| (killmail, "Kill")).collect();
+        entries.extend(load(Category::Losses((actor, ACTOR_MINUTES)), ctx).into_iter().map(|killmail| (killmail, "Loss")));
+        Some(write_feed(title(), path, entries, ctx))
+    } else if let Some(area) = get_area(class, id) {
+        let entries = load(Category::History((area, AREA_MINUTES)), ctx).into_iter().map(|killmail| (killmail, "Kill")).collect();
+        Some(write_feed(title(), path, entries, ctx))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at() -> DateTime {
+        chrono::NaiveDate::from_ymd(2019, 12, 6).and_hms(7, 5, 9)
+    }
+
+    #[test]
+    fn escaped() {
+        assert_eq!(escape(r#"<a href="x">Tom & Jerry's</a>"#), "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;");
+        assert_eq!(escape("plain"), "plain");
+    }
+
+    #[test]
+    fn rfc3339() {
+        assert_eq!(timestamp(&at()), "2019-12-06T07:05:09Z");
+    }
+
+    #[test]
+    fn atom() {
+        let mut output = String::new();
+        write_head(&mut output, "http://host/feed/system/1.atom", "Jita & Co system", &at());
+        write_item(&mut output, "http://host/api/killmail/1", "Kill: <Rifter>", &at(), "100 ISK");
+        assert!(output.starts_with(r#"<?xml version="1.0" encoding="utf-8"?>"#));
+        assert!(output.contains(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#));
+        assert!(output.contains("<title>Jita &amp; Co system</title>"));
+        assert!(output.contains(r#"<link rel="self" href="http://host/feed/system/1.atom"/>"#));
+        assert!(output.contains("<updated>2019-12-06T07:05:09Z</updated>"));
+        assert!(output.contains("<title>Kill: &lt;Rifter&gt;</title>"));
+        assert!(output.contains(r#"<link href="http://host/api/killmail/1"/>"#));
+        assert!(output.contains("<summary>100 ISK</summary>"));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::models::*;
 use crate::reports;
+use crate::navigation;
 use crate::services::{Context, Report, Area, Actor, Category};
 use chrono::{Duration, Utc};
 
@@ -34,9 +35,7 @@ impl History {
                     reports::table_start(&mut output, "Attackers", table_style, "");
                     reports::Killmail::write_head(&mut output);
                     reports::caption(&mut output, caption);
-                    for killmail in history {
-                        reports::Killmail::write_row(&mut output, &killmail, &ctx);
-                    }
+                    reports::Killmail::write_rows(&mut output, &history, ctx);
                     reports::table_end(&mut output);
                 }
             },
@@ -85,6 +84,17 @@ impl History {
         Self::count(Category::HistoryCount((Area::System(*id), *minutes)), ctx)
     }
 
+    /// Kills count by system for the whole universe by the single query
+    pub fn kills(minutes: &Integer, ctx: &Context) -> navigation::Kills {
+        match reports::load(Category::Kills(*minutes), ctx) {
+            Report::Kills(kills) => kills,
+            report => {
+                warn!("Unexpected report {:?}", report);
+                navigation::Kills::new()
+            }
+        }
+    }
+
     pub fn region_count(id: &Integer, minutes: &Integer, ctx: &Context) -> i32 {
         Self::count(Category::HistoryCount((Area::Region(*id), *minutes)), ctx)
     }
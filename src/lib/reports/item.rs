@@ -3,6 +3,7 @@ use crate::services;
 use crate::services::Context;
 use crate::reports;
 
+use std::collections::HashMap;
 use std::fmt::Write;
 
 #[derive(Debug, PartialEq)]
@@ -30,6 +31,21 @@ impl Item {
         return None;
     }
 
+    /// Items of the killmails by the killmail id
+    pub fn load_all(ids: &[i32], ctx: &Context) -> HashMap<i32, Vec<models::item::ItemNamed>> {
+        use services::{Category, Report};
+        let mut result = HashMap::new();
+        match reports::load(Category::KillmailItems(ids.to_owned()), ctx) {
+            Report::Items(items) => {
+                for item in items {
+                    result.entry(item.killmail_id).or_insert_with(Vec::new).push(item);
+                }
+            },
+            report => warn!("Unexpected report {:?}", report)
+        }
+        result
+    }
+
     pub fn brief_impl(id: &i32, ctx: &Context) -> String {
         let mut output = String::new();
         if let Some(items) = Self::load(id, ctx) {
@@ -3,12 +3,47 @@ use crate::services;
 use crate::services::Context;
 use crate::reports;
 use crate::models;
-use crate::provider;
+use crate::valuation;
+use crate::models::price::Price;
 use separator::Separatable;
 
 
+use std::collections::HashMap;
 use std::fmt::Write;
 
+/// Victims, items and prices of the killmails loaded by the single query each
+pub struct Details {
+    victims: HashMap<Integer, models::victim::VictimNamed>,
+    items: HashMap<Integer, Vec<models::item::ItemNamed>>,
+    pub prices: Vec<Price>,
+}
+impl Details {
+
+    pub fn load(killmails: &[killmail::KillmailNamed], ctx: &Context) -> Self {
+        let ids: Vec<i32> = killmails.iter().map(|killmail| killmail.killmail_id).collect();
+        let victims = reports::Victim::load_all(&ids, ctx);
+        let items = reports::Item::load_all(&ids, ctx);
+        let dates: Vec<Date> = killmails.iter().map(|killmail| killmail.killmail_time.date()).collect();
+        let prices = match (dates.iter().min(), dates.iter().max()) {
+            (Some(first), Some(last)) => {
+                let mut ids: Vec<i32> = items.values().flatten().map(|item| item.item_type_id)
+                    .chain(victims.values().map(|victim| victim.ship_id))
+                    .collect();
+                ids.sort_unstable();
+                ids.dedup();
+                Killmail::load_prices(ids, first, last, ctx)
+            },
+            _ => Vec::new()
+        };
+        Self { victims, items, prices }
+    }
+
+    /// The victim and the items of the killmail, the killmail without items has the empty list
+    pub fn take(&mut self, id: &Integer) -> (Option<models::victim::VictimNamed>, Option<Vec<models::item::ItemNamed>>) {
+        (self.victims.remove(id), Some(self.items.remove(id).unwrap_or_default()))
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Killmail {
     pub killmail_id: Integer,
@@ -22,37 +57,38 @@ pub struct Killmail {
 }
 impl Killmail {
 
-    fn get_dropped_sum(items: &Option<Vec<models::item::ItemNamed>>) -> u64 {
-        let mut result = 0;
-        if let Some(items) = items {
-            for item in items {
-                if let Some(ref price) = provider::get_avg_price(&Some(item.item_type_id)){
-                    if let Some(ref quantity) = item.quantity_dropped {
-                        result = result + (*quantity as f32  * *price) as u64;
-                    }
-                }
+    /// Price snapshots around the kill dates by the single query
+    pub fn load_prices(ids: Vec<i32>, first: &Date, last: &Date, ctx: &Context) -> Vec<Price> {
+        use services::{Category, Report};
+        match reports::load(Category::Prices((ids, *first, *last)), ctx) {
+            Report::Prices(prices) => prices,
+            report => {
+                warn!("Unexpected report {:?}", report);
+                Vec::new()
             }
         }
-        return result;
     }
 
-    fn get_total_sum(items: &Option<Vec<models::item::ItemNamed>>, victim: &Option<models::victim::VictimNamed>) -> u64 {
-        let mut result = Self::get_dropped_sum(items);
-        if let Some(items) = items {
-            for item in items {
-                if let Some(ref price) = provider::get_avg_price(&Some(item.item_type_id)){
-                    if let Some(ref quantity) = item.quantity_destroyed {
-                        result = result + (*quantity as f32  * *price) as u64;
-                    }
-                }
-            }
-        }
-        if let Some(victim) = victim {
-            if let Some(price) = provider::get_avg_price(&Some(victim.ship_id)){
-                result = result + price as u64;
-            }
-        }
-        return result;
+    fn get_ids(items: &Option<Vec<models::item::ItemNamed>>, victim: &Option<models::victim::VictimNamed>) -> Vec<i32> {
+        let ship_id = victim.as_ref().map(|victim| victim.ship_id);
+        valuation::Line::from_named(items).iter().map(|line| line.type_id).chain(ship_id).collect()
+    }
+
+    /// Value of the hull and the items by the loaded snapshots nearest to the kill date
+    pub fn get_value_by(time: &DateTime, items: &Option<Vec<models::item::ItemNamed>>, victim: &Option<models::victim::VictimNamed>, source: &valuation::Source, prices: &[Price]) -> valuation::Value {
+        let ship_id = victim.as_ref().map(|victim| victim.ship_id);
+        let lines = valuation::Line::from_named(items);
+        valuation::evaluate_at(ship_id, &lines, source, &valuation::nearest(prices, &time.date()))
+    }
+
+    /// Value of the hull and the items by the source at the kill date
+    pub fn get_value(time: &DateTime, items: &Option<Vec<models::item::ItemNamed>>, victim: &Option<models::victim::VictimNamed>, source: &valuation::Source, ctx: &Context) -> valuation::Value {
+        let prices = Self::load_prices(Self::get_ids(items, victim), &time.date(), &time.date(), ctx);
+        Self::get_value_by(time, items, victim, source, &prices)
+    }
+
+    pub fn get_total_sum(time: &DateTime, items: &Option<Vec<models::item::ItemNamed>>, victim: &Option<models::victim::VictimNamed>, ctx: &Context) -> u64 {
+        Self::get_value(time, items, victim, &valuation::Source::default(), ctx).total()
     }
 
     fn volume_color(value: &u64) -> String {
@@ -106,14 +142,15 @@ impl Killmail {
             format!("{:.1}", security),
         );
 
-        let dropped_sum = Self::get_dropped_sum(&items);
+        let value = Self::get_value(&killmail.killmail_time, &items, &victim, &valuation::Source::default(), ctx);
+        let dropped_sum = value.dropped;
         let dropped = reports::span(
             "Dropped Volume",
             format!("display: inline-block; width: 115px; text-align: right; background-color: {};", Self::volume_color(&dropped_sum)),
             dropped_sum.separated_string()
         );
 
-        let total_sum = Self::get_total_sum(&items, &victim);
+        let total_sum = value.total();
         let total = reports::span(
             "Total Kill Mail Volume",
             format!("display: inline-block; width: 125px; text-align: right; background-color: {};", Self::volume_color(&total_sum)),
@@ -159,28 +196,36 @@ impl Killmail {
         reports::table_row_end(output);
     }
 
-    pub fn write_row(output: &mut dyn Write, killmail: &killmail::KillmailNamed, ctx: &Context) {
+    /// The rows of the killmails, the prices of the whole table are loaded at once
+    pub fn write_rows(output: &mut dyn Write, killmails: &[killmail::KillmailNamed], ctx: &Context) {
+        let mut details = Details::load(killmails, ctx);
+        for killmail in killmails {
+            let (victim, items) = details.take(&killmail.killmail_id);
+            Self::write_row(output, killmail, victim, &items, &details.prices, ctx);
+        }
+    }
+
+    fn write_row(output: &mut dyn Write, killmail: &killmail::KillmailNamed, victim: Option<models::victim::VictimNamed>, items: &Option<Vec<models::item::ItemNamed>>, prices: &[Price], ctx: &Context) {
         let text_style = "border: 1px solid black; padding: 2px 5px;";
 
         let killmail_id = killmail.killmail_id;
-        let victim = reports::Victim::load(&killmail_id, ctx);
         let attackers = reports::Attacker::load(&killmail_id, ctx);
         let system = reports::System::load(&killmail.system_id, ctx);
-        let items = reports::Item::load(&killmail_id, ctx);
 
         let mut security = 0.0;
         if let Some(system) = system {
             security = system.security_status;
         }
 
-        let dropped_sum = Self::get_dropped_sum(&items);
+        let value = Self::get_value_by(&killmail.killmail_time, items, &victim, &valuation::Source::default(), prices);
+        let dropped_sum = value.dropped;
         let dropped_span = reports::span(
             "Dropped Volume",
             format!("display: inline-block; width: 100%; text-align: right; background-color: {};", Self::volume_color(&dropped_sum)),
             format!("{}", dropped_sum.separated_string())
         );
 
-        let total_sum = Self::get_total_sum(&items, &victim);
+        let total_sum = value.total();
         let total_span = reports::span(
             "Total Kill Mail Volume",
             format!("display: inline-block; width: 100%; text-align: right; background-color: {};", Self::volume_color(&total_sum)),
@@ -235,7 +280,16 @@ impl Killmail {
         }
     }
 
-    fn write_report(output: &mut dyn Write, killmail: &killmail::KillmailNamed, ctx: &Context) {
+    fn write_sources(output: &mut dyn Write, killmail_id: &i32, source: &valuation::Source, ctx: &Context) {
+        let links: Vec<String> = valuation::Source::get_all()
+            .iter()
+            .filter(|item| *item != source)
+            .map(|item| format!(r#"<a href="{}/api/killmail/{}?price={}">{}</a>"#, ctx.get_root(), killmail_id, item.get_key(), item.get_title()))
+            .collect();
+        reports::div(output, format!("Valuation: <b>{}</b> ({})", source.get_title(), links.join(", ")));
+    }
+
+    fn write_report(output: &mut dyn Write, killmail: &killmail::KillmailNamed, source: &valuation::Source, ctx: &Context) {
         let killmail_id = killmail.killmail_id;
         let victim = reports::Victim::load(&killmail_id, ctx);
         let attackers = reports::Attacker::load(&killmail_id, ctx);
@@ -245,6 +299,7 @@ impl Killmail {
         reports::div(output, format!("{timestamp} [{zkb}]",
             timestamp = ctx.get_api_href("killmail", killmail_id, killmail.killmail_time.to_string()),
             zkb = ctx.get_zkb_href("kill", killmail.get_id("id"), format!("zkb"))));
+        Self::write_sources(output, &killmail_id, source, ctx);
 
         if let Some(system) = system {
             reports::div(output,
@@ -261,13 +316,20 @@ impl Killmail {
         let text_style    = "border: 1px solid black; padding: 2px 5px;";
         let numeric_style = "border: 1px solid black; padding: 2px 5px;; text-align: right;";
 
-        let total_amount = Self::get_total_sum(&items, &victim);
-        let dropped_amount = Self::get_dropped_sum(&items);
+        let ship_id = victim.as_ref().map(|victim| victim.ship_id);
+        let lines = valuation::Line::from_named(&items);
+        let date = killmail.killmail_time.date();
+        let snapshots = valuation::nearest(&Self::load_prices(Self::get_ids(&items, &victim), &date, &date, ctx), &date);
+        let value = valuation::evaluate_at(ship_id, &lines, source, &snapshots);
+        let total_amount = value.total();
+        let dropped_amount = value.dropped;
+        let destroyed_amount = value.ship + value.destroyed;
         if let Some(victim) = victim {
             reports::table_start(output, "Victim", table_style, "Victim");
             reports::table_row_start(output, "");
             reports::table_cell_head(output, "Total Amount", head_style, "Total");
             reports::table_cell_head(output, "Dropped Amount", head_style, "Dropped");
+            reports::table_cell_head(output, "Destroyed Amount (the hull included)", head_style, "Destroyed");
             reports::table_cell_head(output, "Damage Taken", head_style, "Damage");
             reports::table_cell_head(output, "Ship Type", head_style, "Ship Type");
             reports::table_cell_head(output, "Faction Name", head_style, "Faction");
@@ -279,10 +341,12 @@ impl Killmail {
 
             let total_amount_style  = format!("{} background-color: {};", numeric_style, Self::volume_color(&total_amount));
             let dropped_amount_style  = format!("{} background-color: {};", numeric_style, Self::volume_color(&dropped_amount));
+            let destroyed_amount_style  = format!("{} background-color: {};", numeric_style, Self::volume_color(&destroyed_amount));
 
             reports::table_row_end(output);
             reports::table_cell(output, "Total Amount", total_amount_style, total_amount.separated_string());
             reports::table_cell(output, "Dropped Amount", dropped_amount_style, dropped_amount.separated_string());
+            reports::table_cell(output, "Destroyed Amount", destroyed_amount_style, destroyed_amount.separated_string());
             reports::table_cell(output, "Damage Taken", numeric_style, victim.damage_taken.separated_string());
             reports::table_cell(output, "Ship Type", text_style, ctx.get_zkb_href("ship", victim.get_id("ship"), victim.get_name("ship")));
             reports::table_cell(output, "Faction Name", text_style, ctx.get_api_link("faction", victim.get_name("faction")));
@@ -357,7 +421,7 @@ impl Killmail {
                     zipped.destroyed += destroyed as u64;
                 }
                 if zipped.price.is_none() {
-                    zipped.price = provider::get_avg_price(&Some(id))
+                    zipped.price = valuation::get_price_at(source, &id, &snapshots)
                 }
             }
 
@@ -376,7 +440,8 @@ impl Killmail {
                 reports::table_cell(output, "Item name", text_style, name);
                 reports::table_cell(output, "Dropped quantity", numeric_style, desc.dropped.separated_string());
                 reports::table_cell(output, "Destroyed quantity", numeric_style, desc.destroyed.separated_string());
-                reports::table_cell(output, "Item's average price", numeric_style, desc.get_price().separated_string());
+                reports::table_cell(output, "Item's price", numeric_style,
+                    format!(r#"<a href="{}/prices/{}">{}</a>"#, ctx.get_root(), desc.id, desc.get_price().separated_string()));
                 reports::table_cell(output, "Dropped quantity", numeric_style, desc.get_dropped_volume().separated_string());
                 reports::table_cell(output, "Destroyed quantity", numeric_style, desc.get_destroyed_volume().separated_string());
                 reports::table_row_end(output);
@@ -394,9 +459,9 @@ impl Killmail {
         }
     }
 
-    pub fn report(arg: &String, ctx: &Context) -> String {
+    pub fn report(arg: &String, source: &valuation::Source, ctx: &Context) -> String {
         if let Ok(ref id) = arg.parse::<i32>() {
-            Self::report_impl(id, ctx)
+            Self::report_impl(id, source, ctx)
         } else {
             format!("Can't parse {}", arg)
         }
@@ -420,10 +485,10 @@ impl Killmail {
         return output;
     }
 
-    pub fn report_impl(id: &i32, ctx: &Context) -> String {
+    pub fn report_impl(id: &i32, source: &valuation::Source, ctx: &Context) -> String {
         let mut output = String::new();
         if let Some(object) = Self::load(id, ctx) {
-            Self::write_report(&mut output, &object, ctx);
+            Self::write_report(&mut output, &object, source, ctx);
         }
         return output;
     }
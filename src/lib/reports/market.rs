@@ -0,0 +1,211 @@
+use crate::api::{Orders, OrderType};
+use crate::api::item_type::ItemType;
+use crate::market;
+use crate::market::Location;
+use crate::market::arbitrage::{self, Deal, Fees, Rank};
+use crate::navigation::Universe;
+use crate::reports;
+use crate::reports::get_object_name;
+use crate::services::{Context, Category, Report};
+use crate::valuation::HUBS;
+
+use crate::separator::Separatable;
+
+/// Kills on the route are counted for the last day
+pub const RISK_MINUTES: i32 = 24 * 60;
+/// Deals shown on the page
+pub const DEALS_LIMIT: usize = 50;
+/// Only the most profitable deals get the volume and the route
+const CANDIDATES_LIMIT: usize = 200;
+/// Types shown by the traded volume
+pub const VOLUMES_LIMIT: i32 = 100;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Query {
+    pub sources: Vec<Location>,
+    pub destinations: Vec<Location>,
+    pub types: Vec<i32>,
+    pub fees: Fees,
+    pub rank: Rank,
+    pub limit: usize,
+}
+
+fn find_location(universe: &Universe, graph_systems: &Vec<i32>, token: &str) -> Option<Location> {
+    let token = token.trim();
+    let system = |id: &i32| universe.get_system(id).map(|system| Location::System { system_id: system.system_id, region_id: system.region_id });
+    if let Some((_, hub)) = HUBS.iter().find(|(key, _)| key.eq_ignore_ascii_case(token)) {
+        return Some(Location::System { system_id: hub.system_id, region_id: hub.region_id });
+    }
+    if let Ok(id) = token.parse::<i32>() {
+        return system(&id).or(Some(Location::Region(id)));
+    }
+    let same = |name: &Option<String>| name.as_ref().map(|name| name.eq_ignore_ascii_case(token)).unwrap_or(false);
+    for id in graph_systems {
+        if let Some(found) = universe.get_system(id) {
+            if same(&found.system_name) {
+                return system(id);
+            }
+            if same(&found.region_name) {
+                return Some(Location::Region(found.region_id));
+            }
+        }
+    }
+    None
+}
+
+/// Comma separated hubs, system or region names or ids. The unknown ones are returned as errors.
+pub fn get_locations(list: &str, ctx: &Context) -> Result<Vec<Location>, String> {
+    let universe = ctx.universe.read().map_err(|e| e.to_string())?;
+    let systems = universe.get_graph().get_systems();
+    let mut locations = Vec::new();
+    for token in list.split(',').filter(|token| !token.trim().is_empty()) {
+        match find_location(&universe, &systems, token) {
+            Some(location) => locations.push(location),
+            None => return Err(format!("Unknown system or region '{}'", token.trim())),
+        }
+    }
+    Ok(locations)
+}
+
+fn get_system_name(id: &i32, ctx: &Context) -> String {
+    ctx.universe.read().ok()
+        .and_then(|universe| universe.get_system(id).and_then(|system| system.system_name.clone()))
+        .unwrap_or(id.to_string())
+}
+
+fn format_isk(value: f64) -> String {
+    (value.max(0.0) as u64).separated_string()
+}
+
+fn format_option<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or(String::from("-"))
+}
+
+fn get_regions(ctx: &Context) -> Vec<i32> {
+    match reports::load(Category::MarketRegions, ctx) {
+        Report::Ids(regions) => regions,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+/// The snapshots are used for the ingested regions, the rest is loaded from ESI
+pub fn load_orders(locations: &[Location], order_type: OrderType, types: &Vec<i32>, ctx: &Context) -> Orders {
+    let stored = get_regions(ctx);
+    let (local, remote): (Vec<Location>, Vec<Location>) = locations.iter().cloned().partition(|location| stored.contains(&location.get_region_id()));
+    let mut orders = market::load_orders(&remote, &order_type, types);
+    if !local.is_empty() {
+        let regions = local.iter().map(Location::get_region_id).collect();
+        match reports::load(Category::MarketOrders((regions, types.clone(), order_type == OrderType::BUY)), ctx) {
+            Report::MarketOrders(stored) => orders.extend(stored.iter()
+                .filter(|order| local.iter().any(|location| location.get_region_id() == order.region_id && location.contains_system(&order.system_id)))
+                .map(|order| order.to_api())),
+            report => warn!("Unexpected report {:?}", report),
+        }
+    }
+    orders
+}
+
+/// The ranked deals between the sources and the destinations
+pub fn find(query: &Query, ctx: &Context) -> Vec<Deal> {
+    let sells = load_orders(&query.sources, OrderType::SELL, &query.types, ctx);
+    let buys = load_orders(&query.destinations, OrderType::BUY, &query.types, ctx);
+    info!("arbitrage over {} sell and {} buy orders", sells.len(), buys.len());
+    let mut deals = arbitrage::find_deals(&sells, &buys, &query.fees);
+    deals.truncate(CANDIDATES_LIMIT);
+    arbitrage::set_volumes(&mut deals, &|id| ItemType::new(id).and_then(|item| item.get_volume()));
+    let kills = reports::History::kills(&RISK_MINUTES, ctx);
+    if let Ok(universe) = ctx.universe.read() {
+        arbitrage::set_routes(&mut deals, universe.get_graph(), &kills);
+    }
+    arbitrage::sort(&mut deals, query.rank);
+    deals.truncate(query.limit);
+    deals
+}
+
+pub fn arbitrage(query: &Query, ctx: &Context) -> String {
+    let mut output = String::new();
+    let deals = find(query, ctx);
+    reports::div(&mut output, format!("Sales tax {:.1}%, broker fee {:.1}%, kills on the route for the last {} minutes",
+        100.0 * query.fees.sales_tax, 100.0 * query.fees.broker_fee, RISK_MINUTES));
+    if deals.is_empty() {
+        reports::div(&mut output, "No profitable deals were found");
+        return output;
+    }
+    let table_style = "border-collapse: collapse;";
+    let head_style = "border: 1px solid black; padding: 2px 5px; text-align: center;";
+    let text_style = "border: 1px solid black; padding: 2px 5px;";
+    let numeric_style = "border: 1px solid black; padding: 2px 5px; text-align: right;";
+    reports::table_start(&mut output, "Arbitrage", table_style, "Arbitrage");
+    reports::table_row_start(&mut output, "");
+    reports::table_cell_head(&mut output, "Item Type", head_style, "Item");
+    reports::table_cell_head(&mut output, "Buy from the sell orders", head_style, "From");
+    reports::table_cell_head(&mut output, "Sell to the buy orders", head_style, "To");
+    reports::table_cell_head(&mut output, "Quantity", head_style, "Quantity");
+    reports::table_cell_head(&mut output, "Cargo volume, m3", head_style, "Volume");
+    reports::table_cell_head(&mut output, "Paid for the sell orders", head_style, "Cost");
+    reports::table_cell_head(&mut output, "Income after the fees", head_style, "Income");
+    reports::table_cell_head(&mut output, "Profit", head_style, "Profit");
+    reports::table_cell_head(&mut output, "Profit to the cost", head_style, "Margin");
+    reports::table_cell_head(&mut output, "Profit per m3", head_style, "ISK/m3");
+    reports::table_cell_head(&mut output, "Jumps of the shortest route", head_style, "Jumps");
+    reports::table_cell_head(&mut output, "Kills on the route", head_style, "Kills");
+    reports::table_row_end(&mut output);
+    for deal in &deals {
+        reports::table_row_start(&mut output, "");
+        reports::table_cell(&mut output, "Item Type", text_style,
+            format!(r#"<a href="{}/prices/{}">{}</a>"#, ctx.get_root(), deal.type_id, get_object_name(&deal.type_id)));
+        reports::table_cell(&mut output, "From", text_style, ctx.get_api_link("system", get_system_name(&deal.src_system_id, ctx)));
+        reports::table_cell(&mut output, "To", text_style, ctx.get_api_link("system", get_system_name(&deal.dst_system_id, ctx)));
+        reports::table_cell(&mut output, "Quantity", numeric_style, deal.quantity.separated_string());
+        reports::table_cell(&mut output, "Volume", numeric_style, format_option(deal.volume.map(|volume| format!("{:.1}", volume))));
+        reports::table_cell(&mut output, "Cost", numeric_style, format_isk(deal.cost));
+        reports::table_cell(&mut output, "Income", numeric_style, format_isk(deal.income));
+        reports::table_cell(&mut output, "Profit", numeric_style, format_isk(deal.get_profit()));
+        reports::table_cell(&mut output, "Margin", numeric_style, format!("{:.1}%", 100.0 * deal.get_margin()));
+        reports::table_cell(&mut output, "ISK/m3", numeric_style, format_option(deal.get_density().map(format_isk)));
+        reports::table_cell(&mut output, "Jumps", numeric_style, format_option(deal.jumps));
+        reports::table_cell(&mut output, "Kills", numeric_style, format_option(deal.kills));
+        reports::table_row_end(&mut output);
+    }
+    reports::table_end(&mut output);
+    output
+}
+
+/// The most traded types of the region estimated by the snapshots
+pub fn volumes(region_id: &i32, days: &i32, ctx: &Context) -> String {
+    let mut output = String::new();
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(*days as i64);
+    let volumes = match reports::load(Category::TypeVolumes((*region_id, since, VOLUMES_LIMIT)), ctx) {
+        Report::TypeVolumes(volumes) => volumes,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    };
+    reports::div(&mut output, format!("Traded in {} for the last {} days", get_object_name(region_id), days));
+    if volumes.is_empty() {
+        reports::div(&mut output, "The region has no market snapshots");
+        return output;
+    }
+    let table_style = "border-collapse: collapse;";
+    let head_style = "border: 1px solid black; padding: 2px 5px; text-align: center;";
+    let text_style = "border: 1px solid black; padding: 2px 5px;";
+    let numeric_style = "border: 1px solid black; padding: 2px 5px; text-align: right;";
+    reports::table_start(&mut output, "Volumes", table_style, "Volumes");
+    reports::table_row_start(&mut output, "");
+    reports::table_cell_head(&mut output, "Item Type", head_style, "Item");
+    reports::table_cell_head(&mut output, "Estimated traded quantity", head_style, "Traded");
+    reports::table_row_end(&mut output);
+    for volume in &volumes {
+        reports::table_row_start(&mut output, "");
+        reports::table_cell(&mut output, "Item Type", text_style,
+            format!(r#"<a href="{}/prices/{}">{}</a>"#, ctx.get_root(), volume.type_id, get_object_name(&volume.type_id)));
+        reports::table_cell(&mut output, "Traded", numeric_style, volume.traded.unwrap_or_default().separated_string());
+        reports::table_row_end(&mut output);
+    }
+    reports::table_end(&mut output);
+    output
+}
@@ -8,6 +8,14 @@ pub mod region;
 pub mod stargate;
 pub mod constellation;
 pub mod network;
+pub mod statistics;
+pub mod association;
+pub mod alerts;
+pub mod feed;
+pub mod export;
+pub mod prices;
+pub mod market;
+pub mod appraisal;
 
 mod item;
 mod character;
@@ -87,6 +95,20 @@ pub trait ReportableEx {
     fn report_by_id(id: &i32, ctx: &Context, report_type: ReportType) -> String;
 }
 
+/// The ESI name of the object, the id when ESI does not know it
+pub fn get_object_name(id: &i32) -> String {
+    crate::api::object::Object::new(id).map(|object| object.get_name()).unwrap_or(id.to_string())
+}
+
+/// The text safe to put into the HTML or XML element and attribute
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
 pub fn href<S: Into<String>>(url: S, name: S) -> String{
     format!(r#"<a href="{url}">{name}</a>"#, url = url.into(), name = name.into())
 }
@@ -173,6 +195,29 @@ pub fn lazy<S: Into<String>>(output: &mut dyn Write, url: S, ctx: &Context) {
     ).expect(FAIL);
 }
 
+/// Newly saved killmails pushed by the server, the latest is the first
+pub fn live<S: Into<String>>(output: &mut dyn Write, query: S, ctx: &Context) {
+    std::fmt::write(
+        output,
+        format_args!(r##"
+        <div id = "{id}"></div>
+        <script>
+            var feed = new EventSource("{root}/stream/kills?{query}");
+            feed.addEventListener("kill", function(message) {{
+                var kill = JSON.parse(message.data);
+                var line = document.createElement("div");
+                line.innerHTML = kill.time + " <a href='" + kill.url + "'>" + kill.ship + "</a> " +
+                    (kill.victim || "") + " in " + kill.system + " " + kill.value.toLocaleString() + " ISK";
+                var list = document.getElementById("{id}");
+                list.insertBefore(line, list.firstChild);
+            }} );
+        </script>"##,
+        id=crate::create_id(),
+        root=ctx.get_root(),
+        query=query.into())
+    ).expect(FAIL);
+}
+
 pub fn find_id<S: Into<String>>(category: S, name: S, ctx: &Context) -> Option<i32> {
     use crate::services::*;
 
@@ -187,11 +232,16 @@ pub fn find_id<S: Into<String>>(category: S, name: S, ctx: &Context) -> Option<i
 pub fn load(category: Category, ctx: &Context) -> Report {
     use std::{thread, time};
     let msg_id = crate::create_id().to_simple();
+    let variant = crate::metrics::get_variant(&category);
+    let started = time::Instant::now();
     ctx.database.push(Message::Find((msg_id, category)));
     loop {
         while let Some(msg) = ctx.responses.pop() {
             if let Message::Report((id, content)) = msg {
                 if id == msg_id {
+                    let elapsed = started.elapsed();
+                    let seconds = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+                    crate::metrics::observe("zkb_report_load_seconds", &[("category", variant.as_str())], seconds);
                     return content;
                 } else {
                     ctx.responses.push(Message::Report((id, content)));
@@ -252,6 +302,13 @@ pub fn map<S: Into<String>>(output: &mut dyn Write, id: &i32, deep: u32, target:
                             .then(html => document.getElementById("extinfo").innerHTML = html)
                             .catch((err) => console.log("Can’t access " + url + ": " + err));
                     }} );
+                    var feed = new EventSource("{root}/stream/kills");
+                    feed.addEventListener("kill", function(message) {{
+                        var kill = JSON.parse(message.data);
+                        if (nodes_ds.get(kill.system_id)) {{
+                            nodes_ds.update({{ id: kill.system_id, color: "red", title: kill.ship + " " + kill.time }});
+                        }}
+                    }} );
                 }}
 
                 start();
@@ -2,6 +2,11 @@
 use crate::services::Context;
 use crate::models;
 use crate::reports;
+use crate::provider;
+use crate::services::{Category, Report};
+use crate::navigation::{Kills, Universe, Metric, Timeline};
+use crate::navigation::activity;
+use crate::models::killmail::SystemLoss;
 
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -18,12 +23,14 @@ pub struct Node {
     shape: String,
     #[serde(rename = "borderWidth")]
     border_width: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u32>,
     #[serde(skip)]
     neighbors: Vec<i32>,
 
 }
 impl Node {
-    fn new(model: models::system::SystemNamed, mass: u32, ctx: &Context) -> Self {
+    fn new(model: &models::system::SystemNamed, mass: u32, neighbors: Vec<i32>, kills: &NetworkKills) -> Self {
         let mut output = String::new();
         let id = model.system_id;
         let system = model.get_name("system");
@@ -38,9 +45,9 @@ impl Node {
         reports::div(&mut output, format!("System: {}", colored));
         reports::div(&mut output, format!("Constellation: {}", &constellation));
         reports::div(&mut output, format!("Region: {}", &region));
-        reports::div(&mut output, format!("Kills last 10 minutes: {}", reports::History::system_count(&id, &10, ctx)));
-        reports::div(&mut output, format!("Kills last 60 minutes: {}", reports::History::system_count(&id, &60, ctx)));
-        reports::div(&mut output, format!("{}", model.observatory.map(|_| String::from("Jovian Observatory")).unwrap_or_default()));
+        reports::div(&mut output, format!("Kills last 10 minutes: {}", kills.last_10.get(&id).cloned().unwrap_or_default()));
+        reports::div(&mut output, format!("Kills last 60 minutes: {}", kills.last_60.get(&id).cloned().unwrap_or_default()));
+        reports::div(&mut output, model.observatory.clone().map(|_| String::from("Jovian Observatory")).unwrap_or_default());
 
         let title = format!("{}", output);
         Self {
@@ -52,19 +59,57 @@ impl Node {
             title: Some(title),
             shape: shape,
             border_width: 1,
-            neighbors: reports::system::System::get_neighbors(&id, ctx).iter().map(|x| x.neighbor_id).collect(),
+            size: None,
+            neighbors,
+        }
+    }
+
+    /// Character, corporation or alliance node of the association graph
+    pub fn actor<S: Into<String>>(id: i32, label: S, class: S, mass: u32, title: String, color: S) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            color: color.into(),
+            mass,
+            group: Some(class.into()),
+            title: Some(title),
+            shape: String::from("box"),
+            border_width: 1,
+            size: None,
+            neighbors: Vec::new(),
+        }
+    }
+}
+
+/// Kills counts loaded once for the whole network
+struct NetworkKills {
+    last_10: Kills,
+    last_60: Kills,
+}
+impl NetworkKills {
+    fn load(ctx: &Context) -> Self {
+        Self {
+            last_10: reports::History::kills(&10, ctx),
+            last_60: reports::History::kills(&60, ctx),
         }
     }
 }
 
+fn make_node(universe: &Universe, id: &i32, mass: u32, kills: &NetworkKills) -> Option<Node> {
+    universe.get_system(id).map(|system| Node::new(system, mass, universe.get_neighbors(id).to_vec(), kills))
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default, Eq)]
 pub struct Edge {
     pub from: i32,
     pub to: i32,
     pub color: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 impl Edge {
-    pub fn new(from: i32, to: i32) -> Self { Self { from, to, color: String::from("Black") } }
+    pub fn new(from: i32, to: i32) -> Self { Self { from, to, color: String::from("Black"), title: None } }
+    pub fn colored(from: i32, to: i32, color: String, title: String) -> Self { Self { from, to, color, title: Some(title) } }
 }
 impl PartialEq for Edge {
     fn eq(&self, other: &Self) -> bool {
@@ -72,12 +117,11 @@ impl PartialEq for Edge {
     }
 }
 
-fn make_system_network(ids: &Vec<i32>, ctx: &Context, nodes: &mut HashMap<i32, Node>, deep: u32) {
+fn make_system_network(ids: &Vec<i32>, universe: &Universe, kills: &NetworkKills, nodes: &mut HashMap<i32, Node>, deep: u32) {
     let mut neighbors = Vec::new();
     for id in ids {
         if !nodes.contains_key(id) {
-            if let Some(system) = reports::system::System::load(id, ctx) {
-                let mut node = Node::new(system, 1, ctx);
+            if let Some(mut node) = make_node(universe, id, 1, kills) {
                 if 0 == deep {
                     node.shape = String::from("hexagon");    
                 }    
@@ -87,18 +131,20 @@ fn make_system_network(ids: &Vec<i32>, ctx: &Context, nodes: &mut HashMap<i32, N
         }
     }
     if deep > 0 {
-        make_system_network(&neighbors, ctx, nodes, deep - 1);
+        make_system_network(&neighbors, universe, kills, nodes, deep - 1);
     }
 }
 
 pub fn get_system_nodes(id: &i32, deep: u32, ctx: &Context) -> HashMap<i32, Node> {
     let mut nodes:  HashMap<i32, Node> = HashMap::new();
     if deep > 0 {
-        if let Some(system) = reports::system::System::load(id, ctx) {
-            let node = Node::new(system, 3, ctx);
-            let neighbors = node.neighbors.clone();
-            nodes.insert(node.id, node);
-            make_system_network(&neighbors, ctx, &mut nodes, deep-1);
+        let kills = NetworkKills::load(ctx);
+        if let Ok(universe) = ctx.universe.read() {
+            if let Some(node) = make_node(&universe, id, 3, &kills) {
+                let neighbors = node.neighbors.clone();
+                nodes.insert(node.id, node);
+                make_system_network(&neighbors, &universe, &kills, &mut nodes, deep-1);
+            }
         }
     }
     return nodes;
@@ -125,16 +171,16 @@ pub fn get_system_edges(id: &i32, deep: u32, ctx: &Context) -> Vec<Edge> {
 pub fn get_constellation_nodes(id: &i32, ctx: &Context) -> HashMap<i32, Node> {
     let mut nodes:  HashMap<i32, Node> = HashMap::new();
     let mut neighbors = Vec::new();
-    if let Some(systems) = reports::constellation::Constellation::get_systems(id, ctx) {
-        for system in systems.into_iter() {
-            let node = Node::new(system, 3, ctx);
+    let kills = NetworkKills::load(ctx);
+    if let Ok(universe) = ctx.universe.read() {
+        for system in universe.get_constellation_systems(id) {
+            let node = Node::new(system, 3, universe.get_neighbors(&system.system_id).to_vec(), &kills);
             neighbors.append(&mut node.neighbors.clone());
             nodes.insert(node.id, node);    
         }
         for id in &neighbors {
             if !nodes.contains_key(id) {
-                if let Some(system) = reports::system::System::load(id, ctx) {
-                    let mut node = Node::new(system, 1, ctx);
+                if let Some(mut node) = make_node(&universe, id, 1, &kills) {
                     node.shape = String::from("hexagon");
                     nodes.insert(node.id, node);
                 }
@@ -150,23 +196,17 @@ pub fn get_constellation_edges(id: &i32, ctx: &Context) -> Vec<Edge> {
 
 pub fn get_region_nodes(id: &i32, ctx: &Context) -> HashMap<i32, Node> {
     let mut nodes:  HashMap<i32, Node> = HashMap::new();    
-    if let Some(constellations) = reports::region::Region::get_constellation(id, ctx) {
+    let kills = NetworkKills::load(ctx);
+    if let Ok(universe) = ctx.universe.read() {
         let mut neighbors = HashSet::new();
-        for constellation in constellations.into_iter() {
-            let id = constellation.constellation_id;
-            if let Some(systems) = reports::constellation::Constellation::get_systems(&id, ctx) {
-                for system in systems.into_iter() {
-                    let node = Node::new(system, 1, ctx);
-                    let other: HashSet<i32> = node.neighbors.clone().into_iter().collect();
-                    neighbors = neighbors.union(&other).cloned().collect::<HashSet<_>>();
-                    nodes.insert(node.id, node);
-                }
-            }
+        for system in universe.get_region_systems(id) {
+            let node = Node::new(system, 1, universe.get_neighbors(&system.system_id).to_vec(), &kills);
+            neighbors.extend(node.neighbors.iter().cloned());
+            nodes.insert(node.id, node);
         }
         for id in &neighbors {
             if !nodes.contains_key(id) {
-                if let Some(system) = reports::system::System::load(id, ctx) {
-                    let mut node = Node::new(system, 1, ctx);
+                if let Some(mut node) = make_node(&universe, id, 1, &kills) {
                     node.shape = String::from("hexagon");
                     nodes.insert(node.id, node);
                 }
@@ -179,3 +219,51 @@ pub fn get_region_nodes(id: &i32, ctx: &Context) -> HashMap<i32, Node> {
 pub fn get_region_edges(id: &i32, ctx: &Context) -> Vec<Edge> {
     return build_edges(&get_region_nodes(id, ctx));
 }
+
+pub fn get_area_nodes(area: &str, id: &i32, deep: u32, ctx: &Context) -> HashMap<i32, Node> {
+    match area {
+        "system" => get_system_nodes(id, deep, ctx),
+        "constellation" => get_constellation_nodes(id, ctx),
+        "region" => get_region_nodes(id, ctx),
+        _ => HashMap::new()
+    }
+}
+
+fn get_losses(ids: Vec<i32>, minutes: &i32, ctx: &Context) -> Vec<SystemLoss> {
+    match reports::load(Category::SystemsLosses((ids, *minutes)), ctx) {
+        Report::SystemsLosses(losses) => losses,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+/// Nodes colored and sized by the metric value instead of the security status
+pub fn get_heatmap_nodes(metric: &Metric, area: &str, id: &i32, deep: u32, minutes: &i32, ctx: &Context) -> HashMap<i32, Node> {
+    let mut nodes = get_area_nodes(area, id, deep, ctx);
+    let losses = get_losses(nodes.keys().cloned().collect(), minutes, ctx);
+    let heat = activity::get_heat(&losses, metric, &|ship_id| provider::get_avg_price(&Some(*ship_id)).unwrap_or_default());
+    let max = heat.values().cloned().fold(0.0, f32::max);
+    for node in nodes.values_mut() {
+        let value = heat.get(&node.id).cloned().unwrap_or_default();
+        let ratio = if max > 0.0 { value / max } else { 0.0 };
+        node.color = activity::get_heat_color(ratio);
+        node.shape = String::from("dot");
+        node.size = Some(10 + (30.0 * ratio) as u32);
+        node.title = node.title.clone().map(|title| {
+            let mut output = title;
+            reports::div(&mut output, format!("{:?} last {} minutes: {}", metric, minutes, value.round() as u64));
+            output
+        });
+    }
+    nodes
+}
+
+/// Kills by system in the 10 minutes buckets to replay the area activity
+pub fn get_timeline(area: &str, id: &i32, deep: u32, minutes: &i32, ctx: &Context) -> Timeline {
+    use chrono::Utc;
+    let ids = get_area_nodes(area, id, deep, ctx).keys().cloned().collect();
+    let losses = get_losses(ids, minutes, ctx);
+    Timeline::new(&losses, &Utc::now().naive_utc(), minutes)
+}
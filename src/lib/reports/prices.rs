@@ -0,0 +1,115 @@
+use crate::models::Date;
+use crate::models::price::Price;
+use crate::services::{Context, Category, Report};
+use crate::reports;
+use crate::reports::get_object_name;
+use crate::valuation::REFERENCE_HUB;
+
+use crate::separator::Separatable;
+use serde_json::json;
+
+/// Days of the history shown by default
+pub const HISTORY_DAYS: i32 = 90;
+/// The history is a year long at most
+pub const MAX_HISTORY_DAYS: i32 = 365;
+
+/// The requested days limited to a year
+pub fn clamp_days(days: Option<i32>) -> i32 {
+    days.unwrap_or(HISTORY_DAYS).clamp(1, MAX_HISTORY_DAYS)
+}
+
+pub fn load_history(type_id: &i32, days: &i32, ctx: &Context) -> Vec<Price> {
+    let since = chrono::Utc::now().naive_utc().date() - chrono::Duration::days(*days as i64);
+    match reports::load(Category::PriceHistory((*type_id, since)), ctx) {
+        Report::Prices(prices) => prices,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+fn get_dataset(prices: &[Price], label: String, color: &str, value: &dyn Fn(&Price) -> Option<f32>) -> serde_json::Value {
+    json!({
+        "label": label,
+        "borderColor": color,
+        "fill": false,
+        "spanGaps": true,
+        "data": prices.iter().map(value).collect::<Vec<Option<f32>>>(),
+    })
+}
+
+fn format_date(date: &Date) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn format_price(price: &Option<f32>) -> String {
+    price.map(|price| (price as u64).separated_string()).unwrap_or_default()
+}
+
+/// The daily price snapshots of the type as the chart and the table
+pub fn history(type_id: &i32, days: &i32, ctx: &Context) -> String {
+    let mut output = String::new();
+    let prices = load_history(type_id, days, ctx);
+    reports::div(&mut output, format!("{} prices for the last {} days", get_object_name(type_id), days));
+    if prices.is_empty() {
+        reports::div(&mut output, "No price snapshots were taken yet");
+        return output;
+    }
+    let labels: Vec<String> = prices.iter().map(|price| format_date(&price.price_date)).collect();
+    let datasets = vec![
+        get_dataset(&prices, String::from("ESI average"), "SteelBlue", &|price| price.average),
+        get_dataset(&prices, String::from("ESI adjusted"), "Gray", &|price| price.adjusted),
+        get_dataset(&prices, format!("{} buy", REFERENCE_HUB.name), "SeaGreen", &|price| price.buy),
+        get_dataset(&prices, format!("{} sell", REFERENCE_HUB.name), "IndianRed", &|price| price.sell),
+    ];
+    reports::script(&mut output, ctx.get_js_url("Chart.bundle.min.js")); // https://www.chartjs.org/
+    reports::write(&mut output, format!(r#"
+        <div style="width: 900px; height: 400px;"><canvas id="PriceHistory"></canvas></div>
+        <script>
+            new Chart(document.getElementById("PriceHistory").getContext('2d'), {{
+                type: 'line',
+                data: {{ labels: {labels}, datasets: {datasets} }},
+                options: {{ maintainAspectRatio: false }},
+            }});
+        </script>"#,
+        labels = json!(labels),
+        datasets = json!(datasets)));
+
+    let table_style = "border-collapse: collapse;";
+    let head_style = "border: 1px solid black; padding: 2px 5px; text-align: center;";
+    let numeric_style = "border: 1px solid black; padding: 2px 5px; text-align: right;";
+    reports::table_start(&mut output, "Prices", table_style, "Prices");
+    reports::table_row_start(&mut output, "");
+    reports::table_cell_head(&mut output, "Snapshot Date", head_style, "Date");
+    reports::table_cell_head(&mut output, "ESI Average Price", head_style, "Average");
+    reports::table_cell_head(&mut output, "ESI Adjusted Price", head_style, "Adjusted");
+    reports::table_cell_head(&mut output, "Best Buy Order", head_style, "Buy");
+    reports::table_cell_head(&mut output, "Best Sell Order", head_style, "Sell");
+    reports::table_row_end(&mut output);
+    for price in prices.iter().rev() {
+        reports::table_row_start(&mut output, "");
+        reports::table_cell(&mut output, "Snapshot Date", numeric_style, format_date(&price.price_date));
+        reports::table_cell(&mut output, "ESI Average Price", numeric_style, format_price(&price.average));
+        reports::table_cell(&mut output, "ESI Adjusted Price", numeric_style, format_price(&price.adjusted));
+        reports::table_cell(&mut output, "Best Buy Order", numeric_style, format_price(&price.buy));
+        reports::table_cell(&mut output, "Best Sell Order", numeric_style, format_price(&price.sell));
+        reports::table_row_end(&mut output);
+    }
+    reports::table_end(&mut output);
+    output
+}
+
+/// The daily price snapshots of the type, the oldest first
+pub fn history_json(type_id: &i32, days: &i32, ctx: &Context) -> String {
+    let prices: Vec<serde_json::Value> = load_history(type_id, days, ctx).iter()
+        .map(|price| json!({
+            "date": format_date(&price.price_date),
+            "average": price.average,
+            "adjusted": price.adjusted,
+            "buy": price.buy,
+            "sell": price.sell,
+        }))
+        .collect();
+    json!({ "type_id": type_id, "hub": REFERENCE_HUB.name, "prices": prices }).to_string()
+}
@@ -23,6 +23,7 @@ impl reports::ReportableEx for Region {
                 Self::neighbors(&mut output, id, ctx);
                 Self::constellations(&mut output, &region.region_id, &ctx);
                 reports::map(&mut output, id, 1, "region", &ctx);
+                reports::live(&mut output, format!("area={}", id), ctx);
                 reports::lazy(&mut output, format!("history/region/{}/{}", id, 60), &ctx);
                 reports::lazy(&mut output, format!("stat/region/{}", id), &ctx);
             }
@@ -70,7 +71,11 @@ impl Region {
         }
     }
     
-    pub fn stat(id: &i32, ctx: &Context) -> String {
+    pub fn stat(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::report(api::stats::Entity::Region(*id), minutes, ctx)
+    }
+
+    pub fn zkb_stat(id: &i32, ctx: &Context) -> String {
         use api::stats::Stats;
         use api::stats::Entity;
         use api::stats::TopList;
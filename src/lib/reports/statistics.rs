@@ -0,0 +1,270 @@
+use crate::api::stats::{Entity, Stats, Activity};
+use crate::services::{Context, Category, Report};
+use crate::models::DateTime;
+use crate::models::participant::Participant;
+use crate::statistics::{self, LocalStats, Top, Side, WeekActivity, Doctrine, HullStat};
+use crate::models::Integer;
+use crate::reports;
+use crate::reports::get_object_name;
+use crate::valuation;
+
+use crate::separator::Separatable;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Default statistics window, the same week as the zKillboard top lists
+pub const WEEK_MINUTES: i32 = 7 * 24 * 60;
+/// Default window to detect doctrines, long enough to see them recurring
+pub const MONTH_MINUTES: i32 = 30 * 24 * 60;
+
+pub fn get_class(entity: &Entity) -> &'static str {
+    match entity {
+        Entity::Character(_) => "character",
+        Entity::Corporation(_) => "corporation",
+        Entity::Alliance(_) => "alliance",
+        Entity::Faction(_) => "faction",
+        Entity::Ship(_) => "ship",
+        Entity::Group(_) => "group",
+        Entity::System(_) => "system",
+        Entity::Constellation(_) => "constellation",
+        Entity::Region(_) => "region",
+    }
+}
+
+pub fn get_entity(class: &str, id: i32) -> Option<Entity> {
+    match class {
+        "character" => Some(Entity::Character(id)),
+        "corporation" => Some(Entity::Corporation(id)),
+        "alliance" => Some(Entity::Alliance(id)),
+        "faction" => Some(Entity::Faction(id)),
+        "system" => Some(Entity::System(id)),
+        "constellation" => Some(Entity::Constellation(id)),
+        "region" => Some(Entity::Region(id)),
+        _ => None
+    }
+}
+
+fn get_id(entity: &Entity) -> i32 {
+    match entity {
+        Entity::Character(id) | Entity::Corporation(id) | Entity::Alliance(id) |
+        Entity::Faction(id) | Entity::Ship(id) | Entity::Group(id) |
+        Entity::System(id) | Entity::Constellation(id) | Entity::Region(id) => *id,
+    }
+}
+
+fn get_participants(category: Category, ctx: &Context) -> Vec<Participant> {
+    match reports::load(category, ctx) {
+        Report::Participants(participants) => participants,
+        report => {
+            warn!("Unexpected report {:?}", report);
+            Vec::new()
+        }
+    }
+}
+
+pub fn load(entity: &Entity, minutes: &i32, ctx: &Context) -> Vec<Participant> {
+    get_participants(Category::Statistics((entity.clone(), *minutes)), ctx)
+}
+
+pub fn load_between(entity: &Entity, start: &DateTime, end: &DateTime, ctx: &Context) -> Vec<Participant> {
+    get_participants(Category::Activity((entity.clone(), *start, *end)), ctx)
+}
+
+/// Value of the each killmail with the items by the snapshots nearest to the kill date, the same as the killmail report
+fn get_values(participants: &[Participant], ctx: &Context) -> HashMap<Integer, u64> {
+    let victims: Vec<&Participant> = participants.iter().filter(|participant| participant.is_victim).collect();
+    let ids: Vec<Integer> = victims.iter().map(|victim| victim.killmail_id).collect();
+    let mut items = reports::Item::load_all(&ids, ctx);
+    let dates: Vec<_> = victims.iter().map(|victim| victim.killmail_time.date()).collect();
+    let prices = match (dates.iter().min(), dates.iter().max()) {
+        (Some(first), Some(last)) => {
+            let mut types: Vec<i32> = items.values().flatten().map(|item| item.item_type_id)
+                .chain(victims.iter().filter_map(|victim| victim.ship_id))
+                .collect();
+            types.sort_unstable();
+            types.dedup();
+            reports::Killmail::load_prices(types, first, last, ctx)
+        },
+        _ => Vec::new()
+    };
+    let source = valuation::Source::default();
+    victims.into_iter()
+        .map(|victim| {
+            let lines = valuation::Line::from_named(&items.remove(&victim.killmail_id));
+            let snapshots = valuation::nearest(&prices, &victim.killmail_time.date());
+            (victim.killmail_id, valuation::evaluate_at(victim.ship_id, &lines, &source, &snapshots).total())
+        })
+        .collect()
+}
+
+fn get_value(values: &HashMap<Integer, u64>) -> impl Fn(&Integer) -> u64 + '_ {
+    move |killmail_id| values.get(killmail_id).cloned().unwrap_or_default()
+}
+
+pub fn get_stats(entity: &Entity, minutes: &i32, ctx: &Context) -> LocalStats {
+    let participants = load(entity, minutes, ctx);
+    LocalStats::new(entity, &participants, &get_value(&get_values(&participants, ctx)))
+}
+
+/// Hour of the week profile for the arbitrary date range
+pub fn get_activity(entity: &Entity, side: &Side, start: &DateTime, end: &DateTime, ctx: &Context) -> WeekActivity {
+    WeekActivity::new(entity, &load_between(entity, start, end, ctx), side)
+}
+
+fn get_system_name(id: &i32, ctx: &Context) -> String {
+    ctx.universe.read().ok()
+        .and_then(|universe| universe.get_system(id).map(|system| system.get_name("system")))
+        .unwrap_or(id.to_string())
+}
+
+fn write_top<F>(output: &mut dyn Write, title: &str, top: &Top, href: F)
+    where F: Fn(&i32) -> String
+{
+    if top.is_empty() {
+        return;
+    }
+    let table_style = "border-collapse: collapse;";
+    let text_style  = "border: 1px solid black; padding: 1px 5px;";
+    reports::table_start(output, title, table_style, "");
+    reports::caption(output, title);
+    for (id, kills) in top {
+        reports::table_row_start(output, "");
+        reports::table_cell(output, "Kills", text_style, kills.to_string());
+        reports::table_cell(output, title, text_style, href(id));
+        reports::table_row_end(output);
+    }
+    reports::table_end(output);
+}
+
+/// Statistics computed from the saved killmails with the link to the zKillboard figures
+pub fn report(entity: Entity, minutes: &i32, ctx: &Context) -> String {
+    let mut output = String::new();
+    let participants = load(&entity, minutes, ctx);
+    let stats = LocalStats::new(&entity, &participants, &get_value(&get_values(&participants, ctx)));
+    let class = get_class(&entity);
+    reports::div(&mut output, format!("Last {} minutes activity", minutes));
+    if statistics::is_area(&entity) {
+        reports::div(&mut output, format!("Ships destroyed: {}", stats.ships_destroyed));
+        reports::div(&mut output, format!("ISK destroyed: {}", stats.isk_destroyed.separated_string()));
+    } else {
+        Stats::report_win_loses(&mut output, "Ships", Some(stats.ships_destroyed as i32), Some(stats.ships_lost as i32));
+        Stats::report_win_loses(&mut output, "Solo", Some(stats.solo_kills as i32), Some(stats.solo_losses as i32));
+        reports::div(&mut output, format!("ISK destroyed: {} lost: {}", stats.isk_destroyed.separated_string(), stats.isk_lost.separated_string()));
+        reports::div(&mut output, format!("Danger: {} %", stats.danger_ratio()));
+        reports::div(&mut output, format!("Gangs: {} %", stats.gang_ratio()));
+        let activity = WeekActivity::new(&entity, &participants, &Side::Kills);
+        Activity::write(&mut output, &activity.to_activity(), ctx);
+    }
+    write_top(&mut output, "Ships", &stats.top_ships, |id| ctx.get_zkb_href("ship", *id, get_object_name(id)));
+    write_top(&mut output, "Systems", &stats.top_systems, |id| ctx.get_api_href("system", *id, get_system_name(id, ctx)));
+    write_top(&mut output, "Corporations", &stats.top_corporations, |id| ctx.get_api_href("corporation", *id, get_object_name(id)));
+    let url = format!("{}/zkb/{}/{}", ctx.get_root(), class, get_id(&entity));
+    reports::div(&mut output, reports::href(url, String::from("zKillboard statistics")));
+    if let Entity::Character(id) | Entity::Corporation(id) | Entity::Alliance(id) = entity {
+        let url = format!("{}/associates/{}/{}/{}", ctx.get_root(), class, id, minutes);
+        reports::div(&mut output, reports::href(url, String::from("Associates")));
+    }
+    output
+}
+
+fn get_hulls(hulls: &[(i32, u32)], ctx: &Context) -> String {
+    hulls.iter()
+        .map(|(id, count)| format!("{} &times; {}", count, ctx.get_zkb_href("ship", *id, get_object_name(id))))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Recurring fleet compositions of the corporation or alliance
+pub fn doctrines(entity: Entity, minutes: &i32, ctx: &Context) -> String {
+    let mut output = String::new();
+    let fleets = statistics::doctrine::get_fleets(&entity, &load(&entity, minutes, ctx));
+    let doctrines = Doctrine::detect(fleets);
+    let table_style = "border-collapse: collapse;";
+    let text_style  = "border: 1px solid black; padding: 1px 5px;";
+    let date = |time: &Option<DateTime>| time.map(|time| time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+    reports::table_start(&mut output, "Doctrines", table_style, "");
+    reports::caption(&mut output, format!("Doctrines for the last {} minutes", minutes));
+    reports::table_row_start(&mut output, "");
+    reports::table_cell_head(&mut output, "typical hulls", text_style, "Hulls");
+    reports::table_cell_head(&mut output, "killmails", text_style, "Seen");
+    reports::table_cell_head(&mut output, "first seen", text_style, "First");
+    reports::table_cell_head(&mut output, "last seen", text_style, "Last");
+    reports::table_row_end(&mut output);
+    for doctrine in &doctrines {
+        reports::table_row_start(&mut output, "");
+        reports::table_cell(&mut output, "typical hulls", text_style, get_hulls(&doctrine.hulls, ctx));
+        reports::table_cell(&mut output, "killmails", text_style, doctrine.seen.to_string());
+        reports::table_cell(&mut output, "first seen", text_style, date(&doctrine.first_seen));
+        reports::table_cell(&mut output, "last seen", text_style, date(&doctrine.last_seen));
+        reports::table_row_end(&mut output);
+    }
+    reports::table_end(&mut output);
+    output
+}
+
+/// Kills and losses by the hull flown with the links to the killmails
+pub fn hulls(entity: Entity, minutes: &i32, ctx: &Context) -> String {
+    let mut output = String::new();
+    let participants = load(&entity, minutes, ctx);
+    let hulls = HullStat::collect(&entity, &participants, &get_value(&get_values(&participants, ctx)));
+    let class = get_class(&entity);
+    let id = get_id(&entity);
+    let table_style = "border-collapse: collapse;";
+    let text_style  = "border: 1px solid black; padding: 1px 5px;";
+    let numeric_style = "border: 1px solid black; padding: 1px 5px; text-align: right;";
+    reports::table_start(&mut output, "Hulls", table_style, "");
+    reports::caption(&mut output, format!("Hulls for the last {} minutes", minutes));
+    reports::table_row_start(&mut output, "");
+    reports::table_cell_head(&mut output, "ship", text_style, "Hull");
+    reports::table_cell_head(&mut output, "kills made while flying it", text_style, "Kills");
+    reports::table_cell_head(&mut output, "losses in it", text_style, "Losses");
+    reports::table_cell_head(&mut output, "ISK destroyed", text_style, "Destroyed");
+    reports::table_cell_head(&mut output, "ISK lost", text_style, "Lost");
+    reports::table_cell_head(&mut output, "ISK efficiency", text_style, "Eff");
+    reports::table_cell_head(&mut output, "average attackers count", text_style, "Fleet");
+    reports::table_cell_head(&mut output, "solo kills share", text_style, "Solo");
+    reports::table_row_end(&mut output);
+    for hull in &hulls {
+        let url = format!("{}/hulls/{}/{}/{}?minutes={}", ctx.get_root(), class, id, hull.ship_id, minutes);
+        reports::table_row_start(&mut output, "");
+        reports::table_cell(&mut output, "ship", text_style, reports::href(url, get_object_name(&hull.ship_id)));
+        reports::table_cell(&mut output, "kills made while flying it", numeric_style, hull.kills.to_string());
+        reports::table_cell(&mut output, "losses in it", numeric_style, hull.losses.to_string());
+        reports::table_cell(&mut output, "ISK destroyed", numeric_style, hull.isk_destroyed.separated_string());
+        reports::table_cell(&mut output, "ISK lost", numeric_style, hull.isk_lost.separated_string());
+        reports::table_cell(&mut output, "ISK efficiency", numeric_style, format!("{} %", hull.efficiency()));
+        reports::table_cell(&mut output, "average attackers count", numeric_style, format!("{:.1}", hull.average_fleet()));
+        reports::table_cell(&mut output, "solo kills share", numeric_style, format!("{} %", hull.solo_share()));
+        reports::table_row_end(&mut output);
+    }
+    reports::table_end(&mut output);
+    output
+}
+
+/// Killmails where the entity flew the hull
+pub fn hull_killmails(entity: Entity, ship_id: &i32, minutes: &i32, ctx: &Context) -> String {
+    let mut output = String::new();
+    let ids = HullStat::collect(&entity, &load(&entity, minutes, ctx), &|_| 0).into_iter()
+        .find(|hull| hull.ship_id == *ship_id)
+        .map(|hull| hull.killmails)
+        .unwrap_or_default();
+    reports::div(&mut output, format!("{} in {} for the last {} minutes",
+        ctx.get_api_href(get_class(&entity), get_id(&entity), get_object_name(&get_id(&entity))),
+        ctx.get_zkb_href("ship", *ship_id, get_object_name(ship_id)),
+        minutes));
+    if ids.is_empty() {
+        return output;
+    }
+    match reports::load(Category::Killmails(ids), ctx) {
+        Report::History(killmails) => {
+            reports::table_start(&mut output, "Killmails", "border-collapse: collapse;", "");
+            reports::Killmail::write_head(&mut output);
+            reports::Killmail::write_rows(&mut output, &killmails, ctx);
+            reports::table_end(&mut output);
+        },
+        report => {
+            reports::div(&mut output, format!("Unexpected report {:?}", report));
+        }
+    }
+    output
+}
@@ -3,6 +3,7 @@ use crate::models;
 use crate::services;
 use crate::services::Context;
 use crate::reports;
+use crate::navigation::{RouteQuery, Hop, Safety, RouteDanger, ShipClass, Predicate, SearchQuery, Found};
 use crate::separator::Separatable;
 use std::fmt::Write;
 
@@ -30,6 +31,7 @@ impl reports::ReportableEx for System {
                     reports::Constellation::systems(&mut output, &system.get_id("constellation"), &ctx);
                     reports::Region::constellations(&mut output, &system.get_id("region"), &ctx);
                     reports::map(&mut output, id, 5, "system", &ctx);
+                    reports::live(&mut output, format!("area={}", id), ctx);
                     reports::lazy(&mut output, format!("history/system/{}/{}", id, 60), &ctx);
                     reports::lazy(&mut output, format!("stat/system/{}", id), &ctx);
                     reports::div(&mut output, "");
@@ -75,11 +77,20 @@ impl System {
     }
     
     pub fn get_neighbors(id: &i32, ctx: &Context) -> Vec<models::system::SystemNeighbors> {
-        use services::{Category, Report, Area};
-        match reports::load(Category::Neighbors(Area::System(*id)), &ctx) {
-            Report::SystemNeighbors(neighbors) => neighbors,
-            _ => Vec::new()
+        let mut neighbors = Vec::new();
+        if let Ok(universe) = ctx.universe.read() {
+            let own_name = universe.get_system(id).and_then(|system| system.system_name.clone());
+            for neighbor_id in universe.get_neighbors(id) {
+                neighbors.push(models::system::SystemNeighbors {
+                    own_id: *id,
+                    own_name: own_name.clone(),
+                    neighbor_id: *neighbor_id,
+                    neighbor_name: universe.get_system(neighbor_id).and_then(|system| system.system_name.clone()),
+                });
+            }
         }
+        neighbors.sort_by(|a, b| a.neighbor_name.cmp(&b.neighbor_name));
+        neighbors
     }
 
     fn neighbors(output: &mut dyn Write, id: &i32, ctx: &Context) {
@@ -109,27 +120,24 @@ impl System {
         }
     }
 
-    // todo create api type for route
-    fn get_route(departure: &i32, destination: &i32, flag: &str) -> Option<Vec<i32>> {
-        let response = if flag.is_empty(){
-            api::gw::eve_api(&format!("route/{}/{}", departure, destination)).unwrap_or_default()
-        } else {
-            api::gw::eve_api_ex(
-                &format!("route/{}/{}", departure, destination),
-                &format!("flag={}", flag)
-            ).unwrap_or_default()
-        };
-        serde_json::from_str(&response).ok()
+    fn get_route(query: RouteQuery, ctx: &Context) -> Option<Vec<Hop>> {
+        use services::{Category, Report};
+        match reports::load(Category::Route(query), ctx) {
+            Report::Route(hops) => return Some(hops),
+            Report::NotFoundId(id) => warn!("route to {} was not found", id),
+            report => warn!("Unexpected report {:?}", report)
+        }
+        None
     }
 
     pub fn route(departure: i32, destination: i32, ctx: &Context) -> String {
         let mut path = String::new();
-        if let Some(route) = Self::get_route(&departure, &destination, "") {
-            for id in route.iter().skip(1) {
+        if let Some(route) = Self::get_route(RouteQuery::new(departure, destination, Safety::Shortest), ctx) {
+            for hop in route.iter().skip(1) {
                 if path.is_empty() {
-                    path = Self::get_system_href(&id, ctx);
+                    path = Self::get_system_href(&hop.system_id, ctx);
                 } else {
-                    path = path + " &gt; " + &Self::get_system_href(&id, ctx);
+                    path = path + " &gt; " + &Self::get_system_href(&hop.system_id, ctx);
                 }
             }
         }
@@ -165,32 +173,78 @@ impl System {
         else { 0 }
     }
 
-    pub fn route_named(safety: String, departure: String, destination: String, ctx: &Context) -> String {
+    fn find_system_id(name: &str, ctx: &Context) -> Option<i32> {
+        if let Ok(id) = name.parse::<i32>() {
+            Some(id)
+        } else {
+            reports::find_id("solar_system", name, ctx)
+        }
+    }
+
+    /// The names which were not found are listed in the errors
+    fn get_route_query(safety: String, departure: String, destination: String, avoid: Vec<String>, danger: f32, errors: &mut Vec<String>, ctx: &Context) -> Option<RouteQuery> {
         let category = "solar_system";
-        let mut output = String::new();
-        let src = reports::find_id(category, &departure, ctx).unwrap_or(0);
+        let src = Self::find_system_id(&departure, ctx).unwrap_or(0);
         if 0 == src {
-            reports::div(&mut output, format!("departure {} was not found in category {}", category, departure));
+            errors.push(format!("departure {} was not found in category {}", departure, category));
         }
-        let dst = reports::find_id(category, &destination, ctx).unwrap_or(0);
-        if 0 == src {
-            reports::div(&mut output, format!("destination {} was not found in category {}", category, destination));
+        let dst = Self::find_system_id(&destination, ctx).unwrap_or(0);
+        if 0 == dst {
+            errors.push(format!("destination {} was not found in category {}", destination, category));
         }
-        let route = if vec!["insecure","unsafe", "u"].contains(&safety.as_ref()) {
-            Self::get_route(&src, &dst, "insecure")
-        } else if vec!["safe", "secure", "s"].contains(&safety.as_ref()) {
-            Self::get_route(&src, &dst, "secure")
-        } else {
-            Self::get_route(&src, &dst, "")
+        if 0 == src || 0 == dst {
+            return None;
+        }
+        let mut query = RouteQuery::new(src, dst, Safety::from(safety.as_ref()));
+        query.danger = danger;
+        for name in &avoid {
+            if let Some(id) = Self::find_system_id(name, ctx) {
+                query.avoid.push(id);
+            } else {
+                errors.push(format!("avoided system {} was not found in category {}", name, category));
+            }
+        }
+        Some(query)
+    }
+
+    fn get_route_danger(hops: &Vec<Hop>, windows: &[i32], ctx: &Context) -> RouteDanger {
+        use services::{Category, Report};
+        use chrono::Utc;
+        let ids = hops.iter().map(|hop| hop.system_id).collect();
+        let minutes = windows.iter().cloned().max().unwrap_or(60);
+        let kills = match reports::load(Category::RouteHistory((ids, minutes)), ctx) {
+            Report::RouteHistory(kills) => kills,
+            report => {
+                warn!("Unexpected report {:?}", report);
+                Vec::new()
+            }
         };
+        RouteDanger::new(hops, windows, &kills, &Utc::now().naive_utc(), &ShipClass::from_ship)
+    }
+
+    fn get_window_name(minutes: &i32) -> String {
+        if *minutes >= 60 && 0 == minutes % 60 {
+            format!("{}h", minutes / 60)
+        } else {
+            format!("{}m", minutes)
+        }
+    }
 
-        if let Some(ids) = route {
-            let mut jumps = 0;
+    pub fn route_named(safety: String, departure: String, destination: String, avoid: Vec<String>, danger: f32, windows: Vec<i32>, ctx: &Context) -> String {
+        let mut output = String::new();
+        let mut errors = Vec::new();
+        let query = Self::get_route_query(safety, departure, destination, avoid, danger, &mut errors, ctx);
+        for error in errors {
+            reports::div(&mut output, error);
+        }
+
+        if let Some(hops) = query.and_then(|query| Self::get_route(query, ctx)) {
+            let route = Self::get_route_danger(&hops, &windows, ctx);
             let table_style = "border-collapse: collapse;";
             let head_style = "border: 1px solid black; padding: 2px 5px; text-align: center;";
 
             reports::table_start(&mut output, "", table_style, "");
-            reports::caption(&mut output, "Route");
+            reports::caption(&mut output, format!("Route (risk score: {:.1})", route.risk));
             reports::table_row_start(&mut output, head_style);
             reports::table_cell_head(&mut output, "Jumps offset", head_style, "Jumps");
             reports::table_cell_head(&mut output, "Region Name", head_style, "Region");
@@ -198,44 +252,56 @@ impl System {
             reports::table_cell_head(&mut output, "System Name", head_style, "System");
             reports::table_cell_head(&mut output, "CONCORD reaction time", head_style, "CRT");
             reports::table_cell_head(&mut output, "System Security Status", head_style, "SSS");
-            reports::table_cell_head(&mut output, "10 minutes history", head_style, "10m");
-            reports::table_cell_head(&mut output, "1 hour history", head_style, "1h");
-            reports::table_cell_head(&mut output, "6 hours history", head_style, "6h");
-            reports::table_cell_head(&mut output, "24 hours history", head_style, "24h");
+            for minutes in &route.windows {
+                reports::table_cell_head(&mut output, format!("{} minutes history", minutes), head_style, Self::get_window_name(minutes));
+            }
+            reports::table_cell_head(&mut output, "Last kill time", head_style, "Last");
+            reports::table_cell_head(&mut output, "Kills with Interdictors", head_style, "Dictors");
+            reports::table_cell_head(&mut output, "Kills with Heavy Interdiction Cruisers", head_style, "HICs");
+            reports::table_cell_head(&mut output, "Kills with Battleships", head_style, "BS");
+            reports::table_cell_head(&mut output, "Risk score", head_style, "Risk");
             reports::table_row_end(&mut output);
-            for id in &ids {
-                if let Some(system) = System::load(id, ctx) {
-                    use services::{Message, Api};
-                    use reports::history::History;
+            // The hops are the systems of the universe graph, their names are taken from there too
+            let systems: Vec<Option<models::system::SystemNamed>> = ctx.universe.read()
+                .map(|universe| route.hops.iter().map(|hop| universe.get_system(&hop.system_id).cloned()).collect())
+                .unwrap_or_default();
+            for (jumps, hop) in route.hops.iter().enumerate() {
+                use services::{Message, Api};
 
-                    let color = Self::kills_color(History::system_count(&id, &10, ctx));
-                    let text_style = &format!("border: 1px solid black; padding: 2px 5px; background-color: {};", color);
-                    let num_style = &format!("border: 1px solid black; padding: 2px 5px; text-align: right;  background-color: {};", color);
+                let id = &hop.system_id;
+                let system = systems.get(jumps).and_then(|system| system.as_ref());
+                let color = Self::kills_color(hop.risk.round() as i32);
+                let text_style = &format!("border: 1px solid black; padding: 2px 5px; background-color: {};", color);
+                let num_style = &format!("border: 1px solid black; padding: 2px 5px; text-align: right;  background-color: {};", color);
 
-                    if system.get_name("system").is_empty() {
-                        ctx.resolver.push(Message::Receive(Api::Object(system.get_id("system"))));
-                    }
-                    if system.get_name("constellation").is_empty() {
-                        ctx.resolver.push(Message::Receive(Api::Object(system.get_id("constellation"))));
-                    }
-                    if system.get_name("region").is_empty() {
-                        ctx.resolver.push(Message::Receive(Api::Object(system.get_id("region"))));
+                let mut names = Vec::new();
+                for area in ["region", "constellation", "system"].iter().cloned() {
+                    let (area_id, name) = system.map(|system| (system.get_id(area), system.get_name(area))).unwrap_or((*id, String::new()));
+                    if name.is_empty() {
+                        ctx.resolver.push(Message::Receive(Api::Object(area_id)));
                     }
+                    names.push(system.map(|_| ctx.get_api_href(area, area_id, name)).unwrap_or_default());
+                }
 
-                    reports::table_row_start(&mut output, text_style);
-                    reports::table_cell(&mut output, "Jumps offset", num_style, format!("{}", jumps));
-                    reports::table_cell(&mut output, "Region Name", text_style,         ctx.get_api_href("region", system.get_id("region"), system.get_name("region")));
-                    reports::table_cell(&mut output, "Constellation Name", text_style,  ctx.get_api_href("constellation", system.get_id("constellation"), system.get_name("constellation")));
-                    reports::table_cell(&mut output, "System Name", text_style,         ctx.get_api_href("system", *id, system.get_name("system")));
-                    reports::table_cell(&mut output, "CONCORD reaction time", num_style, format!("{}s", Self::get_concord_reaction(system.security_status)));
-                    reports::table_cell(&mut output, "System Security Status", num_style, format!("{:.2}", system.security_status));
-                    reports::table_cell(&mut output, "10 minutes history", num_style,  History::system_count(&id, &10, ctx).separated_string());
-                    reports::table_cell(&mut output, "1 hour history", num_style,      History::system_count(&id, &60, ctx).separated_string());
-                    reports::table_cell(&mut output, "6 hours history", num_style,     History::system_count(&id, &360, ctx).separated_string());
-                    reports::table_cell(&mut output, "24 hours history", num_style,    History::system_count(&id, &1440, ctx).separated_string());
-                    reports::table_row_end(&mut output);
-                    jumps = jumps + 1;
+                let last_kill = hop.last_kill
+                    .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                reports::table_row_start(&mut output, text_style);
+                reports::table_cell(&mut output, "Jumps offset", num_style, format!("{}", jumps));
+                reports::table_cell(&mut output, "Region Name", text_style,         names[0].clone());
+                reports::table_cell(&mut output, "Constellation Name", text_style,  names[1].clone());
+                reports::table_cell(&mut output, "System Name", text_style,         names[2].clone());
+                reports::table_cell(&mut output, "CONCORD reaction time", num_style, format!("{}s", Self::get_concord_reaction(hop.security_status)));
+                reports::table_cell(&mut output, "System Security Status", num_style, format!("{:.2}", hop.security_status));
+                for (minutes, count) in route.windows.iter().zip(hop.kills.iter()) {
+                    reports::table_cell(&mut output, format!("{} minutes history", minutes), num_style, count.separated_string());
                 }
+                reports::table_cell(&mut output, "Last kill time", num_style, last_kill);
+                reports::table_cell(&mut output, "Kills with Interdictors", num_style, hop.interdictors.separated_string());
+                reports::table_cell(&mut output, "Kills with Heavy Interdiction Cruisers", num_style, hop.heavy_interdictors.separated_string());
+                reports::table_cell(&mut output, "Kills with Battleships", num_style, hop.battleships.separated_string());
+                reports::table_cell(&mut output, "Risk score", num_style, format!("{:.1}", hop.risk));
+                reports::table_row_end(&mut output);
             }
             reports::table_end(&mut output);
         }
@@ -243,73 +309,56 @@ impl System {
         return output;
     }
 
-    fn report_observatory_path(output: &mut dyn Write, id: &i32, ctx: &Context) {
-        use services::{Category, Report};
-        use std::collections::HashSet;
-        let arrow = format!("&nbsp;=&gt;&nbsp;");
-        let mut covered = HashSet::new();
-        match reports::load(Category::ObservatoryPath(*id), &ctx) {
-            Report::ObservatoryPath(paths) => {
-                let mut rest = 5;
-                for path in &paths {
-                    if path.s1_jo {
-                        if covered.insert(&path.s1_id) {
-                            reports::div(output, format!("{}{}",
-                                arrow, Self::get_system_href(&path.s1_id, ctx)
-                            ));
-                            rest = rest - 1;
-                        }
-                    } else if path.s2_jo {
-                        if covered.insert(&path.s2_id) {
-                            reports::div(output, format!("{}{}{}{}",
-                                arrow, Self::get_system_href(&path.s1_id, ctx),
-                                arrow, Self::get_system_href(&path.s2_id, ctx),
-                            ));
-                            rest = rest - 1;
-                        }
-                    } else if path.s3_jo {
-                        if covered.insert(&path.s3_id) {
-                            reports::div(output, format!("{}{}{}{}{}{}",
-                                arrow, Self::get_system_href(&path.s1_id, ctx),
-                                arrow, Self::get_system_href(&path.s2_id, ctx),
-                                arrow, Self::get_system_href(&path.s3_id, ctx),
-                            ));
-                            rest = rest - 1;
-                        }
-                    } else if path.s4_jo {
-                        if covered.insert(&path.s4_id) {
-                            reports::div(output, format!("{}{}{}{}{}{}{}{}",
-                                arrow, Self::get_system_href(&path.s1_id, ctx),
-                                arrow, Self::get_system_href(&path.s2_id, ctx),
-                                arrow, Self::get_system_href(&path.s3_id, ctx),
-                                arrow, Self::get_system_href(&path.s4_id, ctx),
-                            ));
-                            rest = rest - 1;
-                        }
-                    } else if path.s5_jo {
-                        if covered.insert(&path.s5_id) {
-                            reports::div(output, format!("{}{}{}{}{}{}{}{}{}{}",
-                                arrow, Self::get_system_href(&path.s1_id, ctx),
-                                arrow, Self::get_system_href(&path.s2_id, ctx),
-                                arrow, Self::get_system_href(&path.s3_id, ctx),
-                                arrow, Self::get_system_href(&path.s4_id, ctx),
-                                arrow, Self::get_system_href(&path.s5_id, ctx),
-                            ));
-                            rest = rest - 1;
-                        }
-                    }
+    /// The unknown systems and the missing route are the errors
+    pub fn route_json(safety: String, departure: String, destination: String, avoid: Vec<String>, danger: f32, windows: Vec<i32>, ctx: &Context) -> Result<String, String> {
+        let mut errors = Vec::new();
+        let query = Self::get_route_query(safety, departure, destination, avoid, danger, &mut errors, ctx);
+        if !errors.is_empty() {
+            return Err(errors.join(", "));
+        }
+        let query = query.ok_or(String::from("route query was not built"))?;
+        let (src, dst) = (query.departure, query.destination);
+        let hops = Self::get_route(query, ctx).ok_or(format!("route from {} to {} was not found", src, dst))?;
+        serde_json::to_string(&Self::get_route_danger(&hops, &windows, ctx)).map_err(|e| e.to_string())
+    }
 
-                    if 0 == rest {
-                        break;
-                    }
-                }
-            },
+    fn get_nearest(query: SearchQuery, ctx: &Context) -> Vec<Found> {
+        use services::{Category, Report};
+        match reports::load(Category::Nearest(query), ctx) {
+            Report::Nearest(found) => found,
             report => {
-                reports::div(output, format!("Unexpected Report: {:?}", report));
+                warn!("Unexpected report {:?}", report);
+                Vec::new()
             }
         }
     }
 
+    fn write_nearest(output: &mut dyn Write, query: SearchQuery, ctx: &Context) {
+        let arrow = String::from("&nbsp;=&gt;&nbsp;");
+        for found in Self::get_nearest(query, ctx) {
+            let mut path = String::new();
+            for id in &found.path {
+                path = path + &arrow + &Self::get_system_href(id, ctx);
+            }
+            reports::div(output, path);
+        }
+    }
+
+    pub fn nearest(predicate: String, system: String, jumps: u32, minutes: i32, limit: usize, ctx: &Context) -> String {
+        let mut output = String::new();
+        let predicate = match Predicate::from_name(&predicate, minutes) {
+            Some(predicate) => predicate,
+            None => return format!("Unknown predicate {}", predicate)
+        };
+        if let Some(id) = Self::find_system_id(&system, ctx) {
+            reports::div(&mut output, format!("Nearest systems within {} jumps from {}:", jumps, Self::get_system_href(&id, ctx)));
+            Self::write_nearest(&mut output, SearchQuery::new(id, predicate, jumps, limit), ctx);
+        } else {
+            reports::div(&mut output, format!("system {} was not found", system));
+        }
+        output
+    }
+
     fn observatory_report(output: &mut dyn Write, system: &models::system::SystemNamed, ctx: &Context) {
         if system.has_observatory() {
             let name = system.get_name("system");
@@ -319,7 +368,7 @@ impl System {
         }
 
         reports::div(output, format!("Nearest system with Jovian Observatory:"));
-        Self::report_observatory_path(output, &system.system_id, ctx);
+        Self::write_nearest(output, SearchQuery::new(system.system_id, Predicate::Observatory, 5, 5), ctx);
     }
 
     pub fn observatory_add(id: &i32, ctx: &Context) -> String {
@@ -334,7 +383,11 @@ impl System {
         String::from("Done")
     }
 
-    pub fn stat(id: &i32, ctx: &Context) -> String {
+    pub fn stat(id: &i32, minutes: &i32, ctx: &Context) -> String {
+        reports::statistics::report(api::stats::Entity::System(*id), minutes, ctx)
+    }
+
+    pub fn zkb_stat(id: &i32, ctx: &Context) -> String {
         use api::stats::Stats;
         use api::stats::Entity;
         use api::stats::TopList;
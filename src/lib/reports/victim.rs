@@ -3,6 +3,7 @@ use crate::services;
 use crate::services::Context;
 use crate::reports;
 
+use std::collections::HashMap;
 use std::fmt::Write;
 
 #[derive(Debug, PartialEq)]
@@ -46,6 +47,16 @@ impl Victim {
         return None;
     }
 
+    /// Victims of the killmails by the killmail id
+    pub fn load_all(ids: &[i32], ctx: &Context) -> HashMap<i32, models::victim::VictimNamed> {
+        use services::{Category, Report};
+        match reports::load(Category::Victims(ids.to_owned()), ctx) {
+            Report::Victims(victims) => return victims.into_iter().map(|victim| (victim.killmail_id, victim)).collect(),
+            report => warn!("Unexpected report {:?}", report)
+        }
+        HashMap::new()
+    }
+
     pub fn brief(arg: &String, ctx: &Context) -> String {
         if let Ok(ref id) = arg.parse::<i32>() {
             Self::brief_impl(id, ctx)
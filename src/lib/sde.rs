@@ -0,0 +1,87 @@
+use crate::models::{Connection, QueryResult};
+use crate::models::category::Category;
+use crate::models::object::Object;
+
+/// The objects category of the item types, the same as ESI universe/names returns
+pub const TYPES_CATEGORY: &str = "inventory_type";
+
+/// Splits the CSV text to the records, the quoted fields may hold the commas, the quotes and the line breaks
+pub fn parse_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {},
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            },
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Type ids with the names of the invTypes.csv dump, the columns are found by the header
+pub fn parse_types(text: &str) -> Result<Vec<(i32, String)>, String> {
+    let records = parse_records(text);
+    let header = records.first().ok_or(String::from("the file is empty"))?;
+    let column = |name: &str| header.iter().position(|title| title.trim() == name).ok_or(format!("the header has no {} column", name));
+    let id_column = column("typeID")?;
+    let name_column = column("typeName")?;
+    let mut types = Vec::new();
+    for (index, record) in records.iter().enumerate().skip(1) {
+        if record.len() == 1 && record[0].trim().is_empty() {
+            continue;
+        }
+        let id = record.get(id_column).and_then(|id| id.trim().parse().ok())
+            .ok_or(format!("record {} has no valid typeID", index))?;
+        let name = record.get(name_column).map(|name| name.trim().to_string()).unwrap_or_default();
+        if !name.is_empty() {
+            types.push((id, name));
+        }
+    }
+    Ok(types)
+}
+
+/// Stores the item types as the objects, the stored names are replaced
+pub fn import_types(conn: &Connection, types: &[(i32, String)]) -> QueryResult<usize> {
+    let category_id = Category::get_or_create(conn, TYPES_CATEGORY)?;
+    let objects: Vec<Object> = types.iter().map(|(id, name)| Object::new(*id, category_id, name.clone())).collect();
+    Object::save_all(conn, &objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        let text = "typeID,typeName,description\r\n34,Tritanium,\"The main \"\"building\"\" block,\nof everything\"\n587,Rifter,\n";
+        let records = parse_records(text);
+        assert_eq!(3, records.len());
+        assert_eq!(vec!["34", "Tritanium", "The main \"building\" block,\nof everything"], records[1]);
+        assert_eq!(vec!["587", "Rifter", ""], records[2]);
+    }
+
+    #[test]
+    fn types() {
+        let text = "groupID,typeID,typeName\n18,34,Tritanium\n25,587,Rifter\n1,2,\n";
+        assert_eq!(vec![(34, String::from("Tritanium")), (587, String::from("Rifter"))], parse_types(text).unwrap());
+        assert_eq!(Err(String::from("the header has no typeName column")), parse_types("typeID,name\n34,Tritanium"));
+        assert_eq!(Err(String::from("record 1 has no valid typeID")), parse_types("typeID,typeName\nx,Tritanium"));
+    }
+}
@@ -1,8 +1,15 @@
 use crate::api;
 use crate::services::*;
 use crate::models;
+use crate::navigation;
+use crate::alerts;
+use crate::valuation;
+use crate::market;
+use crate::provider;
 use crate::services::{AppContext, Command, Message, Category, Report};
+use crate::webhooks::Notification;
 use models::Connection;
+use std::collections::VecDeque;
 //use std::collections::HashSet;
 
 fn enqueue_check(queue: &Queue, id: &i32) {
@@ -22,6 +29,23 @@ pub fn get_name_if_none(queue: &Queue, name: &Option<String>, id: i32) {
     }
 }
 
+/// The missing names of the victim are resolved for the next time
+fn check_victim_names(queue: &Queue, victim: &models::victim::VictimNamed) {
+    get_name_if_none(queue, &victim.ship_name, victim.ship_id);
+    if let Some(id) = victim.character_id {
+        get_name_if_none(queue, &victim.character_name, id);
+    }
+    if let Some(id) = victim.corporation_id {
+        get_name_if_none(queue, &victim.corporation_name, id);
+    }
+    if let Some(id) = victim.alliance_id {
+        get_name_if_none(queue, &victim.alliance_name, id);
+    }
+    if let Some(id) = victim.faction_id {
+        get_name_if_none(queue, &victim.faction_name, id);
+    }
+}
+
 fn handle_killmail(queue: &Queue, killmail: &api::Killmail) {
     enqueue_check(queue, &killmail.solar_system_id);
     try_enqueue_check(queue, &killmail.moon_id);
@@ -50,16 +74,149 @@ fn handle_killmail(queue: &Queue, killmail: &api::Killmail) {
     }
 }
 
+/// The names are taken from the local objects table, the unknown ones are checked with the killmail
+fn get_local_name(conn: &Connection, id: &i32) -> Option<String> {
+    models::object::Object::load(conn, id).ok().map(|object| object.object_name)
+}
+
+fn get_notification(conn: &Connection, context: &AppContext, killmail: &api::Killmail, rules: Vec<String>) -> Notification {
+    let value = valuation::get_value(killmail, &valuation::Source::default()).total();
+    let mut notification = Notification::new(killmail, value, rules);
+    if let Ok(universe) = context.universe.read() {
+        if let Some(system) = universe.get_system(&killmail.solar_system_id) {
+            notification.system_name = system.system_name.clone();
+            notification.constellation_id = Some(system.constellation_id);
+            notification.region_id = Some(system.region_id);
+        }
+    }
+    let victim = &killmail.victim;
+    notification.ship_name = get_local_name(conn, &victim.ship_type_id);
+    notification.victim_name = victim.character_id.or(victim.corporation_id).and_then(|id| get_local_name(conn, &id));
+    notification
+}
+
+fn load_universe(conn: &Connection, context: &AppContext) {
+    match navigation::Universe::load(conn) {
+        Ok(loaded) => {
+            info!("universe loaded with {} systems", loaded.len());
+            if let Ok(mut universe) = context.universe.write() {
+                *universe = loaded;
+            }
+        },
+        Err(e) => warn!("was not able to load universe: {}", e)
+    }
+}
+
+fn refresh_universe_system(conn: &Connection, context: &AppContext, id: &i32) {
+    match models::system::SystemNamed::load(conn, id) {
+        Ok(system) => {
+            if let Ok(mut universe) = context.universe.write() {
+                universe.add_system(system);
+            }
+        },
+        Err(e) => warn!("was not able to refresh system {} in universe: {}", id, e)
+    }
+}
+
+/// Returns the names of the matched rules
+fn check_alerts(conn: &Connection, context: &AppContext, killmail: &api::Killmail) -> models::QueryResult<Vec<String>> {
+    use models::rule::Rule;
+    use models::watchlist::Watched;
+    use models::alert::Alert;
+
+    let rules = Rule::load_all(conn)?;
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+    let watchlists = alerts::Watchlists::new(Watched::load_all(conn)?);
+    let event = alerts::Event::from(killmail);
+    let matched = match context.universe.read() {
+        Ok(universe) => alerts::evaluate(&rules, &event, &watchlists, universe.get_graph(), &alerts::is_capital),
+        Err(_) => Vec::new(),
+    };
+    for rule_id in &matched {
+        Alert::save(conn, rule_id, &killmail.killmail_id)?;
+    }
+    Ok(rules.into_iter().filter(|rule| matched.contains(&rule.rule_id)).map(|rule| rule.name).collect())
+}
+
+fn start_snapshot(conn: &Connection, region_id: &i32, orders: &Vec<api::Order>) -> Option<market::ingest::Snapshot> {
+    let now = chrono::Utc::now().naive_utc();
+    match market::ingest::Snapshot::new(conn, region_id, orders, &now) {
+        Ok(snapshot) => Some(snapshot),
+        Err(err) => {
+            warn!("was not able to compare market orders of {}: {}", region_id, err);
+            None
+        }
+    }
+}
+
+/// The valuation takes the hub prices from the stored snapshot instead of ESI
+fn load_snapshot_prices(conn: &Connection, region_id: &i32) {
+    match models::market::MarketOrder::load_region(conn, region_id) {
+        Ok(orders) => {
+            let orders: Vec<api::Order> = orders.iter().map(|order| order.to_api()).collect();
+            provider::set_snapshot_prices(region_id, &orders);
+        },
+        Err(err) => warn!("was not able to load market orders of {}: {}", region_id, err),
+    }
+}
+
+fn load_all_snapshot_prices(conn: &Connection) {
+    match models::market::MarketOrder::load_regions(conn) {
+        Ok(regions) => {
+            for region_id in &regions {
+                load_snapshot_prices(conn, region_id);
+            }
+            info!("hub prices loaded from {} market snapshots", regions.len());
+        },
+        Err(err) => warn!("was not able to load market regions: {}", err),
+    }
+}
+
+/// True when the snapshot is complete or failed
+fn save_snapshot_batch(conn: &Connection, context: &AppContext, snapshot: &mut market::ingest::Snapshot) -> bool {
+    match snapshot.step(conn) {
+        Ok(true) => {
+            info!("market snapshot of {}: {:?}, queue length: {}", snapshot.region_id, snapshot.summary, context.database.len());
+            load_snapshot_prices(conn, &snapshot.region_id);
+            true
+        },
+        Ok(false) => false,
+        Err(err) => {
+            warn!("was not able to save market orders of {}: {}", snapshot.region_id, err);
+            true
+        }
+    }
+}
+
 pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
     info!("Started");
+    load_universe(&conn, &context);
+    load_all_snapshot_prices(&conn);
 //    let mut known = HashSet::new();
 //    let mut objects = HashSet::new();
+    let mut snapshots = VecDeque::new();
+    let mut snapshot = None;
     loop {
+        context.health.beat(health::DATABASE);
         if let Some(Command::Quit) = context.commands.pop() {
             context.commands.push(Command::Quit);
             info!("received Command::Quit");
             break;
         }
+        // The market snapshot is written by the batches between the messages, the killmails do not wait for it
+        if snapshot.is_none() {
+            snapshot = snapshots.pop_front().and_then(|(region_id, orders)| start_snapshot(&conn, &region_id, &orders));
+        }
+        if let Some(current) = snapshot.as_mut() {
+            if save_snapshot_batch(&conn, &context, current) {
+                snapshot = None;
+            }
+            if context.database.len() == 0 {
+                continue;
+            }
+        }
         if let Some(msg) = context.database.pop() {
             match msg {
                 Message::Save(model) => {
@@ -69,6 +226,24 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 warn!("was not able to save killmail: {}", err);
                             } else {
                                 info!("Killmail({}) saved, queue length: {}", killmail.killmail_id, context.database.len());
+                                crate::metrics::increment("zkb_killmails_ingested_total", &[]);
+                                context.health.killmail();
+                                let rules = match check_alerts(&conn, &context, &killmail) {
+                                    Ok(rules) => {
+                                        if !rules.is_empty() {
+                                            info!("Killmail({}) matched {} rules", killmail.killmail_id, rules.len());
+                                        }
+                                        rules
+                                    },
+                                    Err(err) => {
+                                        warn!("was not able to check alerts: {}", err);
+                                        Vec::new()
+                                    }
+                                };
+                                // The killfeed is served without the webhooks too
+                                if context.config.features.notifier || !context.streams.is_empty() {
+                                    context.notifier.push(Message::Notify(get_notification(&conn, &context, &killmail, rules)));
+                                }
                             }
 
                             handle_killmail(&context.database, &killmail);
@@ -79,6 +254,9 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 warn!("was not able to save object: {}", err);
                             } else {
                                 info!("Object {} - '{}' saved, queue length: {}", object.id, &object.name, context.database.len());
+                                if let Ok(mut universe) = context.universe.write() {
+                                    universe.set_name(&object.id, &object.name);
+                                }
                             }
                         },
                         Model::System(object) => {
@@ -86,6 +264,10 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 warn!("was not able to save system: {}", err);
                             } else {
                                 info!("System {} - '{}' saved, queue length: {}", object.system_id, &object.name, context.database.len());
+                                refresh_universe_system(&conn, &context, &object.system_id);
+                            }
+                            if let Err(err) = models::station::Station::save(&conn, &object) {
+                                warn!("was not able to save stations of system {}: {}", object.system_id, err);
                             }
                         },
                         Model::Constellation(object) => {
@@ -100,6 +282,9 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 warn!("was not able to save stargate: {}", err);
                             } else {
                                 info!("Stargate {} - '{}' saved, queue length: {}", object.stargate_id, &object.name, context.database.len());
+                                if let Ok(mut universe) = context.universe.write() {
+                                    universe.add_link(object.system_id, object.destination.system_id);
+                                }
                             }
                         },
                         Model::Observatory(id) => {
@@ -107,8 +292,34 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 warn!("was not able to save observatory: {}", err);
                             } else {
                                 info!("Observatory in system {} saved, queue length: {}", id, context.database.len());
+                                refresh_universe_system(&conn, &context, &id);
+                            }
+                        },
+                        Model::Watched((list, category, id)) => {
+                            if let Err(err) = models::watchlist::Watched::save(&conn, &list, &category, &id) {
+                                warn!("was not able to save watched: {}", err);
+                            } else {
+                                info!("{} {} added to watchlist '{}', queue length: {}", category, id, list, context.database.len());
                             }
                         },
+                        Model::Rule(rule) => {
+                            if let Err(err) = rule.save(&conn) {
+                                warn!("was not able to save rule: {}", err);
+                            } else {
+                                info!("Rule '{}' saved, queue length: {}", rule.name, context.database.len());
+                            }
+                        },
+                        Model::Prices(prices) => {
+                            match models::price::Price::save_all(&conn, &prices) {
+                                Ok(count) => info!("{} prices saved, queue length: {}", count, context.database.len()),
+                                Err(err) => warn!("was not able to save prices: {}", err),
+                            }
+                        },
+                        Model::MarketOrders((region_id, orders)) => {
+                            info!("market snapshot of {} with {} orders is queued", region_id, orders.len());
+                            snapshots.push_back((region_id, orders));
+                        },
+                        model => warn!("Save operation is not implemented for {:?}", model)
                     };
                 },
                 Message::Delete(model) => {
@@ -118,6 +329,21 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 warn!("was not able to delete observatory: {}", err);
                             } else {
                                 info!("Observatory in system {} deleted, queue length: {}", id, context.database.len());
+                                refresh_universe_system(&conn, &context, &id);
+                            }
+                        },
+                        Model::Watched((list, category, id)) => {
+                            if let Err(err) = models::watchlist::Watched::delete(&conn, &list, &category, &id) {
+                                warn!("was not able to delete watched: {}", err);
+                            } else {
+                                info!("{} {} removed from watchlist '{}', queue length: {}", category, id, list, context.database.len());
+                            }
+                        },
+                        Model::RuleId(id) => {
+                            if let Err(err) = models::rule::Rule::delete(&conn, &id) {
+                                warn!("was not able to delete rule: {}", err);
+                            } else {
+                                info!("Rule {} deleted, queue length: {}", id, context.database.len());
                             }
                         },
                         model => warn!("Delete operation is not implemented for {:?}", model)
@@ -235,19 +461,7 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                             match models::victim::VictimNamed::load(&conn, &id) {
                                 Ok(object) => {
                                     info!("loaded victim for KM {} queue length: {}", id, context.database.len());
-                                    get_name_if_none(&context.resolver, &object.ship_name, object.ship_id);
-                                    if let Some(id) = object.character_id {
-                                        get_name_if_none(&context.resolver, &object.character_name, id);
-                                    }
-                                    if let Some(id) = object.corporation_id {
-                                        get_name_if_none(&context.resolver, &object.corporation_name, id);
-                                    }
-                                    if let Some(id) = object.alliance_id {
-                                        get_name_if_none(&context.resolver, &object.alliance_name, id);
-                                    }
-                                    if let Some(id) = object.faction_id {
-                                        get_name_if_none(&context.resolver, &object.faction_name, id);
-                                    }
+                                    check_victim_names(&context.resolver, &object);
                                     context.responses.push(Message::Report((msg_id, Report::Victim(object))));
                                 },
                                 Err(e) => {
@@ -256,6 +470,36 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 }
                             }
                         },
+                        Category::Victims(ids) => {
+                            match models::victim::VictimNamed::load_all(&conn, ids) {
+                                Ok(objects) => {
+                                    info!("loaded {} victims queue length: {}", objects.len(), context.database.len());
+                                    for object in &objects {
+                                        check_victim_names(&context.resolver, object);
+                                    }
+                                    context.responses.push(Message::Report((msg_id, Report::Victims(objects))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load victims: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::KillmailItems(ids) => {
+                            match models::item::ItemNamed::load_all(&conn, ids) {
+                                Ok(objects) => {
+                                    info!("loaded {} items queue length: {}", objects.len(), context.database.len());
+                                    for object in &objects {
+                                        get_name_if_none(&context.resolver, &object.item_type_name, object.item_type_id);
+                                    }
+                                    context.responses.push(Message::Report((msg_id, Report::Items(objects))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load items: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
                         Category::Attackers(id) => {
                             match models::attacker::AttackerNamed::load(&conn, &id) {
                                 Ok(objects) => {
@@ -303,15 +547,22 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 }
                             }
                         },
-                        Category::ObservatoryPath(id) => {
-                            match models::system::ObservatoryPath::load(&conn, &id) {
+                        Category::Nearest(query) => {
+                            let found = match context.universe.read() {
+                                Ok(universe) => {
+                                    let graph = universe.get_graph();
+                                    query.predicate.load(&conn, graph).map(|matcher| navigation::search::nearest(graph, query, &matcher))
+                                },
+                                Err(_) => Ok(Vec::new())
+                            };
+                            match found {
                                 Ok(objects) => {
-                                    info!("loaded paths for {} queue length: {}", id, context.database.len());
-                                    context.responses.push(Message::Report((msg_id, Report::ObservatoryPath(objects))));
+                                    info!("found {} systems near {} queue length: {}", objects.len(), query.system_id, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Nearest(objects))));
                                 },
                                 Err(e) => {
-                                    warn!("was not able to load killmail: {}", e);
-                                    context.responses.push(Message::Report((msg_id, Report::NotFoundId(*id))));
+                                    warn!("was not able to search nearest systems: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
                                 }
                             }
                         },
@@ -469,6 +720,245 @@ pub fn run(conn: Connection, context: actix_web::web::Data<AppContext>) {
                                 },
                             };
                         },
+                        Category::Route(query) => {
+                            match navigation::route::load_kills(&conn, &query.minutes) {
+                                Ok(kills) => {
+                                    let route = context.universe.read().ok().and_then(|universe| universe.get_graph().find(query, &kills));
+                                    if let Some(hops) = route {
+                                        info!("found route {} => {} with {} hops, queue length: {}", query.departure, query.destination, hops.len(), context.database.len());
+                                        context.responses.push(Message::Report((msg_id, Report::Route(hops))));
+                                    } else {
+                                        warn!("was not able to find route {} => {}", query.departure, query.destination);
+                                        context.responses.push(Message::Report((msg_id, Report::NotFoundId(query.destination))));
+                                    }
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load kills for route: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::RouteHistory((ids, minutes)) => {
+                            match models::killmail::SystemKill::load(&conn, ids, minutes) {
+                                Ok(kills) => {
+                                    info!("loaded {} route history records for last {} minutes, queue length: {}", kills.len(), minutes, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::RouteHistory(kills))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load route history: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Kills(minutes) => {
+                            match navigation::route::load_kills(&conn, minutes) {
+                                Ok(kills) => {
+                                    info!("loaded kills in {} systems for last {} minutes, queue length: {}", kills.len(), minutes, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Kills(kills))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load kills: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::SystemsLosses((ids, minutes)) => {
+                            match models::killmail::SystemLoss::load(&conn, ids, minutes) {
+                                Ok(losses) => {
+                                    info!("loaded {} losses in {} systems for last {} minutes, queue length: {}", losses.len(), ids.len(), minutes, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::SystemsLosses(losses))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load losses: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Statistics((entity, minutes)) => {
+                            use models::participant::Participant;
+                            match Participant::load_ids(&conn, entity, minutes).and_then(|ids| Participant::load(&conn, &ids)) {
+                                Ok(participants) => {
+                                    info!("loaded {} participants of {:?} for last {} minutes, queue length: {}", participants.len(), entity, minutes, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Participants(participants))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load participants: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Activity((entity, start, end)) => {
+                            use models::participant::Participant;
+                            match Participant::load_ids_between(&conn, entity, start, end).and_then(|ids| Participant::load(&conn, &ids)) {
+                                Ok(participants) => {
+                                    info!("loaded {} participants of {:?} between {} and {}, queue length: {}", participants.len(), entity, start, end, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Participants(participants))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load participants: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Killmails(ids) => {
+                            match models::killmail::KillmailNamed::load_ids(&conn, ids) {
+                                Ok(killmails) => {
+                                    info!("loaded {} of {} killmails, queue length: {}", killmails.len(), ids.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::History(killmails))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load killmails: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Alerts(limit) => {
+                            match models::alert::Alert::load_last(&conn, limit) {
+                                Ok(alerts) => {
+                                    info!("loaded {} alerts, queue length: {}", alerts.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Alerts(alerts))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load alerts: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Watchlists => {
+                            match models::watchlist::Watchlist::load_all(&conn) {
+                                Ok(watchlists) => {
+                                    info!("loaded {} watchlists, queue length: {}", watchlists.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Watchlists(watchlists))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load watchlists: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Watched => {
+                            match models::watchlist::Watched::load_all(&conn) {
+                                Ok(watched) => {
+                                    info!("loaded {} watched objects, queue length: {}", watched.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Watched(watched))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load watched: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Rules => {
+                            match models::rule::Rule::load_all(&conn) {
+                                Ok(rules) => {
+                                    info!("loaded {} rules, queue length: {}", rules.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Rules(rules))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load rules: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::ExportIds(filter) => {
+                            match models::export::load_ids(&conn, &filter.entity, &filter.start, &filter.end) {
+                                Ok(ids) => {
+                                    info!("loaded {} killmail ids to export, queue length: {}", ids.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Ids(ids))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load killmail ids: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Prices((ids, first, last)) => {
+                            match models::price::Price::load_around(&conn, ids, first, last, &valuation::SNAPSHOT_DAYS) {
+                                Ok(prices) => {
+                                    info!("loaded {} prices, queue length: {}", prices.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Prices(prices))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load prices: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::PriceHistory((type_id, since)) => {
+                            match models::price::Price::load_history(&conn, type_id, since) {
+                                Ok(prices) => {
+                                    info!("loaded {} prices of {}, queue length: {}", prices.len(), type_id, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Prices(prices))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load price history: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::MarketOrders((regions, types, is_buy)) => {
+                            match models::market::MarketOrder::load(&conn, regions, types, is_buy) {
+                                Ok(orders) => {
+                                    info!("loaded {} market orders, queue length: {}", orders.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::MarketOrders(orders))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load market orders: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::MarketRegions => {
+                            match models::market::MarketOrder::load_regions(&conn) {
+                                Ok(regions) => context.responses.push(Message::Report((msg_id, Report::Ids(regions)))),
+                                Err(e) => {
+                                    warn!("was not able to load market regions: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::TypeVolumes((region_id, since, limit)) => {
+                            match models::market::TypeVolume::load(&conn, region_id, since, limit) {
+                                Ok(volumes) => {
+                                    info!("loaded {} type volumes of {}, queue length: {}", volumes.len(), region_id, context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::TypeVolumes(volumes))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load type volumes: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::Export(ids) => {
+                            match models::export::load(&conn, ids) {
+                                Ok(killmails) => {
+                                    info!("loaded {} killmails to export, queue length: {}", killmails.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Killmails(killmails))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to load killmails: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
+                        Category::TypeNames(names) => {
+                            let objects = models::category::Category::find(&conn, &String::from("inventory_type")).and_then(|categories| {
+                                match categories.first() {
+                                    Some(category_id) => models::object::Object::find_names(&conn, category_id, names),
+                                    None => Ok(Vec::new()),
+                                }
+                            });
+                            match objects {
+                                Ok(objects) => {
+                                    info!("resolved {} of {} type names, queue length: {}", objects.len(), names.len(), context.database.len());
+                                    context.responses.push(Message::Report((msg_id, Report::Objects(objects))));
+                                },
+                                Err(e) => {
+                                    warn!("was not able to resolve type names: {}", e);
+                                    context.responses.push(Message::Report((msg_id, Report::QueryFailed(e.to_string()))));
+                                }
+                            }
+                        },
                         category => {
                             warn!("Unexpected category for Find {:?}", category);
                         }
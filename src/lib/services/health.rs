@@ -0,0 +1,173 @@
+use crate::config::Config;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The thread that did not beat for so long is considered wedged
+pub const STALE_SECONDS: u64 = 120;
+/// The blocked queues wake the threads up to beat
+pub const HEARTBEAT: Duration = Duration::from_secs(10);
+/// ESI is asked once a minute at most, the probes should not hit ESI on every call
+pub const ESI_PROBE: Duration = Duration::from_secs(60);
+
+pub const MONITOR: &str = "monitor";
+pub const DATABASE: &str = "database";
+
+pub fn get_resolver_name(id: usize) -> String {
+    format!("resolver {}", id)
+}
+
+/// The beat may happen after the check has taken its time
+fn elapsed(now: Instant, time: Instant) -> Duration {
+    if now > time { now - time } else { Duration::from_secs(0) }
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct Beat {
+    pub name: String,
+    /// None when the thread has not started yet
+    pub seconds: Option<u64>,
+    pub alive: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct Status {
+    pub alive: bool,
+    pub ready: bool,
+    pub threads: Vec<Beat>,
+    pub last_killmail_seconds: Option<u64>,
+    pub migrated: bool,
+    pub esi: Option<bool>,
+}
+
+/// Heartbeats of the service threads and the readiness flags
+pub struct Health {
+    stale: Duration,
+    beats: Mutex<BTreeMap<String, Option<Instant>>>,
+    killmail: Mutex<Option<Instant>>,
+    migrated: AtomicBool,
+    esi: Mutex<Option<(Instant, bool)>>,
+}
+impl Health {
+
+    /// The threads enabled by the config are expected to beat
+    pub fn new(config: &Config) -> Self {
+        let mut beats = BTreeMap::new();
+        if config.features.monitor {
+            beats.insert(MONITOR.to_string(), None);
+        }
+        for id in 0..config.services.resolvers {
+            beats.insert(get_resolver_name(id), None);
+        }
+        beats.insert(DATABASE.to_string(), None);
+        Self {
+            // The monitor sleeps for the timeout when RedisQ is empty
+            stale: Duration::from_secs(STALE_SECONDS.max(4 * config.server.timeout)),
+            beats: Mutex::new(beats),
+            killmail: Mutex::new(None),
+            migrated: AtomicBool::new(false),
+            esi: Mutex::new(None),
+        }
+    }
+
+    pub fn beat<S: Into<String>>(&self, name: S) {
+        if let Ok(mut beats) = self.beats.lock() {
+            beats.insert(name.into(), Some(Instant::now()));
+        }
+    }
+
+    pub fn killmail(&self) {
+        if let Ok(mut killmail) = self.killmail.lock() {
+            *killmail = Some(Instant::now());
+        }
+    }
+
+    pub fn set_migrated(&self) {
+        self.migrated.store(true, Ordering::SeqCst);
+    }
+
+    /// The cached ESI reachability, the probe is called when it is outdated
+    pub fn get_esi<P: Fn() -> bool>(&self, now: Instant, probe: &P) -> bool {
+        if let Ok(esi) = self.esi.lock() {
+            if let Some((checked, reachable)) = *esi {
+                if elapsed(now, checked) < ESI_PROBE {
+                    return reachable;
+                }
+            }
+        }
+        let reachable = probe();
+        if let Ok(mut esi) = self.esi.lock() {
+            *esi = Some((now, reachable));
+        }
+        reachable
+    }
+
+    /// The liveness is the threads only, the readiness adds the migrations and ESI
+    pub fn check(&self, now: Instant, esi: Option<bool>) -> Status {
+        let seconds = |time: &Instant| elapsed(now, *time).as_secs();
+        let threads: Vec<Beat> = self.beats.lock()
+            .map(|beats| beats.iter().map(|(name, time)| Beat {
+                name: name.clone(),
+                seconds: time.as_ref().map(seconds),
+                alive: time.map(|time| elapsed(now, time) < self.stale).unwrap_or(false),
+            }).collect())
+            .unwrap_or_default();
+        let alive = threads.iter().all(|thread| thread.alive);
+        let migrated = self.migrated.load(Ordering::SeqCst);
+        Status {
+            alive,
+            ready: alive && migrated && esi.unwrap_or(true),
+            threads,
+            last_killmail_seconds: self.killmail.lock().ok().and_then(|time| time.as_ref().map(seconds)),
+            migrated,
+            esi,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn liveness() {
+        let mut config = Config::default();
+        config.services.resolvers = 2;
+        let health = Health::new(&config);
+        let status = health.check(Instant::now(), None);
+        assert!(!status.alive);
+        assert_eq!(Some(&Beat { name: String::from("resolver 1"), seconds: None, alive: false }), status.threads.last());
+
+        for name in [MONITOR.to_string(), DATABASE.to_string(), get_resolver_name(0), get_resolver_name(1)] {
+            health.beat(name);
+        }
+        let now = Instant::now();
+        let status = health.check(now, None);
+        assert!(status.alive);
+        assert!(!status.ready);
+        assert_eq!(None, status.last_killmail_seconds);
+
+        health.set_migrated();
+        health.killmail();
+        assert!(health.check(now, None).ready);
+        assert!(!health.check(now, Some(false)).ready);
+        assert_eq!(Some(0), health.check(Instant::now(), None).last_killmail_seconds);
+
+        let status = health.check(now + Duration::from_secs(STALE_SECONDS + 1), Some(true));
+        assert!(!status.alive);
+        assert!(!status.ready);
+        assert!(status.threads.iter().all(|thread| !thread.alive && thread.seconds.is_some()));
+    }
+
+    #[test]
+    fn esi_probe() {
+        let health = Health::new(&Config::default());
+        let now = Instant::now();
+        assert!(!health.get_esi(now, &|| false));
+        assert!(!health.get_esi(now + Duration::from_secs(1), &|| true));
+        assert!(health.get_esi(now + ESI_PROBE, &|| true));
+    }
+}
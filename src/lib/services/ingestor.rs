@@ -0,0 +1,41 @@
+use crate::api::{Order, OrderType};
+use crate::services::{AppContext, Command, Message, Model};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// ESI caches the market orders for five minutes, the snapshot every quarter is enough
+const INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Snapshots all pages of the market orders of the configured regions
+pub fn run(context: actix_web::web::Data<AppContext>) {
+    info!("Started");
+    let regions = context.config.market.regions.clone();
+    info!("market regions: {:?}", regions);
+    let mut next = Instant::now();
+    loop {
+        if let Some(Command::Quit) = context.commands.pop() {
+            context.commands.push(Command::Quit);
+            info!("received Command::Quit");
+            break;
+        }
+        if !regions.is_empty() && Instant::now() >= next {
+            next = Instant::now() + INTERVAL;
+            for region_id in &regions {
+                // The partial snapshot would turn the missing orders into the fills and the cancels
+                let orders = match Order::load(region_id, OrderType::ALL, None) {
+                    Some(orders) => orders,
+                    None => continue,
+                };
+                info!("received {} orders of {}", orders.len(), region_id);
+                if orders.is_empty() {
+                    // The empty answer is the ESI failure, not the empty market
+                    continue;
+                }
+                context.database.push(Message::Save(Model::MarketOrders((*region_id, orders))));
+            }
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+    info!("Ended");
+}
@@ -2,10 +2,17 @@ pub mod server;
 pub mod monitor;
 pub mod resolver;
 pub mod database;
+pub mod notifier;
+pub mod stream;
+pub mod pricer;
+pub mod ingestor;
+pub mod health;
 
 use crate::api;
+use crate::config::Config;
 use crate::models;
-use std::sync::{Arc, Mutex, Condvar};
+use crate::navigation;
+use std::sync::{Arc, Mutex, Condvar, RwLock};
 use std::collections::HashMap;
 use uuid::adapter::Simple as Uid;
 
@@ -37,6 +44,11 @@ pub enum Model{
     Constellation(api::constellation::Constellation),
     Killmail(api::Killmail),
     Observatory(i32),
+    Watched((String, String, i32)),
+    Rule(models::rule::NewRule),
+    RuleId(i32),
+    Prices(Vec<models::price::Price>),
+    MarketOrders((i32, Vec<api::Order>)),
 }
 
 #[derive(Debug, PartialEq)]
@@ -46,7 +58,7 @@ pub enum Area{
     Region(i32),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Actor{
     Character(i32),
     Corporation(i32),
@@ -62,7 +74,11 @@ pub enum Category{
     Victim(i32),
     Attackers(i32),
     Items(i32),
-    ObservatoryPath(i32),
+    /// Victims of the killmails
+    Victims(Vec<i32>),
+    /// Items of the killmails
+    KillmailItems(Vec<i32>),
+    Nearest(navigation::SearchQuery),
     System(i32),
     Region(i32),
     Constellation(i32),
@@ -75,12 +91,33 @@ pub enum Category{
     HistoryCount((Area, i32)),
     ObjectDesc((String, String)),
     Neighbors(Area),
+    Route(navigation::RouteQuery),
+    RouteHistory((Vec<i32>, i32)),
+    Kills(i32),
+    SystemsLosses((Vec<i32>, i32)),
+    Statistics((api::stats::Entity, i32)),
+    Activity((api::stats::Entity, models::DateTime, models::DateTime)),
+    Killmails(Vec<i32>),
+    Alerts(i32),
+    Watchlists,
+    Watched,
+    Rules,
+    ExportIds(crate::export::Filter),
+    Export(Vec<i32>),
+    /// The types with the dates of the first and the last kill
+    Prices((Vec<i32>, models::Date, models::Date)),
+    PriceHistory((i32, models::Date)),
+    MarketOrders((Vec<i32>, Vec<i32>, bool)),
+    MarketRegions,
+    TypeVolumes((i32, models::DateTime, i32)),
+    TypeNames(Vec<String>),
 }
 
 #[derive(Debug, PartialEq)]
 pub enum Report{
     Killmail(models::killmail::KillmailNamed),
     Victim(models::victim::VictimNamed),
+    Victims(Vec<models::victim::VictimNamed>),
     Attackers(Vec<models::attacker::AttackerNamed>),
     Items(Vec<models::item::ItemNamed>),
     History(Vec<models::killmail::KillmailNamed>),
@@ -93,8 +130,23 @@ pub enum Report{
     ConstellationNeighbors(Vec<models::constellation::ConstellationNeighbors>),
     RegionNeighbors(Vec<models::region::RegionNeighbors>),
     Constellations(Vec<models::constellation::ConstellationNamed>),
-    ObservatoryPath(Vec<models::system::ObservatoryPath>),
+    Nearest(Vec<navigation::Found>),
+    Route(Vec<navigation::Hop>),
+    RouteHistory(Vec<models::killmail::SystemKill>),
+    Kills(navigation::Kills),
+    SystemsLosses(Vec<models::killmail::SystemLoss>),
+    Participants(Vec<models::participant::Participant>),
+    Alerts(Vec<models::alert::Alert>),
+    Watchlists(Vec<models::watchlist::Watchlist>),
+    Watched(Vec<models::watchlist::Watched>),
+    Rules(Vec<models::rule::Rule>),
+    Ids(Vec<i32>),
+    Killmails(Vec<api::Killmail>),
+    Prices(Vec<models::price::Price>),
+    MarketOrders(Vec<models::market::MarketOrder>),
+    TypeVolumes(Vec<models::market::TypeVolume>),
     Object(models::object::Object),
+    Objects(Vec<models::object::Object>),
     Id(i32),
     NotFoundId(i32),
     NotFoundName(String),
@@ -111,6 +163,7 @@ pub enum Message{
     Check(Category),
     Report((Uid, Report)),
     Find((Uid, Category)),
+    Notify(crate::webhooks::Notification),
 }
 
 type Commands = Channel<Command>;
@@ -128,23 +181,34 @@ pub struct AppContext {
     pub database: Queue,
     pub resolver: Queue,
     pub responses: Queue,
-    pub counters: Mutex<HashMap<String, u64>>
+    pub notifier: Queue,
+    pub streams: stream::Hub,
+    pub counters: Mutex<HashMap<String, u64>>,
+    pub universe: RwLock<navigation::Universe>,
+    pub health: health::Health,
+    pub config: Config,
 }
 impl AppContext {
 
-    pub fn new<S: Into<String>>(address: S, client: S, storage: S, backup: S, timeout: u64, allowed: Option<DateTime<Utc>>) -> Self {
+    /// The client is the RedisQ queue id
+    pub fn new<S: Into<String>>(config: &Config, client: S) -> Self {
         Self {
-            server: address.into(),
+            server: config.server.interface.clone(),
             client: client.into(),
-            storage: storage.into(),
-            backup: backup.into(),
-            timeout: timeout,
-            allowed: allowed,
+            storage: config.get_scripts(),
+            backup: config.get_backup(),
+            timeout: config.server.timeout,
+            allowed: Some(config.get_allowed()),
             commands: Commands::new(Arc::new((Mutex::new(false), Condvar::new()))),
             database: Queue::new(Arc::new((Mutex::new(false), Condvar::new()))),
             resolver: Queue::new(Arc::new((Mutex::new(false), Condvar::new()))),
             responses: Queue::new(Arc::new((Mutex::new(false), Condvar::new()))),
-            counters: Mutex::new(HashMap::new())
+            notifier: Queue::new(Arc::new((Mutex::new(false), Condvar::new()))),
+            streams: stream::Hub::new(),
+            counters: Mutex::new(HashMap::new()),
+            universe: RwLock::new(navigation::Universe::new()),
+            health: health::Health::new(config),
+            config: config.clone(),
         }
     }
 
@@ -160,7 +224,7 @@ impl AppContext {
     }
 
     pub fn get_root(&self) ->String {
-        format!("http://{}/navigator", &self.server)
+        self.config.get_root()
     }
 
     pub fn get_js_url<S: Into<String>>(&self, url: S) ->String {
@@ -271,11 +335,16 @@ impl Channel<Message> {
         }
     }
 
+    /// Wakes up without the message after the heartbeat interval, the idle threads have to beat
     fn wait_notification(&self) {
         let (lock, var) = &*self.guard;
         let mut ready = lock.lock().unwrap();
         while !*ready {
-            ready = var.wait(ready).unwrap();
+            let (guard, timeout) = var.wait_timeout(ready, health::HEARTBEAT).unwrap();
+            ready = guard;
+            if timeout.timed_out() {
+                break;
+            }
         }
     }
 
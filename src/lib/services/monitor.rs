@@ -1,11 +1,14 @@
 use crate::api;
+use crate::metrics;
 use crate::services::{AppContext, Command, Message, Model};
+use crate::services::health;
 
 use crossbeam_utils::sync::Parker;
 
 pub fn run(context: actix_web::web::Data<AppContext>) {
     info!("Started");
     loop {
+        context.health.beat(health::MONITOR);
         if let Some(Command::Quit) = context.commands.pop() {
             context.commands.push(Command::Quit);
             info!("received Command::Quit");
@@ -13,6 +16,7 @@ pub fn run(context: actix_web::web::Data<AppContext>) {
         }
         if let Some(package) = api::gw::get_package(&context.client) {
             if let Some(content) = package.content {
+                metrics::increment("zkb_redisq_polls_total", &[("result", "killmail")]);
                 let killmail = content.killmail;
                 info!("{} {} {} {}",
                     killmail.killmail_time.time().to_string(),
@@ -28,11 +32,13 @@ pub fn run(context: actix_web::web::Data<AppContext>) {
                     }
                 }
             } else {
+                metrics::increment("zkb_redisq_polls_total", &[("result", "empty")]);
                 let timeout = context.timeout.into();
                 info!("monitor will suspended {} sec", timeout);
                 Parker::new().park_timeout(std::time::Duration::from_secs(timeout))
             }
         } else {
+            metrics::increment("zkb_redisq_polls_total", &[("result", "failed")]);
             let timeout = context.timeout.into();
             info!("CCP API down? Will suspended {} sec", timeout);
             Parker::new().park_timeout(std::time::Duration::from_secs(timeout))
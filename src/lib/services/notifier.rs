@@ -0,0 +1,53 @@
+use crate::services::{AppContext, Command, Message};
+use crate::services::stream::KillEvent;
+use crate::webhooks::{Dispatcher, Sink};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Delivers the saved killmails to the live killfeed subscribers and,
+/// with the notifier feature, to the webhooks listed in the configured file
+pub fn run(context: actix_web::web::Data<AppContext>) {
+    info!("Started");
+    let sinks = if context.config.features.notifier {
+        context.config.services.webhooks.as_ref().map(|path| Sink::load(path)).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    info!("loaded {} webhooks", sinks.len());
+    let mut dispatcher = Dispatcher::new(sinks);
+    let mut keepalive = Instant::now();
+    loop {
+        if let Some(Command::Quit) = context.commands.pop() {
+            context.commands.push(Command::Quit);
+            info!("received Command::Quit");
+            break;
+        }
+        // Do not block on the empty queue, the retries have to go on
+        if context.notifier.len() > 0 {
+            match context.notifier.pop() {
+                Some(Message::Notify(notification)) => {
+                    dispatcher.dispatch(&notification, Instant::now());
+                    if !context.streams.is_empty() {
+                        context.streams.publish(&notification, &KillEvent::new(&notification));
+                    }
+                },
+                Some(Message::Ping) | None => {},
+                Some(message) => warn!("received: {:?} ", message),
+            }
+        } else {
+            thread::sleep(Duration::from_millis(500));
+        }
+        if keepalive.elapsed() >= KEEPALIVE {
+            context.streams.keepalive();
+            keepalive = Instant::now();
+        }
+        let delivered = dispatcher.process(Instant::now());
+        if delivered > 0 {
+            info!("delivered {} webhooks, pending: {}", delivered, dispatcher.pending());
+        }
+    }
+    info!("Ended");
+}
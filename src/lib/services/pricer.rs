@@ -0,0 +1,58 @@
+use crate::api;
+use crate::api::price::Prices;
+use crate::models::Date;
+use crate::provider;
+use crate::services::{AppContext, Command, Message, Model};
+use crate::valuation;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The failed snapshot is taken again after the delay
+const RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Returns false when ESI did not answer
+fn take_snapshot(context: &AppContext, date: &Date) -> bool {
+    let prices = Prices::new();
+    if prices.items.is_empty() {
+        warn!("ESI returned no prices, the snapshot for {} is postponed", date);
+        return false;
+    }
+    let hub = valuation::REFERENCE_HUB;
+    let orders = match api::Order::load(&hub.region_id, api::OrderType::ALL, None) {
+        Some(orders) => orders,
+        None => {
+            warn!("ESI returned not all {} orders, the snapshot for {} is postponed", hub.name, date);
+            return false;
+        }
+    };
+    let snapshot = valuation::get_snapshot(date, &prices, &orders);
+    info!("{} prices and {} {} orders taken for {}", prices.items.len(), orders.len(), hub.name, date);
+    context.database.push(Message::Save(Model::Prices(snapshot)));
+    provider::set_prices(prices);
+    true
+}
+
+/// Stores the price snapshot once per the UTC day, the day started before the restart is taken again
+pub fn run(context: actix_web::web::Data<AppContext>) {
+    info!("Started");
+    let mut last: Option<Date> = None;
+    let mut retry = Instant::now();
+    loop {
+        if let Some(Command::Quit) = context.commands.pop() {
+            context.commands.push(Command::Quit);
+            info!("received Command::Quit");
+            break;
+        }
+        let today = chrono::Utc::now().naive_utc().date();
+        if last != Some(today) && Instant::now() >= retry {
+            if take_snapshot(&context, &today) {
+                last = Some(today);
+            } else {
+                retry = Instant::now() + RETRY_DELAY;
+            }
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+    info!("Ended");
+}
@@ -1,9 +1,22 @@
 use crate::api;
 use crate::services::{AppContext, Command, Message, Api, Model, Category};
+use crate::services::health;
 
-pub fn run(context: actix_web::web::Data<AppContext>) {
+pub fn run(context: actix_web::web::Data<AppContext>, id: usize) {
     info!("Started");
+    let name = health::get_resolver_name(id);
+    // The first resolver loads the capital groups the alert rules are evaluated with
+    // and the ship groups of the route danger
+    let mut capitals = id != 0;
+    let mut ship_classes = id != 0;
     loop {
+        context.health.beat(name.as_str());
+        if !capitals {
+            capitals = crate::alerts::load_capitals();
+        }
+        if !ship_classes {
+            ship_classes = crate::navigation::ShipClass::load_groups();
+        }
         if let Some(Command::Quit) = context.commands.pop() {
             context.commands.push(Command::Quit);
             context.resolver.push(Message::Ping); // ping other threads if any
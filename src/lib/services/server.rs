@@ -1,12 +1,33 @@
 use crate::services::{Context, Command, Message};
+use crate::services::health;
 use crate::reports;
 use crate::reports::network;
 use crate::reports::Reportable;
 use crate::reports::ReportableEx;
+use crate::webhooks::Filter;
+use crate::export;
+use crate::valuation;
 
 use actix_rt;
 use actix_files::NamedFile;
-use actix_web::{web, App, HttpServer, HttpResponse, middleware, Result};
+use actix_web::{web, error, App, HttpServer, HttpResponse, middleware, Result};
+use actix_web::http::ContentEncoding;
+use actix_web::middleware::BodyEncoding;
+use futures::{stream, Stream};
+use bytes::Bytes;
+use serde::Deserialize;
+use std::time::Instant;
+
+/// Counts the visit by the path for the statistic page and by the route pattern for the metrics
+macro_rules! notify {
+    ($ctx:expr, $route:literal) => {
+        notify!($ctx, $route,)
+    };
+    ($ctx:expr, $route:literal, $($arg:expr),*) => {{
+        crate::metrics::increment("zkb_http_requests_total", &[("route", $route)]);
+        $ctx.notify(format!($route, $($arg),*));
+    }};
+}
 
 fn style() -> &'static str {
     "<style> body { background-color: LightSeaGreen; } </style>"
@@ -27,20 +48,22 @@ fn quit(ctx: &Context) -> String {
     ctx.database.push(Message::Ping);
     ctx.resolver.push(Message::Ping);
     ctx.responses.push(Message::Ping);
+    ctx.notifier.push(Message::Ping);
     actix_rt::System::current().stop();
     format!("Quit\n")
 }
 
 fn ping(ctx: &Context) -> String {
-    ctx.notify("navigator/cmd");
+    notify!(ctx, "navigator/cmd");
     ctx.database.push(Message::Ping);
     ctx.resolver.push(Message::Ping);
     ctx.responses.push(Message::Ping);
+    ctx.notifier.push(Message::Ping);
     format!("Ping\n")
 }
 
 fn statistic(_info: web::Path<String>, ctx: Context) -> HttpResponse {
-    ctx.notify("navigator/cmd/statistic");
+    notify!(ctx, "navigator/cmd/statistic");
     let mut output = String::new();
     match ctx.get_visits() {
         Some(map) => {
@@ -56,11 +79,50 @@ fn statistic(_info: web::Path<String>, ctx: Context) -> HttpResponse {
     return wrap(output);
 }
 
+/// Prometheus scrape, the queues and the database file are taken on each scrape
+fn metrics(ctx: Context) -> HttpResponse {
+    use crate::metrics::{self, Family};
+    let mut families = vec![Family::gauge("zkb_queue_length")
+        .add(&[("queue", "database")], ctx.database.len() as f64)
+        .add(&[("queue", "resolver")], ctx.resolver.len() as f64)
+        .add(&[("queue", "responses")], ctx.responses.len() as f64)
+        .add(&[("queue", "notifier")], ctx.notifier.len() as f64)];
+    match std::fs::metadata(&ctx.config.database.url) {
+        Ok(file) => families.push(Family::gauge("zkb_database_size_bytes").add(&[], file.len() as f64)),
+        Err(e) => warn!("was not able to get the size of {}: {}", ctx.config.database.url, e),
+    }
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(metrics::render(&families))
+}
+
+fn health_response(status: &health::Status, ok: bool) -> HttpResponse {
+    let mut response = if ok { HttpResponse::Ok() } else { HttpResponse::ServiceUnavailable() };
+    response
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(serde_json::to_string(status).unwrap_or_default())
+}
+
+/// Liveness, fails when the monitor, a resolver or the database thread stopped beating
+fn healthz(ctx: Context) -> HttpResponse {
+    let status = ctx.health.check(Instant::now(), None);
+    health_response(&status, status.alive)
+}
+
+/// Readiness, the live threads with the applied migrations and the reachable ESI
+fn readyz(ctx: Context) -> HttpResponse {
+    let now = Instant::now();
+    let esi = ctx.health.get_esi(now, &crate::api::gw::is_esi_reachable);
+    let status = ctx.health.check(now, Some(esi));
+    health_response(&status, status.ready)
+}
 
 fn find(info: web::Path<String>, ctx: Context) -> HttpResponse {
     use crate::reports::Names;
     info!("/find/{}", info);
-    ctx.notify("navigator/find");
+    notify!(ctx, "navigator/find");
     wrap(Names::report(info.as_ref(), &ctx))
 }
 
@@ -70,7 +132,7 @@ fn history(info: web::Path<(String, i32, i32)>, ctx: Context) -> HttpResponse {
     let route = &info.0;
     let id = info.1;
     let minutes = info.2;
-    ctx.notify(format!("navigator/history/{}", route));
+    notify!(ctx, "navigator/history/{}", route);
 
     let body = match route.as_ref() {
         "system" => reports::History::system(&id, &minutes, &ctx),
@@ -88,13 +150,13 @@ fn report(info: web::Path<(String, String, i32, i32)>, ctx: Context) -> HttpResp
     let class = &info.1;
     let id = info.2;
     let minutes = info.3;
-    ctx.notify(format!("navigator/report/{}/{}", category, class));
+    notify!(ctx, "navigator/report/{}/{}", category, class);
 
     wrap(reports::History::report(category, class, &id, &minutes, &ctx))
 }
 
 fn desc(info: web::Path<(String, i32)>, ctx: Context) -> HttpResponse {
-    ctx.notify(format!("navigator/desc/{}", &info.0));
+    notify!(ctx, "navigator/desc/{}", &info.0);
     let body = match info.0.as_ref() {
         "alliance" => reports::Alliance::description(&info.1, &ctx),
         "corporation" => reports::Corporation::description(&info.1, &ctx),
@@ -103,26 +165,93 @@ fn desc(info: web::Path<(String, i32)>, ctx: Context) -> HttpResponse {
     wrap(body)
 }
 
-fn stat(info: web::Path<(String, i32)>, ctx: Context) -> HttpResponse {
+#[derive(Deserialize)]
+struct StatParams {
+    minutes: Option<i32>,
+}
+
+fn stat(info: web::Path<(String, i32)>, params: web::Query<StatParams>, ctx: Context) -> HttpResponse {
+    let (route, id) = info.into_inner();
+    notify!(ctx, "navigator/stat/{}", route);
+    let minutes = params.minutes.unwrap_or(reports::statistics::WEEK_MINUTES);
+    let body = match route.as_ref() {
+        "alliance" => reports::Alliance::stat(&id, &minutes, &ctx),
+        "corporation" => reports::Corporation::stat(&id, &minutes, &ctx),
+        "character" => reports::Character::stat(&id, &minutes, &ctx),
+        "system" => reports::System::stat(&id, &minutes, &ctx),
+        "constellation" => reports::Constellation::stat(&id, &minutes, &ctx),
+        "region" => reports::Region::stat(&id, &minutes, &ctx),
+
+        _=> format!("Unknown route {} ", route)
+    };
+    wrap(body)
+}
+
+fn doctrines(info: web::Path<(String, i32)>, params: web::Query<StatParams>, ctx: Context) -> HttpResponse {
+    let (route, id) = info.into_inner();
+    notify!(ctx, "navigator/doctrines/{}", route);
+    let minutes = params.minutes.unwrap_or(reports::statistics::MONTH_MINUTES);
+    let body = match route.as_ref() {
+        "alliance" => reports::Alliance::doctrines(&id, &minutes, &ctx),
+        "corporation" => reports::Corporation::doctrines(&id, &minutes, &ctx),
+        _=> format!("Unknown route {} ", route)
+    };
+    wrap(body)
+}
+
+fn hulls(info: web::Path<(String, i32)>, params: web::Query<StatParams>, ctx: Context) -> HttpResponse {
+    let (class, id) = info.into_inner();
+    notify!(ctx, "navigator/hulls/{}", class);
+    let minutes = params.minutes.unwrap_or(reports::statistics::WEEK_MINUTES);
+    let body = match get_actor(&class, id) {
+        Some(entity) => reports::statistics::hulls(entity, &minutes, &ctx),
+        None => format!("Unknown class {} ", class)
+    };
+    wrap(body)
+}
+
+fn hull_killmails(info: web::Path<(String, i32, i32)>, params: web::Query<StatParams>, ctx: Context) -> HttpResponse {
+    let (class, id, ship_id) = info.into_inner();
+    notify!(ctx, "navigator/hulls/{}/ship", class);
+    let minutes = params.minutes.unwrap_or(reports::statistics::WEEK_MINUTES);
+    let body = match get_actor(&class, id) {
+        Some(entity) => reports::statistics::hull_killmails(entity, &ship_id, &minutes, &ctx),
+        None => format!("Unknown class {} ", class)
+    };
+    wrap(body)
+}
+
+/// Statistics from zKillboard to compare with the local ones
+fn zkb_stat(info: web::Path<(String, i32)>, ctx: Context) -> HttpResponse {
     let (route, id) = info.into_inner();
-    ctx.notify(format!("navigator/stat/{}", route));
+    notify!(ctx, "navigator/zkb/{}", route);
     let body = match route.as_ref() {
-        "alliance" => reports::Alliance::stat(&id, &ctx),
-        "corporation" => reports::Corporation::stat(&id, &ctx),
-        "character" => reports::Character::stat(&id, &ctx),
-        "system" => reports::System::stat(&id, &ctx),
-        "constellation" => reports::Constellation::stat(&id, &ctx),
-        "region" => reports::Region::stat(&id, &ctx),
+        "alliance" => reports::Alliance::zkb_stat(&id, &ctx),
+        "corporation" => reports::Corporation::zkb_stat(&id, &ctx),
+        "character" => reports::Character::zkb_stat(&id, &ctx),
+        "system" => reports::System::zkb_stat(&id, &ctx),
+        "constellation" => reports::Constellation::zkb_stat(&id, &ctx),
+        "region" => reports::Region::zkb_stat(&id, &ctx),
 
         _=> format!("Unknown route {} ", route)
     };
     wrap(body)
 }
 
-fn api(info: web::Path<(String, String)>, ctx: Context) -> HttpResponse {
+#[derive(Deserialize, Debug)]
+struct ValuationParams {
+    price: Option<String>,
+}
+impl ValuationParams {
+    fn get_source(&self) -> valuation::Source {
+        self.price.as_ref().and_then(|name| valuation::Source::from_name(name)).unwrap_or_default()
+    }
+}
+
+fn api(info: web::Path<(String, String)>, params: web::Query<ValuationParams>, ctx: Context) -> HttpResponse {
     let (route, id) = info.into_inner();
     info!("/api/{}/{}", &route, &id);
-    ctx.notify(format!("navigator/api/{}", route));
+    notify!(ctx, "navigator/api/{}", route);
     let body = match route.as_ref() {
         "constellation" => reports::Constellation::report(&id, &ctx),
         "constellation_brief" => reports::Constellation::brief(&id, &ctx),
@@ -132,7 +261,7 @@ fn api(info: web::Path<(String, String)>, ctx: Context) -> HttpResponse {
         "system_brief" => reports::System::brief(&id, &ctx),
         "system_hint" => reports::System::hint(&id, &ctx),
         "killmail_brief" => reports::Killmail::brief(&id, &ctx),
-        "killmail" => reports::Killmail::report(&id, &ctx),
+        "killmail" => reports::Killmail::report(&id, &params.get_source(), &ctx),
         "character" => reports::Character::report(&route, &id, &ctx),
         "corporation" => reports::Corporation::report(&route, &id, &ctx),
         "alliance" => reports::Alliance::report(&route, &id, &ctx),
@@ -143,13 +272,70 @@ fn api(info: web::Path<(String, String)>, ctx: Context) -> HttpResponse {
     wrap(body)
 }
 
-fn route(info: web::Path<(String, String, String)>, ctx: Context) -> HttpResponse {
+#[derive(Deserialize, Debug)]
+struct RouteParams {
+    avoid: Option<String>,
+    danger: Option<f32>,
+    windows: Option<String>,
+}
+impl RouteParams {
+    fn get_avoid(&self) -> Vec<String> {
+        self.avoid.clone()
+            .map(|list| list.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    fn get_danger(&self) -> f32 {
+        self.danger.unwrap_or(0.0)
+    }
+
+    fn get_windows(&self) -> Vec<i32> {
+        let windows: Vec<i32> = self.windows.clone()
+            .map(|list| list.split(',').filter_map(|minutes| minutes.trim().parse().ok()).filter(|minutes| *minutes > 0).collect())
+            .unwrap_or_default();
+        if windows.is_empty() {
+            vec![10, 60, 360, 1440]
+        } else {
+            windows
+        }
+    }
+}
+
+fn route(info: web::Path<(String, String, String)>, params: web::Query<RouteParams>, ctx: Context) -> HttpResponse {
     let (route, departure, destination) = info.into_inner();
-    ctx.notify("navigator/api/route");
-    let body = reports::System::route_named(route, departure, destination, &ctx);
+    notify!(ctx, "navigator/api/route");
+    let body = reports::System::route_named(route, departure, destination, params.get_avoid(), params.get_danger(), params.get_windows(), &ctx);
     wrap(body)
 }
 
+fn route_json(info: web::Path<(String, String, String)>, params: web::Query<RouteParams>, ctx: Context) -> HttpResponse {
+    let (route, departure, destination) = info.into_inner();
+    info!("/json/route/{}/{}/{}", &route, &departure, &destination);
+    notify!(ctx, "navigator/json/route");
+    let (mut response, body) = match reports::System::route_json(route, departure, destination, params.get_avoid(), params.get_danger(), params.get_windows(), &ctx) {
+        Ok(body) => (HttpResponse::Ok(), body),
+        Err(e) => (HttpResponse::BadRequest(), serde_json::json!({ "error": e }).to_string()),
+    };
+    response
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(body)
+}
+
+#[derive(Deserialize, Debug)]
+struct NearestParams {
+    minutes: Option<i32>,
+    limit: Option<usize>,
+}
+
+fn nearest(info: web::Path<(String, String, u32)>, params: web::Query<NearestParams>, ctx: Context) -> HttpResponse {
+    let (predicate, system, jumps) = info.into_inner();
+    info!("/nearest/{}/{}/{}", &predicate, &system, jumps);
+    notify!(ctx, "navigator/nearest/{}", predicate);
+    let minutes = params.minutes.unwrap_or(60);
+    let limit = params.limit.unwrap_or(5);
+    wrap(reports::System::nearest(predicate, system, jumps, minutes, limit, &ctx))
+}
 
 fn cmd(info: web::Path<String>, ctx: Context) -> String {
     info!("/cmd/{}", info);
@@ -163,7 +349,7 @@ fn cmd(info: web::Path<String>, ctx: Context) -> String {
 
 fn services(info: web::Path<(String, i32, i32)>, ctx: Context) -> HttpResponse {
     info!("/services/{}/{}/{}", info.0, info.1, info.2);
-    ctx.notify("navigator/services");
+    notify!(ctx, "navigator/services");
     let body = match info.0.as_ref() {
         "route" => reports::System::route(info.1, info.2, &ctx),
         _ => format!("/services/{}/{}/{}", info.0, info.1, info.2)
@@ -172,9 +358,249 @@ fn services(info: web::Path<(String, i32, i32)>, ctx: Context) -> HttpResponse {
     wrap(body)
 }
 
+fn alerts(ctx: Context) -> HttpResponse {
+    notify!(ctx, "navigator/alerts");
+    wrap(reports::alerts::report(&ctx))
+}
+
+fn watch(info: web::Path<(String, String, i32, String)>, ctx: Context) -> HttpResponse {
+    let (list, category, id, cmd) = info.into_inner();
+    notify!(ctx, "navigator/watch/{}", cmd);
+    wrap(reports::alerts::watch(list, category, id, &cmd, &ctx))
+}
+
+#[derive(Deserialize, Debug)]
+struct RuleParams {
+    name: String,
+    watchlist: Option<i32>,
+    system: Option<i32>,
+    jumps: Option<i32>,
+    capitals: Option<bool>,
+}
+
+fn rule_add(params: web::Query<RuleParams>, ctx: Context) -> HttpResponse {
+    use crate::models::rule::NewRule;
+    notify!(ctx, "navigator/rules/add");
+    let params = params.into_inner();
+    let rule = NewRule {
+        name: params.name,
+        watchlist_id: params.watchlist,
+        system_id: params.system,
+        jumps: params.jumps,
+        capitals: params.capitals.unwrap_or(false),
+    };
+    wrap(reports::alerts::rule_add(rule, &ctx))
+}
+
+fn rule_del(info: web::Path<i32>, ctx: Context) -> HttpResponse {
+    notify!(ctx, "navigator/rules/del");
+    wrap(reports::alerts::rule_del(&info, &ctx))
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamParams {
+    area: Option<i32>,
+    actor: Option<i32>,
+    min_value: Option<u64>,
+}
+impl StreamParams {
+    fn get_filter(&self) -> Filter {
+        Filter {
+            areas: self.area.into_iter().collect(),
+            actors: self.actor.into_iter().collect(),
+            min_value: self.min_value,
+            alerts_only: false,
+        }
+    }
+}
+
+fn stream_kills(params: web::Query<StreamParams>, ctx: Context) -> HttpResponse {
+    info!("/stream/kills {:?}", params);
+    notify!(ctx, "navigator/stream/kills");
+    let receiver = ctx.streams.subscribe(params.get_filter());
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .header("X-Header", "zkb")
+        .encoding(ContentEncoding::Identity)
+        .streaming(receiver.map_err(|_| error::ErrorInternalServerError("killfeed closed")))
+}
+
+fn feed(info: web::Path<(String, i32)>, ctx: Context) -> HttpResponse {
+    let (class, id) = info.into_inner();
+    info!("/feed/{}/{}.atom", &class, id);
+    notify!(ctx, "navigator/feed/{}", class);
+    match reports::feed::report(&class, id, &ctx) {
+        Some(body) => HttpResponse::Ok()
+            .content_type("application/atom+xml; charset=UTF-8")
+            .header("X-Header", "zkb")
+            .body(body),
+        None => HttpResponse::NotFound().body(format!("Unknown class {}", class)),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct PriceParams {
+    days: Option<i32>,
+}
+
+fn prices(info: web::Path<i32>, params: web::Query<PriceParams>, ctx: Context) -> HttpResponse {
+    let type_id = info.into_inner();
+    notify!(ctx, "navigator/prices");
+    let days = reports::prices::clamp_days(params.days);
+    wrap(reports::prices::history(&type_id, &days, &ctx))
+}
+
+fn prices_json(info: web::Path<i32>, params: web::Query<PriceParams>, ctx: Context) -> HttpResponse {
+    let type_id = info.into_inner();
+    info!("/json/prices/{}", type_id);
+    notify!(ctx, "navigator/json/prices");
+    let days = reports::prices::clamp_days(params.days);
+    HttpResponse::Ok()
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(reports::prices::history_json(&type_id, &days, &ctx))
+}
+
+#[derive(Deserialize, Debug)]
+struct ArbitrageParams {
+    from: String,
+    to: String,
+    types: Option<String>,
+    rank: Option<String>,
+    tax: Option<f32>,
+    broker: Option<f32>,
+    limit: Option<usize>,
+}
+impl ArbitrageParams {
+    fn get_query(&self, ctx: &Context) -> Result<reports::market::Query, String> {
+        use crate::market::arbitrage::{Fees, Rank, SALES_TAX, BROKER_FEE};
+        let types = self.types.clone()
+            .map(|list| list.split(',').filter_map(|id| id.trim().parse().ok()).collect())
+            .unwrap_or_default();
+        let rank = match &self.rank {
+            Some(name) => Rank::from_name(name).ok_or(format!("Unknown rank '{}'", name))?,
+            None => Rank::Profit,
+        };
+        Ok(reports::market::Query {
+            sources: reports::market::get_locations(&self.from, ctx)?,
+            destinations: reports::market::get_locations(&self.to, ctx)?,
+            types,
+            fees: Fees { sales_tax: self.tax.unwrap_or(SALES_TAX), broker_fee: self.broker.unwrap_or(BROKER_FEE) },
+            rank,
+            limit: self.limit.unwrap_or(reports::market::DEALS_LIMIT),
+        })
+    }
+}
+
+fn arbitrage(params: web::Query<ArbitrageParams>, ctx: Context) -> HttpResponse {
+    notify!(ctx, "navigator/market/arbitrage");
+    let body = match params.get_query(&ctx) {
+        Ok(query) => reports::market::arbitrage(&query, &ctx),
+        Err(message) => format!("<div>{}</div>", reports::escape(&message)),
+    };
+    wrap(body)
+}
+
+fn market_volumes(info: web::Path<i32>, params: web::Query<PriceParams>, ctx: Context) -> HttpResponse {
+    let region_id = info.into_inner();
+    notify!(ctx, "navigator/market/volumes");
+    wrap(reports::market::volumes(&region_id, &params.days.unwrap_or(7), &ctx))
+}
+
+/// Pasted contracts are larger than the default form limit
+const APPRAISAL_LIMIT: usize = 1024 * 1024;
+
+#[derive(Deserialize, Debug)]
+struct AppraiseParams {
+    text: Option<String>,
+    price: Option<String>,
+}
+impl AppraiseParams {
+    fn get_text(&self) -> &str {
+        self.text.as_deref().unwrap_or_default()
+    }
+
+    fn get_source(&self) -> valuation::Source {
+        self.price.as_ref().and_then(|name| valuation::Source::from_name(name)).unwrap_or_default()
+    }
+}
+
+fn appraise(params: web::Query<AppraiseParams>, ctx: Context) -> HttpResponse {
+    notify!(ctx, "navigator/appraise");
+    wrap(reports::appraisal::report(params.get_text(), &params.get_source(), &ctx))
+}
+
+fn appraise_form(params: web::Form<AppraiseParams>, ctx: Context) -> HttpResponse {
+    notify!(ctx, "navigator/appraise");
+    wrap(reports::appraisal::report(params.get_text(), &params.get_source(), &ctx))
+}
+
+fn appraise_json(params: web::Form<AppraiseParams>, ctx: Context) -> HttpResponse {
+    info!("/json/appraise");
+    notify!(ctx, "navigator/json/appraise");
+    HttpResponse::Ok()
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(reports::appraisal::report_json(params.get_text(), &params.get_source(), &ctx))
+}
+
+#[derive(Deserialize, Debug)]
+struct ExportParams {
+    start: Option<chrono::NaiveDate>,
+    end: Option<chrono::NaiveDate>,
+    class: Option<String>,
+    id: Option<i32>,
+}
+impl ExportParams {
+    /// Whole days in UTC, the last day by default
+    fn get_filter(&self) -> Option<export::Filter> {
+        let end = self.end.map(|date| date.succ().and_hms(0, 0, 0)).unwrap_or(chrono::Utc::now().naive_utc());
+        let start = self.start.map(|date| date.and_hms(0, 0, 0)).unwrap_or(end - chrono::Duration::days(1));
+        let entity = match (&self.class, self.id) {
+            (Some(class), Some(id)) => Some(reports::statistics::get_entity(class, id)?),
+            (None, None) => None,
+            _ => return None,
+        };
+        Some(export::Filter { entity, start, end })
+    }
+}
+
+fn export_killmails(info: web::Path<String>, params: web::Query<ExportParams>, ctx: Context) -> HttpResponse {
+    use crate::export::Format;
+    info!("/export/{} {:?}", info, params);
+    notify!(ctx, "navigator/export/{}", info);
+    let (format, filter) = match (Format::from_name(&info), params.get_filter()) {
+        (Some(format), Some(filter)) => (format, filter),
+        _ => return HttpResponse::BadRequest().body(format!("Unknown format {} or filter {:?}", info, params)),
+    };
+    if filter.end - filter.start > chrono::Duration::days(export::EXPORT_DAYS) {
+        return HttpResponse::BadRequest().body(format!("Killmails are exported for {} days at most", export::EXPORT_DAYS));
+    }
+    let chunks = reports::export::get_chunks(filter, &ctx);
+    let disposition = format!("attachment; filename=\"killmails.{}\"", format.extension());
+    if format == Format::Parquet {
+        return match reports::export::get_parquet(chunks, &ctx) {
+            Ok(content) => HttpResponse::Ok()
+                .content_type(format.content_type())
+                .header("Content-Disposition", disposition)
+                .header("X-Header", "zkb")
+                .body(content),
+            Err(e) => HttpResponse::InternalServerError().body(e),
+        };
+    }
+    let header = stream::once::<Bytes, error::Error>(Ok(Bytes::from(reports::export::get_header(format))));
+    let body = stream::iter_ok(chunks).map(move |ids| Bytes::from(reports::export::get_text(format, ids, &ctx)));
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .header("Content-Disposition", disposition)
+        .header("X-Header", "zkb")
+        .streaming(header.chain(body))
+}
+
 fn hidden(info: web::Path<(String, i32, String)>, ctx: Context) -> HttpResponse {
     let (area, id, cmd) = info.into_inner();
-    ctx.notify("navigator/api/hidden");
+    notify!(ctx, "navigator/api/hidden");
     let body = match (area.as_ref(), id, cmd.as_ref()){
         ("system", id, "add") => reports::System::observatory_add(&id, &ctx),
         ("system", id, "del") => reports::System::observatory_del(&id, &ctx),
@@ -196,8 +622,28 @@ pub fn run(context: Context) {
             .route("/navigator/api/{type}/{id}", web::get().to(api))
             .route("/navigator/api/{type}/{id}/{cmd}", web::get().to(hidden))
             .route("/navigator/api/route/{safety}/{src}/{dst}", web::get().to(route))
+            .route("/navigator/nearest/{predicate}/{system}/{jumps}", web::get().to(nearest))
             .route("/navigator/desc/{route}/{id}", web::get().to(desc))
             .route("/navigator/stat/{route}/{id}", web::get().to(stat))
+            .route("/navigator/zkb/{route}/{id}", web::get().to(zkb_stat))
+            .route("/navigator/doctrines/{route}/{id}", web::get().to(doctrines))
+            .route("/navigator/hulls/{class}/{id}", web::get().to(hulls))
+            .route("/navigator/hulls/{class}/{id}/{ship}", web::get().to(hull_killmails))
+            .route("/navigator/associates/{class}/{id}/{minutes}", web::get().to(associates))
+            .route("/navigator/stream/kills", web::get().to(stream_kills))
+            .route("/navigator/feed/{class}/{id}.atom", web::get().to(feed))
+            .route("/navigator/export/{format}", web::get().to(export_killmails))
+            .route("/navigator/prices/{type_id}", web::get().to(prices))
+            .route("/navigator/market/arbitrage", web::get().to(arbitrage))
+            .route("/navigator/market/volumes/{region_id}", web::get().to(market_volumes))
+            .service(web::resource("/navigator/appraise")
+                .data(web::FormConfig::default().limit(APPRAISAL_LIMIT))
+                .route(web::get().to(appraise))
+                .route(web::post().to(appraise_form)))
+            .route("/navigator/alerts", web::get().to(alerts))
+            .route("/navigator/watch/{list}/{category}/{id}/{cmd}", web::get().to(watch))
+            .route("/navigator/rules/add", web::get().to(rule_add))
+            .route("/navigator/rules/{id}/del", web::get().to(rule_del))
             .route("/navigator/cmd/{cmd}", web::get().to(cmd))
             .route("/navigator/cmd/statistic/{arg}", web::get().to(statistic))
             .route("/navigator/services/{type}/{first}/{second}", web::get().to(services))
@@ -205,8 +651,20 @@ pub fn run(context: Context) {
             .route("/navigator/report/{category}/{class}/{id}/{minutes}", web::get().to(report))
             .route("/navigator/json/nodes/{area}/{id}/{deep}", web::get().to(nodes))
             .route("/navigator/json/edges/{area}/{id}/{deep}", web::get().to(edges))
+            .route("/navigator/json/route/{safety}/{src}/{dst}", web::get().to(route_json))
+            .route("/navigator/json/heatmap/{metric}/{area}/{id}/{minutes}", web::get().to(heatmap))
+            .route("/navigator/json/timeline/{area}/{id}/{minutes}", web::get().to(timeline))
+            .route("/navigator/json/activity/{side}/{class}/{id}", web::get().to(activity))
+            .route("/navigator/json/associates/{class}/{id}/{minutes}", web::get().to(associates_json))
+            .route("/navigator/json/prices/{type_id}", web::get().to(prices_json))
+            .service(web::resource("/navigator/json/appraise")
+                .data(web::FormConfig::default().limit(APPRAISAL_LIMIT))
+                .route(web::post().to(appraise_json)))
             .route("/navigator/js/{script}", web::get().to(script))
             .route("/navigator/backup/{file}", web::get().to(backup))
+            .route("/metrics", web::get().to(metrics))
+            .route("/healthz", web::get().to(healthz))
+            .route("/readyz", web::get().to(readyz))
     })
     .bind(address)
     .unwrap()
@@ -242,7 +700,7 @@ fn backup(info: web::Path<String>, ctx: Context) -> Result<NamedFile> {
 fn nodes(info: web::Path<(String, i32, u32)>, ctx: Context) -> HttpResponse {
     let (area, id, deep) = info.into_inner();
     info!("/json/nodes/{}/{}", &area, &id);
-    ctx.notify(format!("navigator/json/nodes/{}", area));
+    notify!(ctx, "navigator/json/nodes/{}", area);
     let nodes = match area.as_ref() {
         "system" => network::get_system_nodes(&id, deep, &ctx).values().into_iter().cloned().collect(),
         "constellation" => network::get_constellation_nodes(&id, &ctx).values().into_iter().cloned().collect(),
@@ -259,7 +717,7 @@ fn nodes(info: web::Path<(String, i32, u32)>, ctx: Context) -> HttpResponse {
 fn edges(info: web::Path<(String, i32, u32)>, ctx: Context) -> HttpResponse {
     let (area, id, deep) = info.into_inner();
     info!("/json/edges/{}/{}", &area, &id);
-    ctx.notify(format!("navigator/json/edges/{}", area));
+    notify!(ctx, "navigator/json/edges/{}", area);
 
     let edges = match area.as_ref() {
         "system" => network::get_system_edges(&id, deep, &ctx),
@@ -274,3 +732,103 @@ fn edges(info: web::Path<(String, i32, u32)>, ctx: Context) -> HttpResponse {
         .body(serde_json::to_string(&edges).ok().unwrap_or_default())
 
 }
+
+#[derive(Deserialize, Debug)]
+struct AreaParams {
+    deep: Option<u32>,
+}
+
+fn heatmap(info: web::Path<(String, String, i32, i32)>, params: web::Query<AreaParams>, ctx: Context) -> HttpResponse {
+    use crate::navigation::Metric;
+    use crate::navigation::activity;
+    let (metric, area, id, minutes) = info.into_inner();
+    let minutes = activity::clamp_minutes(&minutes);
+    info!("/json/heatmap/{}/{}/{}/{}", &metric, &area, &id, &minutes);
+    notify!(ctx, "navigator/json/heatmap/{}", area);
+    let nodes: Vec<network::Node> = match Metric::from_name(&metric) {
+        Some(metric) => network::get_heatmap_nodes(&metric, &area, &id, params.deep.unwrap_or(3), &minutes, &ctx).values().cloned().collect(),
+        None => Vec::new()
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(serde_json::to_string(&nodes).ok().unwrap_or_default())
+}
+
+fn timeline(info: web::Path<(String, i32, i32)>, params: web::Query<AreaParams>, ctx: Context) -> HttpResponse {
+    use crate::navigation::activity;
+    let (area, id, minutes) = info.into_inner();
+    let minutes = activity::clamp_minutes(&minutes);
+    info!("/json/timeline/{}/{}/{}", &area, &id, &minutes);
+    notify!(ctx, "navigator/json/timeline/{}", area);
+    let timeline = network::get_timeline(&area, &id, params.deep.unwrap_or(3), &minutes, &ctx);
+
+    HttpResponse::Ok()
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(serde_json::to_string(&timeline).ok().unwrap_or_default())
+}
+
+fn get_actor(class: &str, id: i32) -> Option<crate::api::stats::Entity> {
+    match class {
+        "character" | "corporation" | "alliance" => reports::statistics::get_entity(class, id),
+        _ => None
+    }
+}
+
+fn associates(info: web::Path<(String, i32, i32)>, ctx: Context) -> HttpResponse {
+    let (class, id, minutes) = info.into_inner();
+    info!("/associates/{}/{}/{}", &class, &id, &minutes);
+    notify!(ctx, "navigator/associates/{}", class);
+    let body = match get_actor(&class, id) {
+        Some(entity) => reports::association::report(&entity, &id, &minutes, &ctx),
+        None => format!("Unknown class {} ", class)
+    };
+    wrap(body)
+}
+
+fn associates_json(info: web::Path<(String, i32, i32)>, ctx: Context) -> HttpResponse {
+    let (class, id, minutes) = info.into_inner();
+    info!("/json/associates/{}/{}/{}", &class, &id, &minutes);
+    notify!(ctx, "navigator/json/associates/{}", class);
+    let graph = get_actor(&class, id)
+        .map(|entity| reports::association::get_graph(&entity, &id, &minutes, &ctx))
+        .unwrap_or_default();
+
+    HttpResponse::Ok()
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(serde_json::to_string(&graph).ok().unwrap_or_default())
+}
+
+#[derive(Deserialize, Debug)]
+struct PeriodParams {
+    start: Option<chrono::NaiveDate>,
+    end: Option<chrono::NaiveDate>,
+}
+impl PeriodParams {
+    /// Whole days in UTC, the last week by default
+    fn get_period(&self) -> (chrono::NaiveDateTime, chrono::NaiveDateTime) {
+        let end = self.end.map(|date| date.succ().and_hms(0, 0, 0)).unwrap_or(chrono::Utc::now().naive_utc());
+        let start = self.start.map(|date| date.and_hms(0, 0, 0)).unwrap_or(end - chrono::Duration::minutes(reports::statistics::WEEK_MINUTES as i64));
+        (start, end)
+    }
+}
+
+fn activity(info: web::Path<(String, String, i32)>, params: web::Query<PeriodParams>, ctx: Context) -> HttpResponse {
+    use crate::statistics::Side;
+    let (side, class, id) = info.into_inner();
+    info!("/json/activity/{}/{}/{} {:?}", &side, &class, &id, params);
+    notify!(ctx, "navigator/json/activity/{}", class);
+    let (start, end) = params.get_period();
+    let activity = match (Side::from_name(&side), reports::statistics::get_entity(&class, id)) {
+        (Some(side), Some(entity)) => Some(reports::statistics::get_activity(&entity, &side, &start, &end, &ctx)),
+        _ => None
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/json; charset=UTF-8")
+        .header("X-Header", "zkb")
+        .body(serde_json::to_string(&activity).ok().unwrap_or_default())
+}
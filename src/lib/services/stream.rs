@@ -0,0 +1,213 @@
+use crate::webhooks::{Filter, Notification};
+
+use bytes::Bytes;
+use futures::sync::mpsc::{self, Receiver, Sender};
+use serde::Serialize;
+use std::sync::Mutex;
+
+/// Events buffered for the subscriber, the one that does not read them is dropped
+pub const BUFFER: usize = 64;
+
+/// Compact killmail description for the live feed
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct KillEvent {
+    pub killmail_id: i32,
+    pub time: String,
+    pub system_id: i32,
+    pub system: String,
+    pub ship_id: i32,
+    pub ship: String,
+    pub victim: Option<String>,
+    pub value: u64,
+    pub url: String,
+}
+impl KillEvent {
+    /// The names not resolved yet are shown by the ids
+    pub fn new(notification: &Notification) -> Self {
+        Self {
+            killmail_id: notification.killmail_id,
+            time: notification.killmail_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            system_id: notification.system_id,
+            system: notification.system_name.clone().unwrap_or(notification.system_id.to_string()),
+            ship_id: notification.ship_id,
+            ship: notification.ship_name.clone().unwrap_or(notification.ship_id.to_string()),
+            victim: notification.victim_name.clone(),
+            value: notification.value,
+            url: notification.get_url(),
+        }
+    }
+
+    fn to_bytes(&self) -> Bytes {
+        let json = serde_json::to_string(self).ok().unwrap_or_default();
+        Bytes::from(format!("event: kill\ndata: {}\n\n", json))
+    }
+}
+
+struct Subscriber {
+    filter: Filter,
+    sender: Sender<Bytes>,
+}
+impl Subscriber {
+    /// False for the disconnected and the slow subscribers
+    fn send(&mut self, bytes: Bytes) -> bool {
+        match self.sender.try_send(bytes) {
+            Ok(()) => true,
+            Err(e) => {
+                if e.is_full() {
+                    warn!("dropped the killfeed subscriber, {} events are not read", BUFFER);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Server-Sent Events subscribers of the killfeed
+pub struct Hub {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+impl Default for Hub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hub {
+    pub fn new() -> Self {
+        Self { subscribers: Mutex::new(Vec::new()) }
+    }
+
+    pub fn subscribe(&self, filter: Filter) -> Receiver<Bytes> {
+        let (sender, receiver) = mpsc::channel(BUFFER);
+        let mut subscriber = Subscriber { filter, sender };
+        if subscriber.send(Bytes::from_static(b": connected\n\n")) {
+            if let Ok(mut subscribers) = self.subscribers.lock() {
+                subscribers.push(subscriber);
+            }
+        }
+        receiver
+    }
+
+    /// Keeps the subscribers the closure returns true for
+    fn retain<F: FnMut(&mut Subscriber) -> bool>(&self, mut keep: F) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            let current: Vec<Subscriber> = subscribers.drain(..).collect();
+            subscribers.extend(current.into_iter().filter_map(|mut subscriber| if keep(&mut subscriber) { Some(subscriber) } else { None }));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscribers.lock().map(|subscribers| subscribers.len()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        0 == self.len()
+    }
+
+    /// Sends the event to the accepting subscribers, the disconnected and the slow ones are dropped.
+    /// The subscribers not interested in the event are checked by the keepalive.
+    pub fn publish(&self, notification: &Notification, event: &KillEvent) -> usize {
+        let bytes = event.to_bytes();
+        let mut sent = 0;
+        self.retain(|subscriber| {
+            if !subscriber.filter.accepts(notification) {
+                return true;
+            }
+            let delivered = subscriber.send(bytes.clone());
+            if delivered {
+                sent += 1;
+            }
+            delivered
+        });
+        sent
+    }
+
+    /// The comment line keeps the idle connections open through the proxies
+    pub fn keepalive(&self) {
+        self.retain(|subscriber| subscriber.send(Bytes::from_static(b":\n\n")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Stream;
+
+    fn notification(system_id: i32) -> Notification {
+        Notification {
+            killmail_id: 78146996,
+            killmail_time: chrono::NaiveDate::from_ymd(2019, 10, 1).and_hms(12, 0, 0),
+            system_id,
+            system_name: Some(String::from("Jita")),
+            constellation_id: None,
+            region_id: None,
+            ship_id: 587,
+            ship_name: Some(String::from("Rifter")),
+            victim_name: None,
+            value: 1_000_000,
+            actors: vec![1],
+            rules: Vec::new(),
+        }
+    }
+
+    fn event(notification: &Notification) -> KillEvent {
+        KillEvent {
+            killmail_id: notification.killmail_id,
+            time: String::from("2019-10-01 12:00:00"),
+            system_id: notification.system_id,
+            system: String::from("Jita"),
+            ship_id: notification.ship_id,
+            ship: String::from("Rifter"),
+            victim: None,
+            value: notification.value,
+            url: notification.get_url(),
+        }
+    }
+
+    fn received(receiver: Receiver<Bytes>) -> Vec<String> {
+        receiver.wait()
+            .filter_map(|bytes| bytes.ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn publish() {
+        let hub = Hub::new();
+        let all = hub.subscribe(Filter::default());
+        let jita = hub.subscribe(Filter { areas: vec![30000142], ..Filter::default() });
+        assert_eq!(2, hub.len());
+
+        let kill = notification(30000142);
+        assert_eq!(2, hub.publish(&kill, &event(&kill)));
+        let kill = notification(30002187);
+        assert_eq!(1, hub.publish(&kill, &event(&kill)));
+        drop(hub);
+
+        let all = received(all);
+        assert_eq!(3, all.len());
+        assert!(all[1].starts_with("event: kill\ndata: {"));
+        assert!(all[1].ends_with("}\n\n"));
+        assert!(all[2].contains("\"system_id\":30002187"));
+        assert_eq!(2, received(jita).len());
+    }
+
+    #[test]
+    fn disconnected() {
+        let hub = Hub::new();
+        drop(hub.subscribe(Filter::default()));
+        let kill = notification(30000142);
+        assert_eq!(0, hub.publish(&kill, &event(&kill)));
+        assert_eq!(0, hub.len());
+    }
+
+    #[test]
+    fn slow() {
+        let hub = Hub::new();
+        let _receiver = hub.subscribe(Filter::default());
+        let kill = notification(30000142);
+        let sent: usize = (0..2 * BUFFER).map(|_| hub.publish(&kill, &event(&kill))).sum();
+        assert!(sent < 2 * BUFFER);
+        assert_eq!(0, hub.len());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::api::stats::{Entity, Activity, HourKills};
+use crate::models::participant::Participant;
+use super::get_encounters;
+
+use chrono::{Datelike, Timelike};
+use serde::Serialize;
+
+/// Day names in the zKillboard order, used as the radar ids
+pub const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Side {
+    Kills,
+    Losses,
+}
+impl Side {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "kills" => Some(Side::Kills),
+            "losses" => Some(Side::Losses),
+            _ => None
+        }
+    }
+}
+
+/// Killmails count by the day of the week (Sunday first) and the hour in UTC
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct WeekActivity {
+    pub max: i32,
+    pub days: Vec<String>,
+    pub hours: Vec<Vec<i32>>,
+}
+impl WeekActivity {
+    pub fn new(entity: &Entity, participants: &Vec<Participant>, side: &Side) -> Self {
+        let mut hours = vec![vec![0; 24]; DAYS.len()];
+        for encounter in get_encounters(participants).values() {
+            let matched = match side {
+                Side::Kills => encounter.is_kill(entity),
+                Side::Losses => encounter.is_loss(entity),
+            };
+            if let (true, Some(victim)) = (matched, encounter.victim) {
+                let time = victim.killmail_time;
+                hours[time.weekday().num_days_from_sunday() as usize][time.hour() as usize] += 1;
+            }
+        }
+        Self {
+            max: hours.iter().flatten().cloned().max().unwrap_or_default(),
+            days: DAYS.iter().map(|day| String::from(*day)).collect(),
+            hours,
+        }
+    }
+
+    /// The same structure as the zKillboard activity to feed the radar charts
+    pub fn to_activity(&self) -> Activity {
+        let day = |index: usize| HourKills::AsVec(self.hours[index].clone());
+        Activity {
+            max: self.max,
+            sun: day(0),
+            mon: day(1),
+            tue: day(2),
+            wed: day(3),
+            thu: day(4),
+            fri: day(5),
+            sat: day(6),
+            days: self.days.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statistics::samples::{participant, Sample};
+    use chrono::NaiveDate;
+
+
+    fn participants() -> Vec<Participant> {
+        // 2019-10-06 is Sunday
+        let sunday = NaiveDate::from_ymd(2019, 10, 6).and_hms(18, 30, 0);
+        let monday = NaiveDate::from_ymd(2019, 10, 7).and_hms(2, 10, 0);
+        vec![
+            participant(1, true).at(sunday).character(10),
+            participant(1, false).at(sunday).character(1),
+            participant(2, true).at(sunday).character(11),
+            participant(2, false).at(sunday).character(1),
+            participant(3, true).at(monday).character(1),
+            participant(3, false).at(monday).character(12),
+        ]
+    }
+
+    #[test]
+    fn kills() {
+        let activity = WeekActivity::new(&Entity::Character(1), &participants(), &Side::Kills);
+        assert_eq!(2, activity.hours[0][18]);
+        assert_eq!(0, activity.hours[1][2]);
+        assert_eq!(2, activity.max);
+        assert_eq!(HourKills::AsVec(activity.hours[0].clone()), activity.to_activity().sun);
+    }
+
+    #[test]
+    fn losses() {
+        let activity = WeekActivity::new(&Entity::Character(1), &participants(), &Side::Losses);
+        assert_eq!(0, activity.hours[0][18]);
+        assert_eq!(1, activity.hours[1][2]);
+        assert_eq!(1, activity.max);
+    }
+
+    #[test]
+    fn area() {
+        let kills = WeekActivity::new(&Entity::System(30000142), &participants(), &Side::Kills);
+        assert_eq!(3, kills.hours.iter().flatten().sum::<i32>());
+        let losses = WeekActivity::new(&Entity::System(30000142), &participants(), &Side::Losses);
+        assert_eq!(0, losses.max);
+    }
+}
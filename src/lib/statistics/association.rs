@@ -0,0 +1,152 @@
+use crate::api::stats::Entity;
+use crate::models::{Integer, OptInteger};
+use crate::models::participant::Participant;
+use super::get_encounters;
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Length of the associates list
+pub const ASSOCIATES_LIMIT: usize = 20;
+
+/// Character, corporation or alliance of the participant in the terms of the entity
+pub fn get_key(entity: &Entity, participant: &Participant) -> OptInteger {
+    match entity {
+        Entity::Character(_) => participant.character_id,
+        Entity::Corporation(_) => participant.corporation_id,
+        Entity::Alliance(_) => participant.alliance_id,
+        _ => None
+    }
+}
+
+/// Killmails shared with the entity on the same or on the opposite side
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Association {
+    pub id: Integer,
+    pub together: u32,
+    pub opposite: u32,
+}
+impl Association {
+    fn new(id: Integer) -> Self {
+        Self { id, ..Self::default() }
+    }
+
+    /// Associates ranked by the shared killmails count, the closest is the first one
+    pub fn rank(entity: &Entity, id: &Integer, participants: &Vec<Participant>) -> Vec<Self> {
+        let mut associations = HashMap::new();
+        for encounter in get_encounters(participants).values() {
+            let attackers: HashSet<Integer> = encounter.attackers.iter().filter_map(|attacker| get_key(entity, attacker)).collect();
+            let victim = encounter.victim.and_then(|victim| get_key(entity, victim));
+            if attackers.contains(id) {
+                for other in attackers.iter().filter(|other| *other != id) {
+                    associations.entry(*other).or_insert(Self::new(*other)).together += 1;
+                }
+                if let Some(other) = victim.filter(|other| other != id) {
+                    associations.entry(other).or_insert(Self::new(other)).opposite += 1;
+                }
+            }
+            if victim == Some(*id) {
+                for other in attackers.iter().filter(|other| *other != id) {
+                    associations.entry(*other).or_insert(Self::new(*other)).opposite += 1;
+                }
+            }
+        }
+        let mut ranked: Vec<Self> = associations.into_values().collect();
+        ranked.sort_by(|a, b| (b.together + b.opposite).cmp(&(a.together + a.opposite)).then(a.id.cmp(&b.id)));
+        ranked.truncate(ASSOCIATES_LIMIT);
+        ranked
+    }
+}
+
+/// Killmails shared by the pair of entities, the lesser id is the first one
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Link {
+    pub from: Integer,
+    pub to: Integer,
+    pub together: u32,
+    pub opposite: u32,
+}
+
+fn get_pair(a: Integer, b: Integer) -> (Integer, Integer) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+/// Pairs of the given characters, corporations or alliances met on the same killmails
+pub fn get_links(entity: &Entity, participants: &Vec<Participant>, ids: &HashSet<Integer>) -> Vec<Link> {
+    let mut links: HashMap<(Integer, Integer), Link> = HashMap::new();
+    for encounter in get_encounters(participants).values() {
+        let attackers: HashSet<Integer> = encounter.attackers.iter()
+            .filter_map(|attacker| get_key(entity, attacker))
+            .filter(|id| ids.contains(id))
+            .collect();
+        let victim = encounter.victim.and_then(|victim| get_key(entity, victim)).filter(|id| ids.contains(id));
+        let mut pairs = Vec::new();
+        for a in &attackers {
+            for b in &attackers {
+                if a < b {
+                    pairs.push((get_pair(*a, *b), true));
+                }
+            }
+            if let Some(v) = victim {
+                if v != *a {
+                    pairs.push((get_pair(*a, v), false));
+                }
+            }
+        }
+        for ((from, to), together) in pairs {
+            let link = links.entry((from, to)).or_insert(Link { from, to, together: 0, opposite: 0 });
+            if together {
+                link.together += 1;
+            } else {
+                link.opposite += 1;
+            }
+        }
+    }
+    let mut links: Vec<Link> = links.into_values().collect();
+    links.sort_by_key(|link| (link.from, link.to));
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statistics::samples::{participant, Sample};
+
+
+    fn participants() -> Vec<Participant> {
+        vec![
+            participant(1, true).character(10).corporation(100),
+            participant(1, false).character(1).corporation(1000),
+            participant(1, false).character(2).corporation(1000),
+            participant(1, false).character(3).corporation(2000),
+            participant(2, true).character(11).corporation(100),
+            participant(2, false).character(1).corporation(1000),
+            participant(2, false).character(2).corporation(1000),
+            participant(3, true).character(2).corporation(1000),
+            participant(3, false).character(1).corporation(1000),
+        ]
+    }
+
+    #[test]
+    fn characters() {
+        let ranked = Association::rank(&Entity::Character(1), &1, &participants());
+        assert_eq!(Association { id: 2, together: 2, opposite: 1 }, ranked[0]);
+        assert_eq!(4, ranked.len());
+        assert_eq!(Some(&Association { id: 3, together: 1, opposite: 0 }), ranked.iter().find(|a| a.id == 3));
+    }
+
+    #[test]
+    fn corporations() {
+        let ranked = Association::rank(&Entity::Corporation(1000), &1000, &participants());
+        assert_eq!(vec![Association { id: 100, together: 0, opposite: 2 }, Association { id: 2000, together: 1, opposite: 0 }], ranked);
+    }
+
+    #[test]
+    fn links() {
+        let ids: HashSet<Integer> = vec![1, 2, 3].into_iter().collect();
+        let links = get_links(&Entity::Character(1), &participants(), &ids);
+        assert_eq!(Some(&Link { from: 2, to: 3, together: 1, opposite: 0 }), links.iter().find(|l| l.from == 2 && l.to == 3));
+        assert_eq!(Some(&Link { from: 1, to: 2, together: 2, opposite: 1 }), links.iter().find(|l| l.from == 1 && l.to == 2));
+        assert_eq!(3, links.len());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::api::stats::Entity;
+use crate::models::{Integer, DateTime};
+use crate::models::participant::Participant;
+use super::get_encounters;
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Smaller groups are gangs rather than fleets
+pub const MIN_FLEET: u32 = 5;
+/// Jaccard similarity of the hull sets to join the fleet to the doctrine
+pub const SIMILARITY: f32 = 0.5;
+/// Doctrine seen once is just a random composition
+pub const MIN_SEEN: u32 = 2;
+
+/// Hulls fielded by the entity on the single killmail
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fleet {
+    pub time: DateTime,
+    pub hulls: HashMap<Integer, u32>,
+}
+impl Fleet {
+    pub fn size(&self) -> u32 {
+        self.hulls.values().sum()
+    }
+
+    fn get_types(&self) -> HashSet<Integer> {
+        self.hulls.keys().cloned().collect()
+    }
+}
+
+/// Fleets of the entity with at least MIN_FLEET members, the oldest is the first one
+pub fn get_fleets(entity: &Entity, participants: &Vec<Participant>) -> Vec<Fleet> {
+    let mut fleets = Vec::new();
+    for encounter in get_encounters(participants).values() {
+        if !encounter.is_kill(entity) {
+            continue;
+        }
+        let mut fleet = Fleet { time: encounter.attackers[0].killmail_time, hulls: HashMap::new() };
+        for member in encounter.get_members(entity) {
+            if let Some(ship_id) = member.ship_id {
+                *fleet.hulls.entry(ship_id).or_insert(0) += 1;
+            }
+        }
+        if fleet.size() >= MIN_FLEET {
+            fleets.push(fleet);
+        }
+    }
+    fleets.sort_by_key(|fleet| fleet.time);
+    fleets
+}
+
+pub fn get_similarity(a: &HashSet<Integer>, b: &HashSet<Integer>) -> f32 {
+    let union = a.union(b).count();
+    if union > 0 { a.intersection(b).count() as f32 / union as f32 } else { 0.0 }
+}
+
+/// Recurring fleet composition
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Doctrine {
+    /// Typical count by hull, the most numerous is the first one
+    pub hulls: Vec<(Integer, u32)>,
+    pub seen: u32,
+    pub first_seen: Option<DateTime>,
+    pub last_seen: Option<DateTime>,
+}
+
+#[derive(Default)]
+struct Cluster {
+    fleets: Vec<Fleet>,
+}
+impl Cluster {
+    /// Hulls present at least in the half of the fleets
+    fn get_core(&self) -> HashSet<Integer> {
+        let mut counts = HashMap::new();
+        for fleet in &self.fleets {
+            for hull in fleet.hulls.keys() {
+                *counts.entry(*hull).or_insert(0) += 1;
+            }
+        }
+        counts.into_iter().filter(|(_, count)| 2 * count >= self.fleets.len()).map(|(hull, _)| hull).collect()
+    }
+
+    fn get_doctrine(&self) -> Doctrine {
+        let seen = self.fleets.len() as u32;
+        let mut hulls: Vec<(Integer, u32)> = self.get_core().into_iter()
+            .map(|hull| {
+                let total: u32 = self.fleets.iter().map(|fleet| fleet.hulls.get(&hull).cloned().unwrap_or_default()).sum();
+                (hull, ((total as f32) / (seen as f32)).round().max(1.0) as u32)
+            })
+            .collect();
+        hulls.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        Doctrine {
+            hulls,
+            seen,
+            first_seen: self.fleets.iter().map(|fleet| fleet.time).min(),
+            last_seen: self.fleets.iter().map(|fleet| fleet.time).max(),
+        }
+    }
+}
+
+impl Doctrine {
+    /// Greedy clustering of the fleets by the similarity to the doctrine core hulls,
+    /// the most often seen doctrine is the first one
+    pub fn detect(fleets: Vec<Fleet>) -> Vec<Self> {
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for fleet in fleets {
+            let types = fleet.get_types();
+            let best = clusters.iter_mut()
+                .map(|cluster| (get_similarity(&types, &cluster.get_core()), cluster))
+                .filter(|(similarity, _)| *similarity >= SIMILARITY)
+                .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            match best {
+                Some((_, cluster)) => cluster.fleets.push(fleet),
+                None => clusters.push(Cluster { fleets: vec![fleet] }),
+            }
+        }
+        let mut doctrines: Vec<Self> = clusters.iter()
+            .map(|cluster| cluster.get_doctrine())
+            .filter(|doctrine| doctrine.seen >= MIN_SEEN)
+            .collect();
+        doctrines.sort_by(|a, b| b.seen.cmp(&a.seen).then(b.last_seen.cmp(&a.last_seen)));
+        doctrines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statistics::samples::{participant, Sample};
+    use chrono::NaiveDate;
+
+    const FEROX: Integer = 16227;
+    const EAGLE: Integer = 12011;
+    const BASILISK: Integer = 11985;
+    const RIFTER: Integer = 587;
+
+    fn fleet(day: u32, hulls: Vec<(Integer, u32)>) -> Fleet {
+        Fleet {
+            time: NaiveDate::from_ymd(2019, 10, day).and_hms(12, 0, 0),
+            hulls: hulls.into_iter().collect(),
+        }
+    }
+
+
+    #[test]
+    fn fleets() {
+        let mut participants = vec![participant(1, true).alliance(2).ship(RIFTER), participant(2, true).alliance(2).ship(RIFTER)];
+        for _ in 0..4 {
+            participants.push(participant(1, false).alliance(1).ship(FEROX));
+            participants.push(participant(2, false).alliance(1).ship(FEROX));
+        }
+        participants.push(participant(1, false).alliance(1).ship(BASILISK));
+        participants.push(participant(2, false).alliance(3).ship(BASILISK));
+        let fleets = get_fleets(&Entity::Alliance(1), &participants);
+        assert_eq!(1, fleets.len());
+        assert_eq!(Some(&4), fleets[0].hulls.get(&FEROX));
+        assert_eq!(5, fleets[0].size());
+    }
+
+    #[test]
+    fn doctrines() {
+        let fleets = vec![
+            fleet(1, vec![(FEROX, 10), (BASILISK, 2)]),
+            fleet(2, vec![(FEROX, 14), (BASILISK, 2), (RIFTER, 1)]),
+            fleet(3, vec![(RIFTER, 6)]),
+            fleet(8, vec![(EAGLE, 12), (BASILISK, 3)]),
+            fleet(9, vec![(EAGLE, 10), (BASILISK, 3)]),
+            fleet(10, vec![(EAGLE, 11), (BASILISK, 2)]),
+        ];
+        let doctrines = Doctrine::detect(fleets);
+        assert_eq!(2, doctrines.len());
+        assert_eq!(vec![(EAGLE, 11), (BASILISK, 3)], doctrines[0].hulls);
+        assert_eq!(3, doctrines[0].seen);
+        assert_eq!(Some(NaiveDate::from_ymd(2019, 10, 10).and_hms(12, 0, 0)), doctrines[0].last_seen);
+        assert_eq!(2, doctrines[1].seen);
+        assert_eq!((FEROX, 12), doctrines[1].hulls[0]);
+    }
+
+    #[test]
+    fn similarity() {
+        let a: HashSet<Integer> = vec![FEROX, BASILISK].into_iter().collect();
+        let b: HashSet<Integer> = vec![FEROX, BASILISK, RIFTER].into_iter().collect();
+        assert!((get_similarity(&a, &b) - 0.666).abs() < 0.01);
+        assert_eq!(0.0, get_similarity(&HashSet::new(), &HashSet::new()));
+    }
+}
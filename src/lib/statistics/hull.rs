@@ -0,0 +1,123 @@
+use crate::api::stats::Entity;
+use crate::models::Integer;
+use crate::models::participant::Participant;
+use super::get_encounters;
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Kills and losses of the entity flying the single hull
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct HullStat {
+    pub ship_id: Integer,
+    pub kills: u32,
+    pub losses: u32,
+    /// The hulls with the items by the prices at the kill date
+    pub isk_destroyed: u64,
+    pub isk_lost: u64,
+    pub solo_kills: u32,
+    /// Attackers on all kills, to get the average fleet size
+    pub attackers: u32,
+    pub killmails: Vec<Integer>,
+}
+impl HullStat {
+    fn new(ship_id: Integer) -> Self {
+        Self { ship_id, ..Self::default() }
+    }
+
+    /// Share of the destroyed ISK in the whole ISK
+    pub fn efficiency(&self) -> u32 {
+        (100 * self.isk_destroyed).checked_div(self.isk_destroyed + self.isk_lost).unwrap_or_default() as u32
+    }
+
+    pub fn average_fleet(&self) -> f32 {
+        if self.kills > 0 { self.attackers as f32 / self.kills as f32 } else { 0.0 }
+    }
+
+    pub fn solo_share(&self) -> u32 {
+        (100 * self.solo_kills).checked_div(self.kills).unwrap_or_default()
+    }
+
+    /// Hulls flown by the entity, the most used is the first one.
+    /// The kill is counted once for the each hull the members flew there.
+    /// Value is the valuation of the killmail by its id.
+    pub fn collect<V>(entity: &Entity, participants: &Vec<Participant>, value: &V) -> Vec<Self>
+        where V: Fn(&Integer) -> u64
+    {
+        let mut hulls: HashMap<Integer, Self> = HashMap::new();
+        for (killmail_id, encounter) in get_encounters(participants) {
+            let victim = match encounter.victim {
+                Some(victim) => victim,
+                None => continue,
+            };
+            let value = value(&killmail_id);
+            if encounter.is_loss(entity) {
+                if let Some(ship_id) = victim.ship_id {
+                    let hull = hulls.entry(ship_id).or_insert(Self::new(ship_id));
+                    hull.losses += 1;
+                    hull.isk_lost += value;
+                    hull.killmails.push(killmail_id);
+                }
+            } else if encounter.is_kill(entity) {
+                let ships: HashSet<Integer> = encounter.get_members(entity).iter().filter_map(|member| member.ship_id).collect();
+                for ship_id in ships {
+                    let hull = hulls.entry(ship_id).or_insert(Self::new(ship_id));
+                    hull.kills += 1;
+                    hull.isk_destroyed += value;
+                    hull.attackers += encounter.attackers.len() as u32;
+                    if encounter.is_solo() {
+                        hull.solo_kills += 1;
+                    }
+                    hull.killmails.push(killmail_id);
+                }
+            }
+        }
+        let mut hulls: Vec<Self> = hulls.into_values().collect();
+        hulls.sort_by(|a, b| (b.kills + b.losses).cmp(&(a.kills + a.losses)).then(a.ship_id.cmp(&b.ship_id)));
+        hulls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statistics::samples::{participant, Sample};
+
+    const RIFTER: Integer = 587;
+    const FEROX: Integer = 16227;
+
+
+    /// The Ferox is lost on the first killmail
+    fn value(killmail_id: &Integer) -> u64 {
+        if *killmail_id == 1 { 300 } else { 100 }
+    }
+
+    #[test]
+    fn hulls() {
+        let participants = vec![
+            participant(1, true).corporation(2).ship(FEROX),
+            participant(1, false).corporation(1).ship(RIFTER),
+            participant(2, true).corporation(2).ship(RIFTER),
+            participant(2, false).corporation(1).ship(RIFTER),
+            participant(2, false).corporation(1).ship(RIFTER),
+            participant(2, false).corporation(1).ship(FEROX),
+            participant(3, true).corporation(1).ship(RIFTER),
+            participant(3, false).corporation(2).ship(FEROX),
+        ];
+        let hulls = HullStat::collect(&Entity::Corporation(1), &participants, &value);
+        assert_eq!(2, hulls.len());
+        let rifter = &hulls[0];
+        assert_eq!(RIFTER, rifter.ship_id);
+        assert_eq!(2, rifter.kills);
+        assert_eq!(1, rifter.losses);
+        assert_eq!(400, rifter.isk_destroyed);
+        assert_eq!(100, rifter.isk_lost);
+        assert_eq!(80, rifter.efficiency());
+        assert_eq!(2.0, rifter.average_fleet());
+        assert_eq!(50, rifter.solo_share());
+        assert_eq!(vec![1, 2, 3], rifter.killmails);
+        assert_eq!(FEROX, hulls[1].ship_id);
+        assert_eq!(1, hulls[1].kills);
+        assert_eq!(100, hulls[1].efficiency());
+    }
+}
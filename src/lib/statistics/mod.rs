@@ -0,0 +1,277 @@
+use crate::api::stats::Entity;
+use crate::models::Integer;
+use crate::models::participant::Participant;
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+pub mod activity;
+pub mod association;
+pub mod doctrine;
+pub mod hull;
+
+pub use activity::{Side, WeekActivity};
+pub use association::{Association, Link};
+pub use doctrine::{Fleet, Doctrine};
+pub use hull::HullStat;
+
+/// Length of the each top list
+pub const TOP_LIMIT: usize = 10;
+
+/// Killmails count by the ship, system or corporation, the most active is the first one
+pub type Top = Vec<(Integer, u32)>;
+
+/// Victim and attackers of the single killmail
+pub struct Encounter<'a> {
+    pub victim: Option<&'a Participant>,
+    pub attackers: Vec<&'a Participant>,
+}
+impl<'a> Encounter<'a> {
+    pub fn is_solo(&self) -> bool {
+        1 == self.attackers.len()
+    }
+
+    /// Areas never lose ships
+    pub fn is_loss(&self, entity: &Entity) -> bool {
+        !is_area(entity) && self.victim.map(|victim| is_member(entity, victim)).unwrap_or(false)
+    }
+
+    pub fn get_members(&self, entity: &Entity) -> Vec<&'a Participant> {
+        self.attackers.iter().cloned().filter(|attacker| is_member(entity, attacker)).collect()
+    }
+
+    pub fn is_kill(&self, entity: &Entity) -> bool {
+        self.victim.is_some() && !self.is_loss(entity) && !self.get_members(entity).is_empty()
+    }
+}
+
+/// Participants grouped by killmail, ordered by killmail id
+pub fn get_encounters(participants: &Vec<Participant>) -> BTreeMap<Integer, Encounter<'_>> {
+    let mut encounters = BTreeMap::new();
+    for participant in participants {
+        let encounter = encounters.entry(participant.killmail_id).or_insert(Encounter { victim: None, attackers: Vec::new() });
+        if participant.is_victim {
+            encounter.victim = Some(participant);
+        } else {
+            encounter.attackers.push(participant);
+        }
+    }
+    encounters
+}
+
+/// Areas have no losses, every participant there is a member
+pub fn is_member(entity: &Entity, participant: &Participant) -> bool {
+    match entity {
+        Entity::Character(id) => participant.character_id == Some(*id),
+        Entity::Corporation(id) => participant.corporation_id == Some(*id),
+        Entity::Alliance(id) => participant.alliance_id == Some(*id),
+        Entity::Faction(id) => participant.faction_id == Some(*id),
+        Entity::Ship(id) => participant.ship_id == Some(*id),
+        Entity::Group(_) => false,
+        Entity::System(_) | Entity::Constellation(_) | Entity::Region(_) => true,
+    }
+}
+
+pub fn is_area(entity: &Entity) -> bool {
+    matches!(entity, Entity::System(_) | Entity::Constellation(_) | Entity::Region(_))
+}
+
+fn get_top(counts: HashMap<Integer, u32>) -> Top {
+    let mut top: Top = counts.into_iter().collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    top.truncate(TOP_LIMIT);
+    top
+}
+
+fn count<I: Iterator<Item=Option<Integer>>>(counts: &mut HashMap<Integer, u32>, ids: I) {
+    let unique: HashSet<Integer> = ids.flatten().collect();
+    for id in unique {
+        *counts.entry(id).or_insert(0) += 1;
+    }
+}
+
+/// The same figures as the zKillboard stats computed from the saved killmails
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct LocalStats {
+    pub ships_destroyed: u32,
+    pub ships_lost: u32,
+    /// The hulls with the items by the prices at the kill date
+    pub isk_destroyed: u64,
+    pub isk_lost: u64,
+    pub solo_kills: u32,
+    pub solo_losses: u32,
+    pub top_ships: Top,
+    pub top_systems: Top,
+    pub top_corporations: Top,
+}
+impl LocalStats {
+    /// Value is the valuation of the killmail by its id
+    pub fn new<V>(entity: &Entity, participants: &Vec<Participant>, value: &V) -> Self
+        where V: Fn(&Integer) -> u64
+    {
+        let mut stats = Self::default();
+        let mut ships = HashMap::new();
+        let mut systems = HashMap::new();
+        let mut corporations = HashMap::new();
+        for encounter in get_encounters(participants).values() {
+            let victim = match encounter.victim {
+                Some(victim) => victim,
+                None => continue,
+            };
+            let value = value(&victim.killmail_id);
+            if encounter.is_loss(entity) {
+                stats.ships_lost += 1;
+                stats.isk_lost += value;
+                if encounter.is_solo() {
+                    stats.solo_losses += 1;
+                }
+            } else if encounter.is_kill(entity) {
+                let members = encounter.get_members(entity);
+                stats.ships_destroyed += 1;
+                stats.isk_destroyed += value;
+                if encounter.is_solo() {
+                    stats.solo_kills += 1;
+                }
+                count(&mut ships, members.iter().map(|attacker| attacker.ship_id));
+                count(&mut corporations, members.iter().map(|attacker| attacker.corporation_id));
+                count(&mut systems, std::iter::once(Some(victim.system_id)));
+            }
+        }
+        stats.top_ships = get_top(ships);
+        stats.top_systems = get_top(systems);
+        stats.top_corporations = get_top(corporations);
+        stats
+    }
+
+    /// Share of the destroyed ISK in the whole ISK, 100 is the most dangerous
+    pub fn danger_ratio(&self) -> u32 {
+        (100 * self.isk_destroyed).checked_div(self.isk_destroyed + self.isk_lost).unwrap_or_default() as u32
+    }
+
+    /// Share of the kills done by gangs instead of the solo pilots
+    pub fn gang_ratio(&self) -> u32 {
+        (100 * self.solo_kills).checked_div(self.ships_destroyed).map(|solo| 100 - solo).unwrap_or_default()
+    }
+}
+
+/// Participants for the tests of the statistics
+#[cfg(test)]
+pub mod samples {
+    use crate::models::{Integer, DateTime};
+    use crate::models::participant::Participant;
+    use chrono::NaiveDate;
+
+    /// Rifter in Jita without the actors, the tests add the ones they count
+    pub fn participant(killmail_id: Integer, is_victim: bool) -> Participant {
+        Participant {
+            killmail_id,
+            killmail_time: NaiveDate::from_ymd(2019, 10, 1).and_hms(12, 0, 0),
+            system_id: 30000142,
+            is_victim,
+            ship_id: Some(587),
+            character_id: None,
+            corporation_id: None,
+            alliance_id: None,
+            faction_id: None,
+        }
+    }
+
+    pub trait Sample {
+        fn at(self, time: DateTime) -> Self;
+        fn system(self, id: Integer) -> Self;
+        fn ship(self, id: Integer) -> Self;
+        fn character(self, id: Integer) -> Self;
+        fn corporation(self, id: Integer) -> Self;
+        fn alliance(self, id: Integer) -> Self;
+    }
+    impl Sample for Participant {
+        fn at(self, time: DateTime) -> Self {
+            Self { killmail_time: time, ..self }
+        }
+        fn system(self, id: Integer) -> Self {
+            Self { system_id: id, ..self }
+        }
+        fn ship(self, id: Integer) -> Self {
+            Self { ship_id: Some(id), ..self }
+        }
+        fn character(self, id: Integer) -> Self {
+            Self { character_id: Some(id), ..self }
+        }
+        fn corporation(self, id: Integer) -> Self {
+            Self { corporation_id: Some(id), ..self }
+        }
+        fn alliance(self, id: Integer) -> Self {
+            Self { alliance_id: Some(id), ..self }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::samples::{participant, Sample};
+
+
+    fn participants() -> Vec<Participant> {
+        vec![
+            // solo kill by the character 1
+            participant(1, true).system(30000142).character(10).corporation(100).ship(587),
+            participant(1, false).system(30000142).character(1).corporation(1000).ship(24690),
+            // gang kill by the characters 1 and 2
+            participant(2, true).system(30000144).character(11).corporation(100).ship(670),
+            participant(2, false).system(30000144).character(1).corporation(1000).ship(24690),
+            participant(2, false).system(30000144).character(2).corporation(1000).ship(587),
+            // loss of the character 1
+            participant(3, true).system(30000142).character(1).corporation(1000).ship(24690),
+            participant(3, false).system(30000142).character(12).corporation(100).ship(587),
+            participant(3, false).system(30000142).character(13).corporation(100).ship(587),
+            // not related
+            participant(4, true).system(30000142).character(14).corporation(200).ship(587),
+            participant(4, false).system(30000142).character(15).corporation(300).ship(587),
+        ]
+    }
+
+    fn value(killmail_id: &Integer) -> u64 {
+        match killmail_id {
+            1 | 4 => 100,
+            3 => 300,
+            _ => 0
+        }
+    }
+
+    #[test]
+    fn character() {
+        let stats = LocalStats::new(&Entity::Character(1), &participants(), &value);
+        assert_eq!(2, stats.ships_destroyed);
+        assert_eq!(1, stats.ships_lost);
+        assert_eq!(100, stats.isk_destroyed);
+        assert_eq!(300, stats.isk_lost);
+        assert_eq!(1, stats.solo_kills);
+        assert_eq!(0, stats.solo_losses);
+        assert_eq!(25, stats.danger_ratio());
+        assert_eq!(50, stats.gang_ratio());
+        assert_eq!(vec![(24690, 2)], stats.top_ships);
+        assert_eq!(vec![(30000142, 1), (30000144, 1)], stats.top_systems);
+        assert_eq!(vec![(1000, 2)], stats.top_corporations);
+    }
+
+    #[test]
+    fn area() {
+        let participants = participants().into_iter().filter(|p| p.system_id == 30000142).collect();
+        let stats = LocalStats::new(&Entity::System(30000142), &participants, &value);
+        assert_eq!(3, stats.ships_destroyed);
+        assert_eq!(0, stats.ships_lost);
+        assert_eq!(500, stats.isk_destroyed);
+        assert_eq!(100, stats.danger_ratio());
+        assert_eq!(34, stats.gang_ratio());
+        assert_eq!(vec![(587, 2), (24690, 1)], stats.top_ships);
+    }
+
+    #[test]
+    fn empty() {
+        let stats = LocalStats::new(&Entity::Corporation(1000), &Vec::new(), &value);
+        assert_eq!(LocalStats::default(), stats);
+        assert_eq!(0, stats.danger_ratio());
+        assert_eq!(0, stats.gang_ratio());
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+/// The item name with the quantity as it was pasted
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    pub name: String,
+    pub quantity: i64,
+}
+impl Entry {
+    fn new<S: Into<String>>(name: S, quantity: i64) -> Self {
+        Self { name: name.into(), quantity }
+    }
+}
+
+/// The entry with the resolved type and the unit price
+#[derive(Debug, PartialEq, Clone)]
+pub struct Appraised {
+    pub name: String,
+    pub quantity: i64,
+    pub type_id: Option<i32>,
+    pub price: Option<f32>,
+}
+impl Appraised {
+    pub fn get_value(&self) -> u64 {
+        (self.quantity as f64 * self.price.unwrap_or_default().max(0.0) as f64) as u64
+    }
+}
+
+/// Names are matched case insensitive
+pub fn get_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// 1,000 or 1 000 or 1.000 depending on the client locale
+fn parse_quantity(text: &str) -> Option<i64> {
+    let digits: String = text.trim().chars().filter(|c| !",. '\u{a0}".contains(*c)).collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn split_last(line: &str) -> Option<(&str, &str)> {
+    line.rfind(' ').map(|index| (&line[..index], &line[index + 1..]))
+}
+
+/// x5 or x 5 at the end of the EFT drone and cargo lines
+fn split_suffix(line: &str) -> Option<(&str, i64)> {
+    let (name, last) = split_last(line)?;
+    if last.len() > 1 && last.starts_with('x') {
+        if let Some(quantity) = parse_quantity(&last[1..]) {
+            return Some((name, quantity));
+        }
+    }
+    let (name, x) = split_last(name)?;
+    if x == "x" {
+        return parse_quantity(last).map(|quantity| (name, quantity));
+    }
+    None
+}
+
+/// 5 Name or 5 x Name of the cargo scan
+fn split_prefix(line: &str) -> Option<(&str, i64)> {
+    let mut parts = line.splitn(2, ' ');
+    let quantity = parse_quantity(parts.next()?)?;
+    let name = parts.next()?.trim();
+    let name = name.strip_prefix("x ").map(str::trim).unwrap_or(name);
+    if name.is_empty() { None } else { Some((name, quantity)) }
+}
+
+fn clean(name: &str) -> &str {
+    name.trim().trim_end_matches("/OFFLINE").trim().trim_end_matches('*').trim()
+}
+
+fn parse_line(line: &str) -> Vec<Entry> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Vec::new();
+    }
+    // EFT header [Ship, Fit name] or the empty slot
+    if line.starts_with('[') && line.ends_with(']') {
+        let inner = &line[1..line.len() - 1];
+        if inner.starts_with("Empty ") {
+            return Vec::new();
+        }
+        let ship = clean(inner.split(',').next().unwrap_or_default());
+        return if ship.is_empty() { Vec::new() } else { vec![Entry::new(ship, 1)] };
+    }
+    // Inventory and contract contents: name, quantity, group...
+    if line.contains('\t') {
+        let mut columns = line.split('\t');
+        let name = clean(columns.next().unwrap_or_default());
+        let quantity = columns.next().and_then(parse_quantity).unwrap_or(1);
+        return if name.is_empty() { Vec::new() } else { vec![Entry::new(name, quantity)] };
+    }
+    if let Some((name, quantity)) = split_suffix(line) {
+        return vec![Entry::new(clean(name), quantity)];
+    }
+    if let Some((name, quantity)) = split_prefix(line) {
+        return vec![Entry::new(clean(name), quantity)];
+    }
+    // EFT module with the loaded charge, a single charge is counted
+    line.split(',').map(clean).filter(|name| !name.is_empty()).map(|name| Entry::new(name, 1)).collect()
+}
+
+/// Parses the cargo scan, the contract contents, the EFT fit or the inventory list,
+/// the same names are summed up in the order of the first occurrence
+pub fn parse(text: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for entry in text.lines().flat_map(parse_line) {
+        match index.get(&get_key(&entry.name)) {
+            Some(position) => entries[*position].quantity += entry.quantity,
+            None => {
+                index.insert(get_key(&entry.name), entries.len());
+                entries.push(entry);
+            }
+        }
+    }
+    entries
+}
+
+/// Resolves the entries by the type ids keyed by get_key and prices them
+pub fn appraise<P>(entries: &[Entry], ids: &HashMap<String, i32>, price: &P) -> Vec<Appraised>
+    where P: Fn(&i32) -> Option<f32>
+{
+    entries.iter().map(|entry| {
+        let type_id = ids.get(&get_key(&entry.name)).cloned();
+        Appraised {
+            name: entry.name.clone(),
+            quantity: entry.quantity,
+            type_id,
+            price: type_id.and_then(|id| price(&id)),
+        }
+    }).collect()
+}
+
+pub fn total(items: &[Appraised]) -> u64 {
+    items.iter().map(Appraised::get_value).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(list: &[(&str, i64)]) -> Vec<Entry> {
+        list.iter().map(|(name, quantity)| Entry::new(*name, *quantity)).collect()
+    }
+
+    #[test]
+    fn inventory() {
+        let text = "Tritanium\t12,500\tMineral\t\t125 m3\t62,500.00 ISK\nRifter\t\tFrigate\t\t27,289 m3\nPyerite\t1 000\tMineral";
+        assert_eq!(entries(&[("Tritanium", 12500), ("Rifter", 1), ("Pyerite", 1000)]), parse(text));
+    }
+
+    #[test]
+    fn cargo_scan() {
+        let text = "3 Hobgoblin I\n100MN Afterburner II\n1 x Nanite Repair Paste\n2 Hobgoblin I\n";
+        assert_eq!(entries(&[("Hobgoblin I", 5), ("100MN Afterburner II", 1), ("Nanite Repair Paste", 1)]), parse(text));
+    }
+
+    #[test]
+    fn eft() {
+        let text = "[Rifter, Tackle]\n\
+            Damage Control I\n\
+            [Empty Low slot]\n\
+            \n\
+            200mm AutoCannon I, EMP S\n\
+            200mm AutoCannon I, EMP S\n\
+            Warp Disruptor I /OFFLINE\n\
+            \n\
+            Warrior I x2\n\
+            EMP S x 500\n";
+        assert_eq!(entries(&[
+            ("Rifter", 1),
+            ("Damage Control I", 1),
+            ("200mm AutoCannon I", 2),
+            ("EMP S", 502),
+            ("Warp Disruptor I", 1),
+            ("Warrior I", 2),
+        ]), parse(text));
+    }
+
+    #[test]
+    fn values() {
+        let mut ids = HashMap::new();
+        ids.insert(get_key("Tritanium"), 34);
+        ids.insert(get_key("Rifter"), 587);
+        let items = appraise(&entries(&[("tritanium", 1000), ("Rifter", 2), ("Unknown", 5)]), &ids, &|id| match id {
+            34 => Some(4.5),
+            587 => Some(500000.0),
+            _ => None
+        });
+        assert_eq!(Some(34), items[0].type_id);
+        assert_eq!(4500, items[0].get_value());
+        assert_eq!((None, None), (items[2].type_id, items[2].price));
+        assert_eq!(1004500, total(&items));
+    }
+}
@@ -0,0 +1,398 @@
+use crate::api;
+use crate::models;
+use crate::models::Date;
+use crate::models::price::Price;
+use crate::provider;
+
+use std::collections::HashMap;
+
+pub mod appraisal;
+
+/// Trade hub station with its system and region
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Hub {
+    pub name: &'static str,
+    pub location_id: i64,
+    pub system_id: i32,
+    pub region_id: i32,
+}
+
+pub const JITA: Hub = Hub { name: "Jita 4-4", location_id: 60003760, system_id: 30000142, region_id: 10000002 };
+pub const AMARR: Hub = Hub { name: "Amarr VIII", location_id: 60008494, system_id: 30002187, region_id: 10000043 };
+pub const DODIXIE: Hub = Hub { name: "Dodixie IX", location_id: 60011866, system_id: 30002659, region_id: 10000032 };
+pub const RENS: Hub = Hub { name: "Rens VI", location_id: 60004588, system_id: 30002510, region_id: 10000030 };
+pub const HEK: Hub = Hub { name: "Hek VIII", location_id: 60005686, system_id: 30002053, region_id: 10000042 };
+
+/// The hub whose best orders go to the daily price snapshots
+pub const REFERENCE_HUB: Hub = JITA;
+/// The snapshots further from the kill date are not used
+pub const SNAPSHOT_DAYS: i32 = 30;
+
+pub const HUBS: [(&str, Hub); 5] = [("jita", JITA), ("amarr", AMARR), ("dodixie", DODIXIE), ("rens", RENS), ("hek", HEK)];
+
+/// Where the item price comes from
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Source {
+    /// ESI global average price
+    #[default]
+    Average,
+    /// ESI adjusted price, the one used for the industry and the insurance
+    Adjusted,
+    /// The highest buy order at the hub station
+    Buy(Hub),
+    /// The lowest sell order at the hub station
+    Sell(Hub),
+}
+impl Source {
+    /// average, adjusted or the hub with the side: jita-sell, amarr-buy...
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "average" => Some(Source::Average),
+            "adjusted" => Some(Source::Adjusted),
+            _ => {
+                let mut parts = name.splitn(2, '-');
+                let hub = parts.next().and_then(|hub| HUBS.iter().find(|(key, _)| *key == hub).map(|(_, hub)| *hub))?;
+                match parts.next() {
+                    Some("buy") => Some(Source::Buy(hub)),
+                    Some("sell") => Some(Source::Sell(hub)),
+                    _ => None
+                }
+            }
+        }
+    }
+
+    pub fn get_key(&self) -> String {
+        let hub_key = |hub: &Hub| HUBS.iter().find(|(_, item)| item == hub).map(|(key, _)| *key).unwrap_or_default();
+        match self {
+            Source::Average => String::from("average"),
+            Source::Adjusted => String::from("adjusted"),
+            Source::Buy(hub) => format!("{}-buy", hub_key(hub)),
+            Source::Sell(hub) => format!("{}-sell", hub_key(hub)),
+        }
+    }
+
+    pub fn get_title(&self) -> String {
+        match self {
+            Source::Average => String::from("ESI average price"),
+            Source::Adjusted => String::from("ESI adjusted price"),
+            Source::Buy(hub) => format!("{} best buy", hub.name),
+            Source::Sell(hub) => format!("{} best sell", hub.name),
+        }
+    }
+
+    /// All the sources, the hubs go in the HUBS order
+    pub fn get_all() -> Vec<Self> {
+        let mut sources = vec![Source::Average, Source::Adjusted];
+        for (_, hub) in HUBS.iter() {
+            sources.push(Source::Buy(*hub));
+            sources.push(Source::Sell(*hub));
+        }
+        sources
+    }
+
+    pub fn get_price(&self, type_id: &i32) -> Option<f32> {
+        match self {
+            Source::Average => provider::get_avg_price(&Some(*type_id)),
+            Source::Adjusted => provider::get_adj_price(&Some(*type_id)),
+            Source::Buy(hub) => provider::get_hub_price(type_id, &hub.region_id, &hub.location_id, true),
+            Source::Sell(hub) => provider::get_hub_price(type_id, &hub.region_id, &hub.location_id, false),
+        }
+    }
+
+    /// Loads the hub prices of the types at once, the ESI prices are in memory already
+    pub fn preload(&self, ids: &[i32]) {
+        match self {
+            Source::Average | Source::Adjusted => {},
+            Source::Buy(hub) => provider::load_hub_prices(ids, &hub.region_id, &hub.location_id, true),
+            Source::Sell(hub) => provider::load_hub_prices(ids, &hub.region_id, &hub.location_id, false),
+        }
+    }
+
+    /// The price stored in the snapshot, the other hubs are not stored
+    pub fn get_snapshot(&self, price: &Price) -> Option<f32> {
+        match self {
+            Source::Average => price.average,
+            Source::Adjusted => price.adjusted,
+            Source::Buy(hub) if *hub == REFERENCE_HUB => price.buy,
+            Source::Sell(hub) if *hub == REFERENCE_HUB => price.sell,
+            _ => None
+        }
+    }
+}
+
+/// The best price of the orders placed at the location: the highest buy or the lowest sell
+pub fn best_price(orders: &[api::Order], location_id: &i64, is_buy: bool) -> Option<f32> {
+    let prices = orders.iter()
+        .filter(|order| order.location_id == *location_id && order.is_buy_order == is_buy)
+        .map(|order| order.price);
+    if is_buy {
+        prices.fold(None, |best, price| Some(best.map_or(price, |best: f32| best.max(price))))
+    } else {
+        prices.fold(None, |best, price| Some(best.map_or(price, |best: f32| best.min(price))))
+    }
+}
+
+/// The best buy and sell prices of the each type traded at the location
+pub fn best_prices(orders: &[api::Order], location_id: &i64) -> HashMap<i32, (Option<f32>, Option<f32>)> {
+    let mut result: HashMap<i32, (Option<f32>, Option<f32>)> = HashMap::new();
+    for order in orders.iter().filter(|order| order.location_id == *location_id) {
+        let entry = result.entry(order.type_id).or_insert((None, None));
+        if order.is_buy_order {
+            entry.0 = Some(entry.0.map_or(order.price, |best| best.max(order.price)));
+        } else {
+            entry.1 = Some(entry.1.map_or(order.price, |best| best.min(order.price)));
+        }
+    }
+    result
+}
+
+/// The daily snapshot from the ESI prices and the best orders at the reference hub
+pub fn get_snapshot(date: &Date, prices: &api::price::Prices, orders: &[api::Order]) -> Vec<Price> {
+    let mut hub = best_prices(orders, &REFERENCE_HUB.location_id);
+    let mut snapshot: Vec<Price> = prices.items.iter()
+        .map(|(id, price)| {
+            let (buy, sell) = hub.remove(id).unwrap_or((None, None));
+            Price { type_id: *id, price_date: *date, average: price.average, adjusted: price.adjusted, buy, sell }
+        })
+        .collect();
+    for (id, (buy, sell)) in hub {
+        snapshot.push(Price { type_id: id, price_date: *date, average: None, adjusted: None, buy, sell });
+    }
+    snapshot.sort_by_key(|price| price.type_id);
+    snapshot
+}
+
+/// The snapshot of the each type nearest to the date, the earlier one wins the tie
+pub fn nearest(prices: &[Price], date: &Date) -> HashMap<i32, Price> {
+    let mut result: HashMap<i32, Price> = HashMap::new();
+    for price in prices {
+        let distance = (price.price_date - *date).num_days().abs();
+        if distance > SNAPSHOT_DAYS as i64 {
+            continue;
+        }
+        let closer = result.get(&price.type_id)
+            .map(|best| {
+                let best_distance = (best.price_date - *date).num_days().abs();
+                distance < best_distance || (distance == best_distance && price.price_date < best.price_date)
+            })
+            .unwrap_or(true);
+        if closer {
+            result.insert(price.type_id, price.clone());
+        }
+    }
+    result
+}
+
+/// The item type with the quantities
+#[derive(Debug, PartialEq, Clone)]
+pub struct Line {
+    pub type_id: i32,
+    pub destroyed: i32,
+    pub dropped: i32,
+}
+impl Line {
+    pub fn from_api(items: &Option<Vec<api::Item>>) -> Vec<Self> {
+        let mut lines = Vec::new();
+        if let Some(items) = items {
+            for item in items {
+                lines.push(Self::new(item.item_type_id, item.quantity_destroyed, item.quantity_dropped));
+                lines.append(&mut Self::from_api(&item.items));
+            }
+        }
+        lines
+    }
+
+    pub fn from_named(items: &Option<Vec<models::item::ItemNamed>>) -> Vec<Self> {
+        items.as_ref()
+            .map(|items| items.iter().map(|item| Self::new(item.item_type_id, item.quantity_destroyed, item.quantity_dropped)).collect())
+            .unwrap_or_default()
+    }
+
+    fn new(type_id: i32, destroyed: Option<i32>, dropped: Option<i32>) -> Self {
+        Self { type_id, destroyed: destroyed.unwrap_or_default(), dropped: dropped.unwrap_or_default() }
+    }
+}
+
+/// Value of the killmail, the ship hull is destroyed always
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Value {
+    pub ship: u64,
+    pub destroyed: u64,
+    pub dropped: u64,
+}
+impl Value {
+    pub fn total(&self) -> u64 {
+        self.ship + self.destroyed + self.dropped
+    }
+}
+
+/// Values the hull and the items, the unknown prices count as zero
+pub fn evaluate<P>(ship_id: Option<i32>, lines: &Vec<Line>, price: &P) -> Value
+    where P: Fn(&i32) -> Option<f32>
+{
+    let mut value = Value::default();
+    if let Some(ship_id) = ship_id {
+        value.ship = price(&ship_id).unwrap_or_default().max(0.0) as u64;
+    }
+    for line in lines {
+        let price = price(&line.type_id).unwrap_or_default().max(0.0) as f64;
+        value.destroyed += (line.destroyed as f64 * price) as u64;
+        value.dropped += (line.dropped as f64 * price) as u64;
+    }
+    value
+}
+
+/// The snapshot price, the current one is used when the snapshot is missing
+pub fn get_price_at(source: &Source, type_id: &i32, snapshots: &HashMap<i32, Price>) -> Option<f32> {
+    snapshots.get(type_id).and_then(|price| source.get_snapshot(price)).or_else(|| source.get_price(type_id))
+}
+
+/// Values by the snapshots nearest to the kill date
+pub fn evaluate_at(ship_id: Option<i32>, lines: &Vec<Line>, source: &Source, snapshots: &HashMap<i32, Price>) -> Value {
+    let missing: Vec<i32> = lines.iter().map(|line| line.type_id).chain(ship_id)
+        .filter(|id| snapshots.get(id).and_then(|price| source.get_snapshot(price)).is_none())
+        .collect();
+    source.preload(&missing);
+    evaluate(ship_id, lines, &|id| get_price_at(source, id, snapshots))
+}
+
+/// Values the killmail by the source at the current prices
+pub fn get_value(killmail: &api::Killmail, source: &Source) -> Value {
+    let lines = Line::from_api(&killmail.victim.items);
+    let ids: Vec<i32> = lines.iter().map(|line| line.type_id).chain(Some(killmail.victim.ship_type_id)).collect();
+    source.preload(&ids);
+    evaluate(Some(killmail.victim.ship_type_id), &lines, &|id| source.get_price(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn order(location_id: i64, is_buy_order: bool, price: f32) -> api::Order {
+        api::Order {
+            order_id: 1,
+            type_id: 34,
+            location_id,
+            system_id: JITA.system_id,
+            volume_total: 100,
+            volume_remain: 100,
+            min_volume: 1,
+            price,
+            is_buy_order,
+            duration: 90,
+            issued: Utc::now(),
+            range: String::from("station"),
+        }
+    }
+
+    #[test]
+    fn source_names() {
+        for source in Source::get_all() {
+            assert_eq!(Some(source), Source::from_name(&source.get_key()));
+        }
+        assert_eq!(Some(Source::Sell(JITA)), Source::from_name("jita-sell"));
+        assert_eq!(Some(Source::Buy(AMARR)), Source::from_name("amarr-buy"));
+        assert_eq!(None, Source::from_name("jita"));
+        assert_eq!(None, Source::from_name("perimeter-sell"));
+        assert_eq!(Source::Average, Source::default());
+    }
+
+    #[test]
+    fn best_prices() {
+        let orders = vec![
+            order(JITA.location_id, true, 4.0),
+            order(JITA.location_id, true, 5.0),
+            order(JITA.location_id, false, 6.0),
+            order(JITA.location_id, false, 7.0),
+            order(1, true, 5.5),
+            order(1, false, 5.7),
+        ];
+        assert_eq!(Some(5.0), best_price(&orders, &JITA.location_id, true));
+        assert_eq!(Some(6.0), best_price(&orders, &JITA.location_id, false));
+        assert_eq!(None, best_price(&orders, &2, true));
+    }
+
+    fn price(type_id: i32, date: &str, average: f32) -> Price {
+        Price {
+            type_id,
+            price_date: Date::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            average: Some(average),
+            adjusted: None,
+            buy: Some(average - 1.0),
+            sell: Some(average + 1.0),
+        }
+    }
+
+    #[test]
+    fn snapshot_prices() {
+        let snapshot = price(1, "2019-11-01", 10.0);
+        assert_eq!(Some(10.0), Source::Average.get_snapshot(&snapshot));
+        assert_eq!(None, Source::Adjusted.get_snapshot(&snapshot));
+        assert_eq!(Some(9.0), Source::Buy(JITA).get_snapshot(&snapshot));
+        assert_eq!(Some(11.0), Source::Sell(JITA).get_snapshot(&snapshot));
+        assert_eq!(None, Source::Sell(AMARR).get_snapshot(&snapshot));
+    }
+
+    #[test]
+    fn nearest_prices() {
+        let date = Date::parse_from_str("2019-11-10", "%Y-%m-%d").unwrap();
+        let prices = vec![
+            price(1, "2019-09-01", 0.5),
+            price(1, "2019-11-01", 1.0),
+            price(1, "2019-11-09", 9.0),
+            price(1, "2019-11-11", 11.0),
+            price(2, "2019-11-20", 20.0),
+            price(2, "2019-11-12", 12.0),
+        ];
+        let found = nearest(&prices, &date);
+        assert_eq!(2, found.len());
+        assert_eq!(Some(9.0), found[&1].average);
+        assert_eq!(Some(12.0), found[&2].average);
+        assert!(nearest(&prices, &Date::parse_from_str("2019-08-01", "%Y-%m-%d").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn hub_snapshot() {
+        let date = Date::parse_from_str("2019-11-10", "%Y-%m-%d").unwrap();
+        let mut prices = api::price::Prices { items: HashMap::new() };
+        prices.items.insert(34, api::price::Price { adjusted: Some(4.5), average: Some(5.0) });
+        prices.items.insert(35, api::price::Price { adjusted: None, average: Some(9.0) });
+        let mut other = order(JITA.location_id, false, 20.0);
+        other.type_id = 36;
+        let orders = vec![order(JITA.location_id, true, 4.0), order(JITA.location_id, false, 6.0), order(1, false, 5.0), other];
+        let snapshot = get_snapshot(&date, &prices, &orders);
+        assert_eq!(vec![34, 35, 36], snapshot.iter().map(|price| price.type_id).collect::<Vec<i32>>());
+        assert_eq!((Some(4.0), Some(6.0)), (snapshot[0].buy, snapshot[0].sell));
+        assert_eq!((None, None), (snapshot[1].buy, snapshot[1].sell));
+        assert_eq!((None, Some(20.0)), (snapshot[2].buy, snapshot[2].sell));
+        assert!(snapshot.iter().all(|price| price.price_date == date));
+    }
+
+    #[test]
+    fn values() {
+        let items = Some(vec![
+            api::Item { item_type_id: 2, quantity_destroyed: Some(3), ..Default::default() },
+            api::Item {
+                item_type_id: 3,
+                quantity_dropped: Some(2),
+                items: Some(vec![api::Item { item_type_id: 2, quantity_dropped: Some(1), ..Default::default() }]),
+                ..Default::default()
+            },
+            api::Item { item_type_id: 4, quantity_dropped: Some(10), ..Default::default() },
+        ]);
+        let lines = Line::from_api(&items);
+        assert_eq!(4, lines.len());
+        let price = |id: &i32| match id {
+            1 => Some(1000.0),
+            2 => Some(10.0),
+            3 => Some(100.0),
+            _ => None
+        };
+        let value = evaluate(Some(1), &lines, &price);
+        assert_eq!(1000, value.ship);
+        assert_eq!(30, value.destroyed);
+        assert_eq!(210, value.dropped);
+        assert_eq!(1240, value.total());
+    }
+}
@@ -0,0 +1,442 @@
+use crate::api;
+use crate::api::gw;
+use crate::models::DateTime;
+
+use crossbeam_utils::thread::scope;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Deliveries are dropped after that many failed attempts
+pub const MAX_ATTEMPTS: u32 = 5;
+/// The first retry delay, doubled on the each next attempt
+pub const RETRY_DELAY: Duration = Duration::from_secs(5);
+/// The new deliveries are dropped while the queue is that long
+pub const MAX_PENDING: usize = 1000;
+/// Deliveries posted in parallel by the single process call
+pub const MAX_BATCH: usize = 8;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+fn default_rate() -> usize {
+    30
+}
+
+/// Payload sent to the sink
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// The notification as is
+    Json,
+    /// Discord embed, the text field makes it readable by Slack as well
+    Embed,
+}
+
+/// Empty filter accepts everything
+#[derive(Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct Filter {
+    /// Systems, constellations or regions
+    pub areas: Vec<i32>,
+    /// Characters, corporations, alliances or factions on either side
+    pub actors: Vec<i32>,
+    pub min_value: Option<u64>,
+    /// Only killmails matched by the alert rules
+    pub alerts_only: bool,
+}
+impl Filter {
+    pub fn accepts(&self, notification: &Notification) -> bool {
+        if self.alerts_only && notification.rules.is_empty() {
+            return false;
+        }
+        if !self.areas.is_empty() && !self.areas.iter().any(|id| notification.in_area(id)) {
+            return false;
+        }
+        if !self.actors.is_empty() && !self.actors.iter().any(|id| notification.actors.contains(id)) {
+            return false;
+        }
+        self.min_value.map(|min| notification.value >= min).unwrap_or(true)
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct Sink {
+    pub name: String,
+    pub url: String,
+    pub format: Format,
+    #[serde(default)]
+    pub filter: Filter,
+    /// Deliveries per minute
+    #[serde(default = "default_rate")]
+    pub rate: usize,
+}
+impl Sink {
+    /// Sinks are described by the JSON array in the file
+    pub fn load(path: &str) -> Vec<Self> {
+        match std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string())) {
+            Ok(sinks) => sinks,
+            Err(e) => {
+                warn!("was not able to load webhooks from {}: {}", path, e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Saved killmail with the alert rules it matched
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct Notification {
+    pub killmail_id: i32,
+    pub killmail_time: DateTime,
+    pub system_id: i32,
+    pub system_name: Option<String>,
+    pub constellation_id: Option<i32>,
+    pub region_id: Option<i32>,
+    pub ship_id: i32,
+    pub ship_name: Option<String>,
+    /// The character or the corporation of the victim
+    pub victim_name: Option<String>,
+    pub value: u64,
+    pub actors: Vec<i32>,
+    pub rules: Vec<String>,
+}
+impl Notification {
+    /// The area ids, the names and the value are resolved by the caller
+    pub fn new(killmail: &api::Killmail, value: u64, rules: Vec<String>) -> Self {
+        let victim = &killmail.victim;
+        let mut actors: Vec<i32> = vec![victim.character_id, victim.corporation_id, victim.alliance_id, victim.faction_id]
+            .into_iter()
+            .chain(killmail.attackers.iter().flat_map(|a| vec![a.character_id, a.corporation_id, a.alliance_id, a.faction_id]))
+            .flatten()
+            .collect();
+        actors.sort();
+        actors.dedup();
+        Self {
+            killmail_id: killmail.killmail_id,
+            killmail_time: killmail.killmail_time.naive_utc(),
+            system_id: killmail.solar_system_id,
+            system_name: None,
+            constellation_id: None,
+            region_id: None,
+            ship_id: victim.ship_type_id,
+            ship_name: None,
+            victim_name: None,
+            value,
+            actors,
+            rules,
+        }
+    }
+
+    pub fn in_area(&self, id: &i32) -> bool {
+        self.system_id == *id || self.constellation_id == Some(*id) || self.region_id == Some(*id)
+    }
+
+    pub fn get_url(&self) -> String {
+        format!("https://zkillboard.com/kill/{}/", self.killmail_id)
+    }
+
+    fn get_title(&self) -> String {
+        let ship = self.ship_name.clone().unwrap_or(self.ship_id.to_string());
+        let system = self.system_name.clone().unwrap_or(self.system_id.to_string());
+        format!("{} destroyed in {}", ship, system)
+    }
+
+    pub fn get_payload(&self, format: Format) -> String {
+        match format {
+            Format::Json => serde_json::to_string(self).ok().unwrap_or_default(),
+            Format::Embed => {
+                let title = self.get_title();
+                let mut description = format!("Value: {} ISK", self.value);
+                if !self.rules.is_empty() {
+                    description += &format!("\nRules: {}", self.rules.join(", "));
+                }
+                let payload = serde_json::json!({
+                    "content": title,
+                    "text": format!("<{}|{}>\n{}", self.get_url(), title, description),
+                    "embeds": [{
+                        "title": title,
+                        "url": self.get_url(),
+                        "description": description,
+                        "timestamp": format!("{}Z", self.killmail_time.format("%Y-%m-%dT%H:%M:%S")),
+                    }]
+                });
+                payload.to_string()
+            }
+        }
+    }
+}
+
+/// Sliding window of the recent deliveries
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    limit: usize,
+    sent: VecDeque<Instant>,
+}
+impl RateLimit {
+    pub fn new(limit: usize) -> Self {
+        Self { limit, sent: VecDeque::new() }
+    }
+
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        while self.sent.front().map(|time| now.duration_since(*time) >= RATE_WINDOW).unwrap_or(false) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() < self.limit {
+            self.sent.push_back(now);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Delivery {
+    sink: usize,
+    body: String,
+    attempts: u32,
+    due: Instant,
+}
+
+/// Server errors and throttling are worth to retry, the rest will fail again
+fn is_retriable(code: u32) -> bool {
+    code == 429 || code >= 500
+}
+
+/// Retry queue with the per-sink rate limits
+pub struct Dispatcher {
+    sinks: Vec<Sink>,
+    limits: Vec<RateLimit>,
+    queue: VecDeque<Delivery>,
+}
+impl Dispatcher {
+    pub fn new(sinks: Vec<Sink>) -> Self {
+        let limits = sinks.iter().map(|sink| RateLimit::new(sink.rate)).collect();
+        Self { sinks, limits, queue: VecDeque::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Enqueues the notification for the each sink accepting it, drops it when the queue is full
+    pub fn dispatch(&mut self, notification: &Notification, now: Instant) {
+        for (index, sink) in self.sinks.iter().enumerate() {
+            if self.queue.len() >= MAX_PENDING {
+                warn!("dropped killmail {} for webhook {}, {} deliveries are pending", notification.killmail_id, sink.name, self.queue.len());
+            } else if sink.filter.accepts(notification) {
+                self.queue.push_back(Delivery {
+                    sink: index,
+                    body: notification.get_payload(sink.format),
+                    attempts: 0,
+                    due: now,
+                });
+            }
+        }
+    }
+
+    /// Posts the batch of the due deliveries in parallel, returns the count of the delivered ones
+    pub fn process(&mut self, now: Instant) -> usize {
+        let mut due = Vec::new();
+        let mut postponed = VecDeque::new();
+        while let Some(delivery) = self.queue.pop_front() {
+            if delivery.due > now || due.len() >= MAX_BATCH || !self.limits[delivery.sink].try_acquire(now) {
+                postponed.push_back(delivery);
+            } else {
+                due.push(delivery);
+            }
+        }
+        let sinks = &self.sinks;
+        let results: Vec<Result<u32, String>> = scope(|scope| {
+            let handles: Vec<_> = due.iter()
+                .map(|delivery| scope.spawn(move |_| gw::post_json(&sinks[delivery.sink].url, &delivery.body)))
+                .collect();
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(String::from("webhook delivery panicked"))))
+                .collect()
+        }).unwrap_or_default();
+        let mut delivered = 0;
+        for (mut delivery, result) in due.into_iter().zip(results) {
+            let sink = &self.sinks[delivery.sink];
+            delivery.attempts += 1;
+            match result {
+                Ok(code) if (200..300).contains(&code) => {
+                    delivered += 1;
+                },
+                result => {
+                    let retriable = result.as_ref().map(|code| is_retriable(*code)).unwrap_or(true);
+                    if retriable && delivery.attempts < MAX_ATTEMPTS {
+                        delivery.due = now + RETRY_DELAY * 2_u32.pow(delivery.attempts - 1);
+                        postponed.push_back(delivery);
+                    } else {
+                        warn!("was not able to deliver to webhook {}: {:?}", sink.name, result);
+                    }
+                }
+            }
+        }
+        self.queue = postponed;
+        delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Answers the requests with the given codes and sends the received bodies back
+    fn stub(codes: Vec<u32>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (code, stream) in codes.into_iter().zip(listener.incoming()) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                loop {
+                    let size = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..size]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(position) = text.find("\r\n\r\n") {
+                        let length = text.lines()
+                            .find(|line| line.to_lowercase().starts_with("content-length:"))
+                            .and_then(|line| line[15..].trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if request.len() >= position + 4 + length {
+                            sender.send(text[position + 4..].to_string()).unwrap();
+                            break;
+                        }
+                    }
+                    if size == 0 {
+                        break;
+                    }
+                }
+                let response = format!("HTTP/1.1 {} Stub\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", code);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    fn sink(url: &str, format: Format, filter: Filter, rate: usize) -> Sink {
+        Sink { name: String::from("test"), url: String::from(url), format, filter, rate }
+    }
+
+    fn notification() -> Notification {
+        Notification {
+            killmail_id: 78146996,
+            killmail_time: chrono::NaiveDate::from_ymd(2019, 10, 1).and_hms(12, 0, 0),
+            system_id: 30045352,
+            system_name: Some(String::from("Jita")),
+            constellation_id: Some(20000001),
+            region_id: Some(10000001),
+            ship_id: 587,
+            ship_name: Some(String::from("Rifter")),
+            victim_name: None,
+            value: 1_000_000,
+            actors: vec![1, 2, 3],
+            rules: vec![String::from("capitals")],
+        }
+    }
+
+    #[test]
+    fn filters() {
+        let notification = notification();
+        assert!(Filter::default().accepts(&notification));
+        assert!(Filter { areas: vec![10000001], ..Filter::default() }.accepts(&notification));
+        assert!(!Filter { areas: vec![10000002], ..Filter::default() }.accepts(&notification));
+        assert!(Filter { actors: vec![3, 4], ..Filter::default() }.accepts(&notification));
+        assert!(!Filter { actors: vec![4], ..Filter::default() }.accepts(&notification));
+        assert!(!Filter { min_value: Some(2_000_000), ..Filter::default() }.accepts(&notification));
+        assert!(Filter { alerts_only: true, ..Filter::default() }.accepts(&notification));
+        let mut unmatched = notification.clone();
+        unmatched.rules.clear();
+        assert!(!Filter { alerts_only: true, ..Filter::default() }.accepts(&unmatched));
+    }
+
+    #[test]
+    fn embed() {
+        let payload: serde_json::Value = serde_json::from_str(&notification().get_payload(Format::Embed)).unwrap();
+        let embed = &payload["embeds"][0];
+        assert_eq!("https://zkillboard.com/kill/78146996/", embed["url"]);
+        assert_eq!("2019-10-01T12:00:00Z", embed["timestamp"]);
+        assert_eq!("Rifter destroyed in Jita", embed["title"]);
+        assert!(embed["description"].as_str().unwrap().contains("capitals"));
+        assert!(payload["text"].as_str().unwrap().starts_with("<https://zkillboard.com/kill/78146996/|"));
+    }
+
+    #[test]
+    fn sinks() {
+        let json = r#"[{"name": "discord", "url": "http://localhost/hook", "format": "embed", "filter": {"min_value": 100}}]"#;
+        let sinks: Vec<Sink> = serde_json::from_str(json).unwrap();
+        assert_eq!(1, sinks.len());
+        assert_eq!(Format::Embed, sinks[0].format);
+        assert_eq!(Some(100), sinks[0].filter.min_value);
+        assert_eq!(default_rate(), sinks[0].rate);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let now = Instant::now();
+        let mut limit = RateLimit::new(2);
+        assert!(limit.try_acquire(now));
+        assert!(limit.try_acquire(now));
+        assert!(!limit.try_acquire(now));
+        assert!(limit.try_acquire(now + RATE_WINDOW));
+    }
+
+    #[test]
+    fn delivery() {
+        let (url, bodies) = stub(vec![200]);
+        let mut dispatcher = Dispatcher::new(vec![sink(&url, Format::Json, Filter::default(), 10)]);
+        let now = Instant::now();
+        dispatcher.dispatch(&notification(), now);
+        assert_eq!(1, dispatcher.process(now));
+        assert_eq!(0, dispatcher.pending());
+        let body: serde_json::Value = serde_json::from_str(&bodies.recv().unwrap()).unwrap();
+        assert_eq!(78146996, body["killmail_id"]);
+    }
+
+    #[test]
+    fn retry() {
+        let (url, bodies) = stub(vec![500, 200]);
+        let mut dispatcher = Dispatcher::new(vec![sink(&url, Format::Json, Filter::default(), 10)]);
+        let now = Instant::now();
+        dispatcher.dispatch(&notification(), now);
+        assert_eq!(0, dispatcher.process(now));
+        assert_eq!(1, dispatcher.pending());
+        assert_eq!(0, dispatcher.process(now));
+        assert_eq!(1, dispatcher.process(now + RETRY_DELAY));
+        assert_eq!(0, dispatcher.pending());
+        assert_eq!(bodies.recv().unwrap(), bodies.recv().unwrap());
+    }
+
+    #[test]
+    fn throttled() {
+        let (url, _bodies) = stub(vec![200, 200]);
+        let mut dispatcher = Dispatcher::new(vec![sink(&url, Format::Json, Filter::default(), 1)]);
+        let now = Instant::now();
+        dispatcher.dispatch(&notification(), now);
+        dispatcher.dispatch(&notification(), now);
+        assert_eq!(1, dispatcher.process(now));
+        assert_eq!(1, dispatcher.pending());
+        assert_eq!(1, dispatcher.process(now + RATE_WINDOW));
+    }
+
+    #[test]
+    fn bounded() {
+        let mut dispatcher = Dispatcher::new(vec![sink("http://localhost/hook", Format::Json, Filter::default(), 10)]);
+        let now = Instant::now();
+        for _ in 0..MAX_PENDING + 1 {
+            dispatcher.dispatch(&notification(), now);
+        }
+        assert_eq!(MAX_PENDING, dispatcher.pending());
+    }
+}